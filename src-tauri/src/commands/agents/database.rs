@@ -70,6 +70,7 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         "ALTER TABLE agents ADD COLUMN enable_network BOOLEAN DEFAULT 0",
         [],
     );
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN budget TEXT", []);
 
     // Create agent_runs table
     conn.execute(
@@ -87,6 +88,17 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
             process_started_at TEXT,
             created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
             completed_at TEXT,
+            tool_policy TEXT,
+            scheduled_at TEXT,
+            result TEXT,
+            result_is_error BOOLEAN,
+            use_worktree BOOLEAN NOT NULL DEFAULT 0,
+            worktree_path TEXT,
+            worktree_branch TEXT,
+            worktree_base TEXT,
+            worktree_status TEXT,
+            budget TEXT,
+            unattended BOOLEAN NOT NULL DEFAULT 0,
             FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
         )",
         [],
@@ -103,6 +115,36 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         "ALTER TABLE agent_runs ADD COLUMN process_started_at TEXT",
         [],
     );
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN tool_policy TEXT", []);
+    // Before runs were queued, a 'pending' run was one whose spawn failed; the dispatcher
+    // must not start those, so the migration that adds the queue columns fails them
+    if conn
+        .execute("ALTER TABLE agent_runs ADD COLUMN scheduled_at TEXT", [])
+        .is_ok()
+    {
+        conn.execute(
+            "UPDATE agent_runs SET status = 'failed', completed_at = COALESCE(completed_at, CURRENT_TIMESTAMP) WHERE status = 'pending'",
+            [],
+        )?;
+    }
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE agent_runs ADD COLUMN result_is_error BOOLEAN",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE agent_runs ADD COLUMN use_worktree BOOLEAN NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_path TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_branch TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_base TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_status TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN budget TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE agent_runs ADD COLUMN unattended BOOLEAN NOT NULL DEFAULT 0",
+        [],
+    );
 
     // Drop old columns that are no longer needed (data is now read from JSONL files)
     // Note: SQLite doesn't support DROP COLUMN, so we'll ignore errors for existing columns
@@ -117,6 +159,63 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     );
 
+    // Create agent_schedules table for recurring runs
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            project_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT,
+            cron_expr TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            last_run_at TEXT,
+            next_run_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Create agent_pipelines table (steps are stored as a JSON array of PipelineStep)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_pipelines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT,
+            steps TEXT NOT NULL,
+            stop_on_failure BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Create agent_pipeline_runs table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_pipeline_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id INTEGER NOT NULL,
+            pipeline_name TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            current_step INTEGER NOT NULL DEFAULT 0,
+            run_ids TEXT NOT NULL DEFAULT '[]',
+            error TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            completed_at TEXT,
+            FOREIGN KEY (pipeline_id) REFERENCES agent_pipelines(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Pipelines are driven by an in-process task, so ones interrupted by a restart can't resume
+    let _ = conn.execute(
+        "UPDATE agent_pipeline_runs SET status = 'interrupted', completed_at = CURRENT_TIMESTAMP WHERE status = 'running'",
+        [],
+    );
+
     // Create trigger to update the updated_at timestamp
     conn.execute(
         "CREATE TRIGGER IF NOT EXISTS update_agent_timestamp
@@ -379,43 +478,23 @@ pub async fn list_agent_runs(
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     let query = if agent_id.is_some() {
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
-         FROM agent_runs WHERE agent_id = ?1 ORDER BY created_at DESC"
+        format!(
+            "SELECT {} FROM agent_runs WHERE agent_id = ?1 ORDER BY created_at DESC",
+            AgentRun::SELECT_COLUMNS
+        )
     } else {
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
-         FROM agent_runs ORDER BY created_at DESC"
+        format!(
+            "SELECT {} FROM agent_runs ORDER BY created_at DESC",
+            AgentRun::SELECT_COLUMNS
+        )
     };
 
-    let mut stmt = conn.prepare(query).map_err(|e| e.to_string())?;
-
-    let run_mapper = |row: &rusqlite::Row| -> rusqlite::Result<AgentRun> {
-        Ok(AgentRun {
-            id: Some(row.get(0)?),
-            agent_id: row.get(1)?,
-            agent_name: row.get(2)?,
-            agent_icon: row.get(3)?,
-            task: row.get(4)?,
-            model: row.get(5)?,
-            project_path: row.get(6)?,
-            session_id: row.get(7)?,
-            status: row
-                .get::<_, String>(8)
-                .unwrap_or_else(|_| "pending".to_string()),
-            pid: row
-                .get::<_, Option<i64>>(9)
-                .ok()
-                .flatten()
-                .map(|p| p as u32),
-            process_started_at: row.get(10)?,
-            created_at: row.get(11)?,
-            completed_at: row.get(12)?,
-        })
-    };
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
 
     let runs = if let Some(aid) = agent_id {
-        stmt.query_map(params![aid], run_mapper)
+        stmt.query_map(params![aid], AgentRun::from_row)
     } else {
-        stmt.query_map(params![], run_mapper)
+        stmt.query_map(params![], AgentRun::from_row)
     }
     .map_err(|e| e.to_string())?
     .collect::<Result<Vec<_>, _>>()
@@ -431,26 +510,12 @@ pub async fn get_agent_run(db: State<'_, AgentDb>, id: i64) -> Result<AgentRun,
 
     let run = conn
         .query_row(
-            "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
-             FROM agent_runs WHERE id = ?1",
+            &format!(
+                "SELECT {} FROM agent_runs WHERE id = ?1",
+                AgentRun::SELECT_COLUMNS
+            ),
             params![id],
-            |row| {
-                Ok(AgentRun {
-                    id: Some(row.get(0)?),
-                    agent_id: row.get(1)?,
-                    agent_name: row.get(2)?,
-                    agent_icon: row.get(3)?,
-                    task: row.get(4)?,
-                    model: row.get(5)?,
-                    project_path: row.get(6)?,
-                    session_id: row.get(7)?,
-                    status: row.get::<_, String>(8).unwrap_or_else(|_| "pending".to_string()),
-                    pid: row.get::<_, Option<i64>>(9).ok().flatten().map(|p| p as u32),
-                    process_started_at: row.get(10)?,
-                    created_at: row.get(11)?,
-                    completed_at: row.get(12)?,
-                })
-            },
+            AgentRun::from_row,
         )
         .map_err(|e| e.to_string())?;
 
@@ -461,10 +526,42 @@ pub async fn get_agent_run(db: State<'_, AgentDb>, id: i64) -> Result<AgentRun,
 #[tauri::command]
 pub async fn cleanup_finished_processes(db: State<'_, AgentDb>) -> Result<Vec<i64>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    mark_finished_runs(&conn)
+}
+
+/// Mark `running` agent runs whose process no longer exists as completed
+pub(crate) fn mark_finished_runs(conn: &Connection) -> Result<Vec<i64>, String> {
+    let mut cleaned_up = Vec::new();
+
+    // A run flipped to 'running' without a PID never got a process; free its slot
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id FROM agent_runs WHERE status = 'running' AND (pid IS NULL OR pid <= 0)",
+            )
+            .map_err(|e| e.to_string())?;
+        let orphaned = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        for run_id in orphaned {
+            conn.execute(
+                "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+                params![run_id],
+            )
+            .map_err(|e| e.to_string())?;
+            log::info!(
+                "Marked agent run {} as failed (no process was started)",
+                run_id
+            );
+            cleaned_up.push(run_id);
+        }
+    }
 
     // Get all running processes
     let mut stmt = conn
-        .prepare("SELECT id, pid FROM agent_runs WHERE status = 'running' AND pid IS NOT NULL")
+        .prepare("SELECT id, pid FROM agent_runs WHERE status = 'running' AND pid > 0")
         .map_err(|e| e.to_string())?;
 
     let running_processes = stmt
@@ -475,8 +572,6 @@ pub async fn cleanup_finished_processes(db: State<'_, AgentDb>) -> Result<Vec<i6
 
     drop(stmt);
 
-    let mut cleaned_up = Vec::new();
-
     for (run_id, pid) in running_processes {
         // Check if the process is still running
         let is_running = if cfg!(target_os = "windows") {
@@ -1,14 +1,18 @@
 use log::{debug, error, info, warn};
 use rusqlite::{params, Connection};
-use serde_json::Value as JsonValue;
 use std::process::Stdio;
 use std::sync::Mutex;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::io::{AsyncBufReadExt, BufReader as TokioBufReader};
 use tokio::process::Command;
 
 use super::database::get_agent;
+use super::permissions::AgentToolPolicy;
 use super::types::AgentDb;
+use crate::commands::budget::{self, BudgetEvent, BudgetExceeded, BudgetTracker, RunBudget};
+use crate::commands::claude::ClaudeRunOptions;
+use crate::commands::permission_prompt::{permission_prompt_for, PermissionPrompt};
+use crate::stream_json::{parse_line, StreamMessage};
 
 /// Finds the full path to the claude binary
 /// This is necessary because macOS apps have a limited PATH environment
@@ -25,15 +29,12 @@ fn create_command_with_env(program: &str) -> Command {
 /// Creates a system binary command for agent execution
 fn create_agent_system_command(
     claude_path: &str,
-    args: Vec<String>,
+    options: &ClaudeRunOptions,
     project_path: &str,
 ) -> Command {
     let mut cmd = create_command_with_env(claude_path);
 
-    // Add all arguments
-    for arg in args {
-        cmd.arg(arg);
-    }
+    cmd.args(options.to_args()).envs(&options.env);
 
     cmd.current_dir(project_path)
         .stdin(Stdio::null())
@@ -50,6 +51,10 @@ struct ProcessIoState {
     first_output: std::sync::Arc<std::sync::atomic::AtomicBool>,
     first_error: std::sync::Arc<std::sync::atomic::AtomicBool>,
     start_time: std::time::Instant,
+    /// Kept until the run ends so it can keep asking for tool permissions
+    permission_prompt: Option<PermissionPrompt>,
+    /// Sums the run's usage when it has a budget
+    budget: Option<std::sync::Arc<Mutex<BudgetTracker>>>,
 }
 
 impl ProcessIoState {
@@ -60,20 +65,44 @@ impl ProcessIoState {
             first_output: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
             first_error: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
             start_time: std::time::Instant::now(),
+            permission_prompt: None,
+            budget: None,
         }
     }
 }
 
+/// Record and announce that a run went over its budget, then interrupt it
+fn stop_agent_run_over_budget(
+    app: &AppHandle,
+    run_id: i64,
+    pid: u32,
+    project_path: &str,
+    session_id: String,
+    exceeded: &BudgetExceeded,
+) {
+    warn!("Agent run {} went over budget: {}", run_id, exceeded.reason);
+    let session_id = Some(session_id).filter(|sid| !sid.is_empty());
+    budget::report_budget_exceeded(
+        app,
+        &BudgetEvent::new(project_path, session_id, Some(run_id), exceeded),
+    );
+    let app = app.clone();
+    budget::interrupt_process(pid, move || async move {
+        let registry = app.state::<crate::process::ProcessRegistryState>();
+        registry.0.kill_if_running(run_id).unwrap_or(false)
+    });
+}
+
 /// Creates and spawns the Claude process, returning PID and IO handles
 async fn create_and_spawn_process(
     claude_path: &str,
-    args: Vec<String>,
+    options: &ClaudeRunOptions,
     project_path: &str,
     run_id: i64,
     db: &State<'_, AgentDb>,
 ) -> Result<(tokio::process::Child, u32, std::path::PathBuf), String> {
     // Build the command
-    let mut cmd = create_agent_system_command(claude_path, args, project_path);
+    let mut cmd = create_agent_system_command(claude_path, options, project_path);
 
     // Spawn the process
     info!("🚀 Spawning Claude system process...");
@@ -131,6 +160,8 @@ fn spawn_stdout_reader(
     stdout_reader: TokioBufReader<tokio::process::ChildStdout>,
     app: AppHandle,
     run_id: i64,
+    pid: u32,
+    project_path: String,
     db_path: std::path::PathBuf,
     io_state: &ProcessIoState,
     registry: std::sync::Arc<crate::process::ProcessRegistry>,
@@ -138,6 +169,7 @@ fn spawn_stdout_reader(
     let session_id_clone = io_state.session_id.clone();
     let live_output_clone = io_state.live_output.clone();
     let first_output_clone = io_state.first_output.clone();
+    let budget_clone = io_state.budget.clone();
 
     tokio::spawn(async move {
         info!("📖 Starting to read Claude stdout...");
@@ -171,45 +203,65 @@ fn spawn_stdout_reader(
             // Also store in process registry for cross-session access
             let _ = registry.append_live_output(run_id, &line);
 
+            let message = parse_line(&line);
+
             // Extract session ID from JSONL output
-            if let Ok(json) = serde_json::from_str::<JsonValue>(&line) {
-                // Claude Code uses "session_id" (underscore), not "sessionId"
-                if json.get("type").and_then(|t| t.as_str()) == Some("system")
-                    && json.get("subtype").and_then(|s| s.as_str()) == Some("init")
-                {
-                    if let Some(sid) = json.get("session_id").and_then(|s| s.as_str()) {
-                        if let Ok(mut current_session_id) = session_id_clone.lock() {
-                            if current_session_id.is_empty() {
-                                *current_session_id = sid.to_string();
-                                info!("🔑 Extracted session ID: {}", sid);
-
-                                // Update database immediately with session ID
-                                if let Ok(conn) = Connection::open(&db_path) {
-                                    match conn.execute(
-                                        "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
-                                        params![sid, run_id],
-                                    ) {
-                                        Ok(rows) => {
-                                            if rows > 0 {
-                                                info!("✅ Updated agent run {} with session ID immediately", run_id);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            error!(
-                                                "❌ Failed to update session ID immediately: {}",
-                                                e
-                                            );
+            if let Some(message) = &message {
+                if let Some(sid) = message.init_session_id() {
+                    if let Ok(mut current_session_id) = session_id_clone.lock() {
+                        if current_session_id.is_empty() {
+                            *current_session_id = sid.to_string();
+                            info!("🔑 Extracted session ID: {}", sid);
+
+                            // Update database immediately with session ID
+                            if let Ok(conn) = Connection::open(&db_path) {
+                                match conn.execute(
+                                    "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
+                                    params![sid, run_id],
+                                ) {
+                                    Ok(rows) => {
+                                        if rows > 0 {
+                                            info!("✅ Updated agent run {} with session ID immediately", run_id);
                                         }
                                     }
+                                    Err(e) => {
+                                        error!("❌ Failed to update session ID immediately: {}", e);
+                                    }
                                 }
                             }
                         }
                     }
                 }
+
+                // Persist the final result so pipelines can hand it to the next step
+                if let StreamMessage::Result(result) = message {
+                    let text = result.result.as_deref().unwrap_or_default();
+                    if let Ok(conn) = Connection::open(&db_path) {
+                        if let Err(e) = conn.execute(
+                            "UPDATE agent_runs SET result = ?1, result_is_error = ?2 WHERE id = ?3",
+                            params![text, result.is_error, run_id],
+                        ) {
+                            error!("❌ Failed to store result for agent run {}: {}", run_id, e);
+                        }
+                    }
+                }
             }
 
-            // Emit the line to the frontend with run_id for isolation
+            // Stop the run as soon as it goes over its budget
+            let exceeded = match (&budget_clone, &message) {
+                (Some(tracker), Some(message)) => tracker.lock().unwrap().observe(message),
+                _ => None,
+            };
+            if let Some(exceeded) = exceeded {
+                let session_id = session_id_clone.lock().unwrap().clone();
+                stop_agent_run_over_budget(&app, run_id, pid, &project_path, session_id, &exceeded);
+            }
+
+            // Emit the line to the frontend with run_id for isolation, once raw and once parsed
             let _ = app.emit(&format!("agent-output:{}", run_id), &line);
+            if let Some(message) = &message {
+                let _ = app.emit(&format!("agent-message:{}", run_id), message);
+            }
             // Also emit to the generic event for backward compatibility
             let _ = app.emit("agent-output", &line);
         }
@@ -262,6 +314,73 @@ fn spawn_stderr_reader(
     })
 }
 
+/// Store how a finished run ended; returns whether it completed
+///
+/// A run stopped over budget failed, with the reason as its result. A run that exits
+/// without reporting a result crashed or was killed; a cancelled run keeps its status.
+fn finish_agent_run(
+    conn: &Connection,
+    run_id: i64,
+    session_id: &str,
+    budget_exceeded: Option<&str>,
+) -> bool {
+    info!(
+        "🔄 Updating database with extracted session ID: {}",
+        session_id
+    );
+    if let Some(reason) = budget_exceeded {
+        if let Err(e) = conn.execute(
+            "UPDATE agent_runs SET result = ?1, result_is_error = 1 WHERE id = ?2",
+            params![reason, run_id],
+        ) {
+            error!(
+                "❌ Failed to store budget result for agent run {}: {}",
+                run_id, e
+            );
+        }
+    }
+    if let Err(e) = conn.execute(
+        "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
+        params![session_id, run_id],
+    ) {
+        error!(
+            "❌ Failed to update agent run {} with session ID: {}",
+            run_id, e
+        );
+    }
+    match conn.execute(
+        "UPDATE agent_runs
+         SET status = CASE WHEN ?1 OR result IS NULL THEN 'failed' ELSE 'completed' END,
+             completed_at = CURRENT_TIMESTAMP
+         WHERE id = ?2 AND status = 'running'",
+        params![budget_exceeded.is_some(), run_id],
+    ) {
+        Ok(rows_affected) => {
+            if rows_affected > 0 {
+                info!(
+                    "✅ Successfully finished agent run {} with session ID: {}",
+                    run_id, session_id
+                );
+            } else {
+                warn!(
+                    "⚠️ Agent run {} was no longer running when it finished",
+                    run_id
+                );
+            }
+        }
+        Err(e) => {
+            error!("❌ Failed to finish agent run {}: {}", run_id, e);
+        }
+    }
+    conn.query_row(
+        "SELECT status FROM agent_runs WHERE id = ?1",
+        params![run_id],
+        |row| row.get::<_, String>(0),
+    )
+    .map(|status| status == "completed")
+    .unwrap_or(false)
+}
+
 /// Spawns the process monitoring task
 fn spawn_process_monitor(
     app: AppHandle,
@@ -326,13 +445,14 @@ fn spawn_process_monitor(
                 // Update database
                 if let Ok(conn) = Connection::open(&db_path) {
                     let _ = conn.execute(
-                        "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                        "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
                         params![run_id],
                     );
                 }
 
                 let _ = app.emit("agent-complete", false);
                 let _ = app.emit(&format!("agent-complete:{}", run_id), false);
+                super::queue::notify_dispatcher(&app);
                 return;
             }
 
@@ -343,6 +463,11 @@ fn spawn_process_monitor(
         info!("⏳ Waiting for stdout/stderr reading to complete...");
         let _ = stdout_task.await;
         let _ = stderr_task.await;
+        let budget_exceeded = io_state.budget.as_ref().and_then(|tracker| {
+            let mut tracker = tracker.lock().unwrap();
+            tracker.finish();
+            tracker.exceeded().map(|exceeded| exceeded.reason.clone())
+        });
 
         let duration_ms = io_state.start_time.elapsed().as_millis() as i64;
         info!("⏱️ Process execution took {} ms", duration_ms);
@@ -358,37 +483,29 @@ fn spawn_process_monitor(
         info!("✅ Claude process execution monitoring complete");
 
         // Update the run record with session ID and mark as completed - open a new connection
-        if let Ok(conn) = Connection::open(&db_path) {
-            info!(
-                "🔄 Updating database with extracted session ID: {}",
-                extracted_session_id
-            );
-            match conn.execute(
-                "UPDATE agent_runs SET session_id = ?1, status = 'completed', completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
-                params![extracted_session_id, run_id],
-            ) {
-                Ok(rows_affected) => {
-                    if rows_affected > 0 {
-                        info!("✅ Successfully updated agent run {} with session ID: {}", run_id, extracted_session_id);
-                    } else {
-                        warn!("⚠️ No rows affected when updating agent run {} with session ID", run_id);
-                    }
-                }
-                Err(e) => {
-                    error!("❌ Failed to update agent run {} with session ID: {}", run_id, e);
-                }
+        let success = match Connection::open(&db_path) {
+            Ok(conn) => finish_agent_run(
+                &conn,
+                run_id,
+                &extracted_session_id,
+                budget_exceeded.as_deref(),
+            ),
+            Err(_) => {
+                error!(
+                    "❌ Failed to open database to update session ID for run {}",
+                    run_id
+                );
+                false
             }
-        } else {
-            error!(
-                "❌ Failed to open database to update session ID for run {}",
-                run_id
-            );
-        }
+        };
 
         // Cleanup will be handled by the cleanup_finished_processes function
 
-        let _ = app.emit("agent-complete", true);
-        let _ = app.emit(&format!("agent-complete:{}", run_id), true);
+        let _ = app.emit("agent-complete", success);
+        let _ = app.emit(&format!("agent-complete:{}", run_id), success);
+
+        // A slot just freed up; let the dispatcher start the next queued run
+        super::queue::notify_dispatcher(&app);
     });
 }
 
@@ -399,28 +516,35 @@ async fn spawn_agent_system(
     agent_id: i64,
     agent_name: String,
     claude_path: String,
-    args: Vec<String>,
+    options: ClaudeRunOptions,
     project_path: String,
     task: String,
     execution_model: String,
+    permission_prompt: Option<PermissionPrompt>,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
     // Create and spawn the process
     let (mut child, pid, db_path) =
-        create_and_spawn_process(&claude_path, args, &project_path, run_id, &db).await?;
+        create_and_spawn_process(&claude_path, &options, &project_path, run_id, &db).await?;
 
     // Set up IO handlers
     let (stdout_reader, stderr_reader) = setup_io_handlers(&mut child)?;
 
     // Create shared IO state
-    let io_state = ProcessIoState::new();
+    let mut io_state = ProcessIoState::new();
+    io_state.permission_prompt = permission_prompt;
+    io_state.budget = options
+        .budget
+        .map(|budget| std::sync::Arc::new(Mutex::new(BudgetTracker::new(budget))));
 
     // Spawn stdout reader task
     let stdout_task = spawn_stdout_reader(
         stdout_reader,
         app.clone(),
         run_id,
+        pid,
+        project_path.clone(),
         db_path.clone(),
         &io_state,
         registry.0.clone(),
@@ -445,6 +569,16 @@ async fn spawn_agent_system(
         .map_err(|e| format!("Failed to register process: {}", e))?;
     info!("📋 Registered process in registry");
 
+    // Runs can go over their time budget without sending anything
+    if let Some(tracker) = &io_state.budget {
+        let app = app.clone();
+        let session_id = io_state.session_id.clone();
+        budget::spawn_duration_check(tracker.clone(), move |exceeded| {
+            let session_id = session_id.lock().unwrap().clone();
+            stop_agent_run_over_budget(&app, run_id, pid, &project_path, session_id, &exceeded);
+        });
+    }
+
     // Spawn process monitor task
     spawn_process_monitor(
         app,
@@ -460,6 +594,10 @@ async fn spawn_agent_system(
 }
 
 /// Execute a CC agent with streaming output
+///
+/// The run is queued as `pending` and started by the dispatcher once the concurrency limits
+/// allow (immediately when there is capacity). Pass `scheduled_at` (RFC3339) to delay it.
+/// `budget` replaces the agent's own budget for this run.
 #[tauri::command]
 pub async fn execute_agent(
     app: AppHandle,
@@ -467,14 +605,66 @@ pub async fn execute_agent(
     project_path: String,
     task: String,
     model: Option<String>,
+    scheduled_at: Option<String>,
+    use_worktree: Option<bool>,
+    budget: Option<RunBudget>,
     db: State<'_, AgentDb>,
-    registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
     info!("Executing agent {} with task: {}", agent_id, task);
 
+    let run_id = super::queue::enqueue_agent_run(
+        &db,
+        agent_id,
+        &project_path,
+        &task,
+        model,
+        scheduled_at,
+        use_worktree.unwrap_or(false),
+        budget,
+        false,
+    )
+    .await?;
+    super::queue::notify_dispatcher(&app);
+
+    Ok(run_id)
+}
+
+/// Start a queued run: prepare the project, build the CLI arguments and spawn the process
+pub(crate) async fn start_agent_run(app: &AppHandle, run_id: i64) -> Result<i64, String> {
+    let db = app.state::<AgentDb>();
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+
+    let mut run = super::database::get_agent_run(db.clone(), run_id).await?;
+    info!(
+        "Starting queued agent run {} with task: {}",
+        run_id, run.task
+    );
+
+    // Isolated runs get their own worktree so they can't clash with other runs on the project
+    if run.use_worktree && run.worktree_path.is_none() {
+        let worktree = super::worktree::create_agent_worktree(&run.project_path, run_id)?;
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE agent_runs SET worktree_path = ?1, worktree_branch = ?2, worktree_base = ?3, worktree_status = 'active' WHERE id = ?4",
+            params![worktree.path, worktree.branch, worktree.base, run_id],
+        )
+        .map_err(|e| e.to_string())?;
+        run.worktree_path = Some(worktree.path);
+    }
+
+    let budget: Option<RunBudget> = run
+        .budget
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| format!("Failed to parse run budget: {}", e))?;
+    let agent_id = run.agent_id;
+    let project_path = run.working_dir().to_string();
+    let task = run.task;
+    let execution_model = run.model;
+
     // Get the agent from database
     let agent = get_agent(db.clone(), agent_id).await?;
-    let execution_model = model.unwrap_or(agent.model.clone());
 
     // Create .claude/settings.json with agent hooks if it doesn't exist
     if let Some(hooks_json) = &agent.hooks {
@@ -515,16 +705,21 @@ pub async fn execute_agent(
         }
     }
 
-    // Create a new run record
-    let run_id = {
+    // Turn the agent's permission flags into the tool policy enforced for this run
+    let tool_policy = AgentToolPolicy::for_agent(&agent);
+    let tool_policy_json = serde_json::to_string(&tool_policy)
+        .map_err(|e| format!("Failed to serialize tool policy: {}", e))?;
+    info!("Agent '{}' tool policy: {}", agent.name, tool_policy_json);
+
+    // Record the policy the run is started with
+    {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
         conn.execute(
-            "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![agent_id, agent.name, agent.icon, task, execution_model, project_path, ""],
+            "UPDATE agent_runs SET tool_policy = ?1 WHERE id = ?2",
+            params![tool_policy_json, run_id],
         )
         .map_err(|e| e.to_string())?;
-        conn.last_insert_rowid()
-    };
+    }
 
     // Find Claude binary
     info!("Running agent '{}'", agent.name);
@@ -536,33 +731,123 @@ pub async fn execute_agent(
         }
     };
 
-    // Build arguments
-    let args = vec![
-        "-p".to_string(),
-        task.clone(),
-        "--system-prompt".to_string(),
-        agent.system_prompt.clone(),
-        "--model".to_string(),
-        execution_model.clone(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--verbose".to_string(),
-        "--dangerously-skip-permissions".to_string(),
-    ];
+    let mut options = ClaudeRunOptions::default()
+        .print_prompt(task.clone())
+        .system_prompt(agent.system_prompt.clone())
+        .model(execution_model.clone())
+        .unattended(run.unattended)
+        .budget(budget);
+    // With interactive permissions, tools outside the policy are asked about instead of
+    // denied. Scheduled and pipeline runs have nobody to ask, so the policy alone decides.
+    let permission_prompt = if run.unattended {
+        None
+    } else {
+        permission_prompt_for(app, &project_path)?
+    };
+    if let Some(prompt) = &permission_prompt {
+        prompt.set_run_id(run_id);
+        options = options.permission_prompt(prompt);
+    }
+    let options = tool_policy.apply(options);
 
     // Always use system binary execution (sidecar removed)
     spawn_agent_system(
-        app,
+        app.clone(),
         run_id,
         agent_id,
         agent.name.clone(),
         claude_path,
-        args,
+        options,
         project_path,
         task,
         execution_model,
+        permission_prompt,
         db,
         registry,
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE agent_runs (
+                id INTEGER PRIMARY KEY,
+                session_id TEXT NOT NULL DEFAULT '',
+                status TEXT NOT NULL,
+                result TEXT,
+                result_is_error BOOLEAN,
+                completed_at TEXT
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn add_run(conn: &Connection, id: i64, status: &str, result: Option<&str>) {
+        conn.execute(
+            "INSERT INTO agent_runs (id, status, result) VALUES (?1, ?2, ?3)",
+            params![id, status, result],
+        )
+        .unwrap();
+    }
+
+    fn run_state(conn: &Connection, id: i64) -> (String, Option<String>, String) {
+        conn.query_row(
+            "SELECT status, result, session_id FROM agent_runs WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finished_runs_record_their_outcome() {
+        let conn = runs_db();
+        add_run(&conn, 1, "running", Some("done"));
+        add_run(&conn, 2, "running", None);
+        add_run(&conn, 3, "cancelled", Some("partial"));
+
+        assert!(finish_agent_run(&conn, 1, "s1", None));
+        assert_eq!(
+            run_state(&conn, 1),
+            (
+                "completed".to_string(),
+                Some("done".to_string()),
+                "s1".to_string()
+            )
+        );
+
+        // Crashed without a result
+        assert!(!finish_agent_run(&conn, 2, "s2", None));
+        assert_eq!(run_state(&conn, 2).0, "failed");
+
+        assert!(!finish_agent_run(&conn, 3, "s3", None));
+        assert_eq!(run_state(&conn, 3).0, "cancelled");
+    }
+
+    #[test]
+    fn runs_over_budget_fail_with_the_reason() {
+        let conn = runs_db();
+        add_run(&conn, 1, "running", Some("done"));
+
+        let reason = "Cost $0.7500 exceeded the $0.50 budget";
+        assert!(!finish_agent_run(&conn, 1, "s1", Some(reason)));
+        let (status, result, _) = run_state(&conn, 1);
+        assert_eq!(status, "failed");
+        assert_eq!(result.as_deref(), Some(reason));
+        let is_error: bool = conn
+            .query_row(
+                "SELECT result_is_error FROM agent_runs WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(is_error);
+    }
+}
@@ -6,22 +6,36 @@
 //! - `types`: Type definitions (Agent, AgentRun, AgentRunMetrics, etc.)
 //! - `database`: Database operations (init, CRUD)
 //! - `execution`: Agent execution and process management
+//! - `permissions`: Tool policy derived from an agent's permission flags
+//! - `pipeline`: Multi-step pipelines that chain agents together
+//! - `queue`: Run queue, concurrency limits and the dispatcher
+//! - `schedule`: Cron-style recurring runs
 //! - `session`: Session management and JSONL reading
+//! - `worktree`: Git worktree isolation for agent runs
 //! - `import_export`: Import/export and GitHub integration
 
 // Submodules
 pub mod database;
 pub mod execution;
 pub mod import_export;
+pub mod permissions;
+pub mod pipeline;
+pub mod queue;
+pub mod schedule;
 pub mod session;
 pub mod types;
+pub mod worktree;
 
 // Re-export types
 pub use types::{
-    Agent, AgentData, AgentDb, AgentExport, AgentRun, AgentRunMetrics, AgentRunWithMetrics,
-    GitHubAgentFile,
+    Agent, AgentData, AgentDb, AgentExport, AgentPipeline, AgentPipelineRun,
+    AgentPipelineRunWithMetrics, AgentQueueConfig, AgentRun, AgentRunMetrics, AgentRunWithMetrics,
+    AgentSchedule, GitHubAgentFile, PipelineStep,
 };
 
+// Re-export permission types
+pub use permissions::AgentToolPolicy;
+
 // Re-export database functions
 pub use database::{
     cleanup_finished_processes, create_agent, delete_agent, get_agent, get_agent_run,
@@ -32,6 +46,22 @@ pub use database::{
 // Re-export execution functions
 pub use execution::execute_agent;
 
+// Re-export queue and schedule functions
+pub use queue::{
+    cancel_queued_agent_run, get_agent_queue_config, list_agent_queue, set_agent_queue_config,
+    AgentQueueState,
+};
+pub use schedule::{
+    create_agent_schedule, delete_agent_schedule, list_agent_schedules, set_agent_schedule_enabled,
+};
+
+// Re-export pipeline functions
+pub use pipeline::{
+    cancel_agent_pipeline_run, delete_agent_pipeline, execute_agent_pipeline, get_agent_pipeline,
+    get_agent_pipeline_run_with_metrics, list_agent_pipeline_runs, list_agent_pipelines,
+    save_agent_pipeline,
+};
+
 // Re-export session functions
 pub use session::{
     get_agent_run_with_metrics, get_agent_run_with_real_time_metrics, get_live_session_output,
@@ -39,6 +69,12 @@ pub use session::{
     list_running_sessions, load_agent_session_history, read_session_jsonl, stream_session_output,
 };
 
+// Re-export worktree functions
+pub use worktree::{
+    cherry_pick_agent_worktree, discard_agent_worktree, get_agent_worktree_diff,
+    merge_agent_worktree,
+};
+
 // Re-export import/export functions
 pub use import_export::{
     export_agent, export_agent_to_file, fetch_github_agent_content, fetch_github_agents,
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::Agent;
+use crate::commands::claude::{ClaudeRunOptions, PermissionMode};
+
+/// Tools that only read the project
+const FILE_READ_TOOLS: &[&str] = &["Read", "Glob", "Grep", "LS", "NotebookRead"];
+
+/// Tools that modify files in the project
+const FILE_WRITE_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Tools that reach the network
+const NETWORK_TOOLS: &[&str] = &["WebFetch", "WebSearch"];
+
+/// Tools that don't touch files or the network and are always available
+const ALWAYS_ALLOWED_TOOLS: &[&str] = &["Task", "TodoWrite", "exit_plan_mode"];
+
+/// Read-only shell commands granted to agents that can read but not get full shell access
+const READ_ONLY_BASH_TOOLS: &[&str] = &[
+    "Bash(git status:*)",
+    "Bash(git diff:*)",
+    "Bash(git log:*)",
+    "Bash(git show:*)",
+];
+
+/// Effective tool policy for an agent run, derived from the agent's permission flags
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AgentToolPolicy {
+    pub enable_file_read: bool,
+    pub enable_file_write: bool,
+    pub enable_network: bool,
+    /// True when the agent has every permission, in which case the CLI skips prompts entirely
+    pub skip_permissions: bool,
+    pub allowed_tools: Vec<String>,
+    pub disallowed_tools: Vec<String>,
+}
+
+impl AgentToolPolicy {
+    /// Build the policy for an agent
+    pub fn for_agent(agent: &Agent) -> Self {
+        Self::from_flags(
+            agent.enable_file_read,
+            agent.enable_file_write,
+            agent.enable_network,
+        )
+    }
+
+    /// Build the policy from the three permission flags
+    pub fn from_flags(
+        enable_file_read: bool,
+        enable_file_write: bool,
+        enable_network: bool,
+    ) -> Self {
+        let mut allowed_tools: Vec<String> =
+            ALWAYS_ALLOWED_TOOLS.iter().map(|t| t.to_string()).collect();
+        let mut disallowed_tools = Vec::new();
+
+        for (enabled, tools) in [
+            (enable_file_read, FILE_READ_TOOLS),
+            (enable_file_write, FILE_WRITE_TOOLS),
+            (enable_network, NETWORK_TOOLS),
+        ] {
+            let target = if enabled {
+                &mut allowed_tools
+            } else {
+                &mut disallowed_tools
+            };
+            target.extend(tools.iter().map(|t| t.to_string()));
+        }
+
+        // An unrestricted shell can write files and reach the network, so it is only granted
+        // when both are allowed. Readers still get a handful of read-only git commands.
+        if enable_file_write && enable_network {
+            allowed_tools.push("Bash".to_string());
+        } else if enable_file_read {
+            allowed_tools.extend(READ_ONLY_BASH_TOOLS.iter().map(|t| t.to_string()));
+        } else {
+            disallowed_tools.push("Bash".to_string());
+        }
+
+        Self {
+            enable_file_read,
+            enable_file_write,
+            enable_network,
+            skip_permissions: enable_file_read && enable_file_write && enable_network,
+            allowed_tools,
+            disallowed_tools,
+        }
+    }
+
+    /// Run options that enforce this policy
+    ///
+    /// Restricted agents run without `--dangerously-skip-permissions`, so in non-interactive
+    /// mode any tool outside `--allowedTools` is denied rather than prompted for. With a
+    /// permission prompt, full-access agents keep their tools allowed and are asked about
+    /// the rest instead of skipping checks.
+    pub fn apply(&self, options: ClaudeRunOptions) -> ClaudeRunOptions {
+        if self.skip_permissions && options.permission_prompt.is_none() {
+            return options.permission_mode(PermissionMode::SkipChecks);
+        }
+        options.tools(&self.allowed_tools, &self.disallowed_tools)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_access_skips_permissions() {
+        let policy = AgentToolPolicy::from_flags(true, true, true);
+        assert!(policy.skip_permissions);
+        assert!(policy.disallowed_tools.is_empty());
+        let args = policy.apply(ClaudeRunOptions::default()).to_args();
+        assert!(args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(!args.contains(&"--allowedTools".to_string()));
+    }
+
+    #[test]
+    fn test_full_access_asks_when_prompted() {
+        let policy = AgentToolPolicy::from_flags(true, true, true);
+        let options = ClaudeRunOptions {
+            permission_prompt: Some((
+                "/tmp/mcp.json".to_string(),
+                "mcp__anyon_permissions__approve".to_string(),
+            )),
+            ..Default::default()
+        };
+        let args = policy.apply(options).to_args();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(args.contains(&"--permission-prompt-tool".to_string()));
+        let allowed = &args[args.iter().position(|a| a == "--allowedTools").unwrap() + 1];
+        assert!(allowed.split(',').any(|tool| tool == "Bash"));
+    }
+
+    #[test]
+    fn test_read_only_reviewer_cannot_edit_or_fetch() {
+        let policy = AgentToolPolicy::from_flags(true, false, false);
+        assert!(!policy.skip_permissions);
+        assert!(policy.allowed_tools.contains(&"Read".to_string()));
+        assert!(!policy.allowed_tools.contains(&"Bash".to_string()));
+        for tool in ["Edit", "Write", "WebFetch", "WebSearch"] {
+            assert!(policy.disallowed_tools.contains(&tool.to_string()));
+        }
+
+        let args = policy.apply(ClaudeRunOptions::default()).to_args();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(args.contains(&"--allowedTools".to_string()));
+        assert!(args.contains(&"--disallowedTools".to_string()));
+    }
+
+    #[test]
+    fn test_no_permissions_blocks_shell() {
+        let policy = AgentToolPolicy::from_flags(false, false, false);
+        assert!(policy.disallowed_tools.contains(&"Bash".to_string()));
+        assert!(policy.disallowed_tools.contains(&"Read".to_string()));
+    }
+}
@@ -0,0 +1,574 @@
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+
+use super::database::get_agent_run;
+use super::queue::{enqueue_agent_run, notify_dispatcher};
+use super::session::get_agent_run_with_metrics;
+use super::types::{
+    AgentDb, AgentPipeline, AgentPipelineRun, AgentPipelineRunWithMetrics, AgentRun,
+    AgentRunMetrics, PipelineStep,
+};
+
+/// How often a step's status is re-read in case its completion event was missed
+const STEP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+const PIPELINE_COLUMNS: &str =
+    "id, name, description, steps, stop_on_failure, created_at, updated_at";
+
+const PIPELINE_RUN_COLUMNS: &str = "id, pipeline_id, pipeline_name, project_path, task, status, current_step, run_ids, error, created_at, completed_at";
+
+fn pipeline_from_row(row: &rusqlite::Row) -> rusqlite::Result<AgentPipeline> {
+    let steps_json: String = row.get(3)?;
+    Ok(AgentPipeline {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        description: row.get(2)?,
+        steps: serde_json::from_str(&steps_json).unwrap_or_default(),
+        stop_on_failure: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn pipeline_run_from_row(row: &rusqlite::Row) -> rusqlite::Result<AgentPipelineRun> {
+    let run_ids_json: String = row.get(7)?;
+    Ok(AgentPipelineRun {
+        id: Some(row.get(0)?),
+        pipeline_id: row.get(1)?,
+        pipeline_name: row.get(2)?,
+        project_path: row.get(3)?,
+        task: row.get(4)?,
+        status: row.get(5)?,
+        current_step: row.get(6)?,
+        run_ids: serde_json::from_str(&run_ids_json).unwrap_or_default(),
+        error: row.get(8)?,
+        created_at: row.get(9)?,
+        completed_at: row.get(10)?,
+    })
+}
+
+fn load_pipeline(conn: &Connection, id: i64) -> Result<AgentPipeline, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM agent_pipelines WHERE id = ?1",
+            PIPELINE_COLUMNS
+        ),
+        params![id],
+        pipeline_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn load_pipeline_run(conn: &Connection, id: i64) -> Result<AgentPipelineRun, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM agent_pipeline_runs WHERE id = ?1",
+            PIPELINE_RUN_COLUMNS
+        ),
+        params![id],
+        pipeline_run_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Build the task for a step from its template
+fn render_step_task(
+    step: &PipelineStep,
+    index: usize,
+    task: &str,
+    previous: Option<&str>,
+) -> String {
+    match &step.task_template {
+        Some(template) => template
+            .replace("{{task}}", task)
+            .replace("{{previous_result}}", previous.unwrap_or_default()),
+        None if index == 0 => task.to_string(),
+        None => previous.unwrap_or(task).to_string(),
+    }
+}
+
+/// The result a finished step hands to the next one, or why the step failed
+fn step_result(run: &AgentRun) -> Result<String, String> {
+    match (run.status.as_str(), &run.result) {
+        ("completed", Some(result)) if !run.result_is_error.unwrap_or(false) => Ok(result.clone()),
+        ("completed", Some(result)) => Err(format!("reported an error: {}", result)),
+        ("completed", None) => Err("finished without a result".to_string()),
+        (status, _) => Err(format!("ended with status '{}'", status)),
+    }
+}
+
+/// Block until a step's agent run reaches a terminal state
+async fn wait_for_run(
+    app: &AppHandle,
+    run_id: i64,
+    mut done: tokio::sync::oneshot::Receiver<()>,
+) -> Result<AgentRun, String> {
+    loop {
+        tokio::select! {
+            _ = &mut done => {}
+            _ = tokio::time::sleep(STEP_POLL_INTERVAL) => {}
+        }
+
+        let run = get_agent_run(app.state::<AgentDb>(), run_id).await?;
+        if matches!(run.status.as_str(), "completed" | "failed" | "cancelled") {
+            return Ok(run);
+        }
+    }
+}
+
+/// Cancel a pipeline step, whether it's still queued or already running
+async fn cancel_step(app: &AppHandle, run_id: i64) -> Result<(), String> {
+    let dequeued =
+        super::queue::cancel_queued_agent_run(app.clone(), app.state::<AgentDb>(), run_id).await?;
+    if !dequeued {
+        super::session::kill_agent_session(
+            app.clone(),
+            app.state::<AgentDb>(),
+            app.state::<crate::process::ProcessRegistryState>(),
+            run_id,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Drive a pipeline run step by step
+async fn run_pipeline(
+    app: AppHandle,
+    pipeline_run_id: i64,
+    pipeline: AgentPipeline,
+    project_path: String,
+    task: String,
+) {
+    // Returns the number of rows changed, or None if the update failed
+    let db_update = |sql: &str, params: &[&dyn rusqlite::ToSql]| {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock();
+        match conn {
+            Ok(conn) => match conn.execute(sql, params) {
+                Ok(updated) => Some(updated),
+                Err(e) => {
+                    error!("Failed to update pipeline run {}: {}", pipeline_run_id, e);
+                    None
+                }
+            },
+            Err(e) => {
+                error!("Failed to lock agent database: {}", e);
+                None
+            }
+        }
+    };
+
+    let mut run_ids: Vec<i64> = Vec::new();
+    let mut previous_result: Option<String> = None;
+    let mut failure: Option<String> = None;
+
+    for (index, step) in pipeline.steps.iter().enumerate() {
+        // Stop if the pipeline was cancelled between steps
+        let status = {
+            let db = app.state::<AgentDb>();
+            let conn = db.0.lock();
+            conn.ok()
+                .and_then(|conn| load_pipeline_run(&conn, pipeline_run_id).ok())
+                .map(|r| r.status)
+        };
+        if status.as_deref() == Some("cancelled") {
+            info!(
+                "Pipeline run {} cancelled before step {}",
+                pipeline_run_id, index
+            );
+            return;
+        }
+
+        let step_task = render_step_task(step, index, &task, previous_result.as_deref());
+
+        let db = app.state::<AgentDb>();
+        let run_id = match enqueue_agent_run(
+            &db,
+            step.agent_id,
+            &project_path,
+            &step_task,
+            step.model.clone(),
+            None,
+            // Steps share the project so each one sees the previous step's changes
+            false,
+            None,
+            true,
+        )
+        .await
+        {
+            Ok(run_id) => run_id,
+            Err(e) => {
+                failure = Some(format!("Step {} could not be queued: {}", index + 1, e));
+                break;
+            }
+        };
+
+        // Record the step only while the pipeline is still running. A cancel that got in
+        // since the check above didn't see this step, so it's cancelled here instead.
+        run_ids.push(run_id);
+        let run_ids_json = serde_json::to_string(&run_ids).unwrap_or_else(|_| "[]".to_string());
+        let recorded = db_update(
+            "UPDATE agent_pipeline_runs SET current_step = ?1, run_ids = ?2 WHERE id = ?3 AND status = 'running'",
+            &[&(index as i64), &run_ids_json, &pipeline_run_id],
+        );
+        if recorded == Some(0) {
+            info!(
+                "Pipeline run {} cancelled while step {} was queued",
+                pipeline_run_id, index
+            );
+            if let Err(e) = cancel_step(&app, run_id).await {
+                error!("Failed to cancel agent run {}: {}", run_id, e);
+            }
+            return;
+        }
+
+        // Listen before waking the dispatcher so the completion event can't be missed
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.once(format!("agent-complete:{}", run_id), move |_| {
+            let _ = tx.send(());
+        });
+        notify_dispatcher(&app);
+
+        let _ = app.emit(
+            &format!("pipeline-step:{}", pipeline_run_id),
+            serde_json::json!({ "step": index, "run_id": run_id }),
+        );
+        info!(
+            "Pipeline run {} step {} started as agent run {}",
+            pipeline_run_id,
+            index + 1,
+            run_id
+        );
+
+        let run = match wait_for_run(&app, run_id, rx).await {
+            Ok(run) => run,
+            Err(e) => {
+                failure = Some(format!("Step {} could not be tracked: {}", index + 1, e));
+                break;
+            }
+        };
+
+        match step_result(&run) {
+            Ok(result) => previous_result = Some(result),
+            Err(why) => {
+                let reason = format!("Step {} ({}) {}", index + 1, run.agent_name, why);
+                warn!("Pipeline run {}: {}", pipeline_run_id, reason);
+                if pipeline.stop_on_failure {
+                    failure = Some(reason);
+                    break;
+                }
+                // Carry on with whatever the failed step produced, if anything
+                previous_result = run.result.clone().or(previous_result);
+            }
+        }
+    }
+
+    let status = if failure.is_some() {
+        "failed"
+    } else {
+        "completed"
+    };
+    let updated = db_update(
+        "UPDATE agent_pipeline_runs SET status = ?1, error = ?2, completed_at = CURRENT_TIMESTAMP WHERE id = ?3 AND status = 'running'",
+        &[&status, &failure, &pipeline_run_id],
+    );
+    // Cancelled meanwhile; the cancel already announced the end
+    if updated == Some(0) {
+        info!("Pipeline run {} was cancelled", pipeline_run_id);
+        return;
+    }
+
+    info!(
+        "Pipeline run {} finished with status {}",
+        pipeline_run_id, status
+    );
+    let _ = app.emit(
+        &format!("pipeline-complete:{}", pipeline_run_id),
+        failure.is_none(),
+    );
+}
+
+/// List all agent pipelines
+#[tauri::command]
+pub async fn list_agent_pipelines(db: State<'_, AgentDb>) -> Result<Vec<AgentPipeline>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_pipelines ORDER BY created_at DESC",
+            PIPELINE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let pipelines = stmt
+        .query_map([], pipeline_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(pipelines)
+}
+
+/// Get a single agent pipeline by ID
+#[tauri::command]
+pub async fn get_agent_pipeline(db: State<'_, AgentDb>, id: i64) -> Result<AgentPipeline, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_pipeline(&conn, id)
+}
+
+/// Create or update an agent pipeline (pass `id` to update)
+#[tauri::command]
+pub async fn save_agent_pipeline(
+    db: State<'_, AgentDb>,
+    id: Option<i64>,
+    name: String,
+    description: Option<String>,
+    steps: Vec<PipelineStep>,
+    stop_on_failure: Option<bool>,
+) -> Result<AgentPipeline, String> {
+    if steps.is_empty() {
+        return Err("A pipeline needs at least one step".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    for step in &steps {
+        conn.query_row(
+            "SELECT id FROM agents WHERE id = ?1",
+            params![step.agent_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|_| format!("Agent {} does not exist", step.agent_id))?;
+    }
+
+    let steps_json = serde_json::to_string(&steps).map_err(|e| e.to_string())?;
+    let stop_on_failure = stop_on_failure.unwrap_or(true);
+
+    let id = match id {
+        Some(id) => {
+            conn.execute(
+                "UPDATE agent_pipelines SET name = ?1, description = ?2, steps = ?3, stop_on_failure = ?4, updated_at = CURRENT_TIMESTAMP WHERE id = ?5",
+                params![name, description, steps_json, stop_on_failure, id],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO agent_pipelines (name, description, steps, stop_on_failure) VALUES (?1, ?2, ?3, ?4)",
+                params![name, description, steps_json, stop_on_failure],
+            )
+            .map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    load_pipeline(&conn, id)
+}
+
+/// Delete an agent pipeline
+#[tauri::command]
+pub async fn delete_agent_pipeline(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM agent_pipelines WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Start a pipeline run and return its ID; progress is reported via
+/// `pipeline-step:{id}` and `pipeline-complete:{id}` events
+#[tauri::command]
+pub async fn execute_agent_pipeline(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    pipeline_id: i64,
+    project_path: String,
+    task: String,
+) -> Result<i64, String> {
+    let (pipeline, pipeline_run_id) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let pipeline = load_pipeline(&conn, pipeline_id)?;
+        conn.execute(
+            "INSERT INTO agent_pipeline_runs (pipeline_id, pipeline_name, project_path, task) VALUES (?1, ?2, ?3, ?4)",
+            params![pipeline_id, pipeline.name, project_path, task],
+        )
+        .map_err(|e| e.to_string())?;
+        (pipeline, conn.last_insert_rowid())
+    };
+
+    info!(
+        "Starting pipeline '{}' ({} steps) as run {}",
+        pipeline.name,
+        pipeline.steps.len(),
+        pipeline_run_id
+    );
+
+    tauri::async_runtime::spawn(run_pipeline(
+        app,
+        pipeline_run_id,
+        pipeline,
+        project_path,
+        task,
+    ));
+
+    Ok(pipeline_run_id)
+}
+
+/// Cancel a pipeline run: the current step is killed and no further steps start
+#[tauri::command]
+pub async fn cancel_agent_pipeline_run(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+) -> Result<bool, String> {
+    let run = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let updated = conn
+            .execute(
+                "UPDATE agent_pipeline_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+                params![id],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Ok(false);
+        }
+        load_pipeline_run(&conn, id)?
+    };
+
+    // The current step is either still queued or running
+    if let Some(&current_run_id) = run.run_ids.last() {
+        cancel_step(&app, current_run_id).await?;
+    }
+
+    let _ = app.emit(&format!("pipeline-complete:{}", id), false);
+    Ok(true)
+}
+
+/// List pipeline runs (optionally filtered by pipeline_id)
+#[tauri::command]
+pub async fn list_agent_pipeline_runs(
+    db: State<'_, AgentDb>,
+    pipeline_id: Option<i64>,
+) -> Result<Vec<AgentPipelineRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let runs = if let Some(pid) = pipeline_id {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_pipeline_runs WHERE pipeline_id = ?1 ORDER BY created_at DESC",
+                PIPELINE_RUN_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![pid], pipeline_run_from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>();
+        rows
+    } else {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_pipeline_runs ORDER BY created_at DESC",
+                PIPELINE_RUN_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], pipeline_run_from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>();
+        rows
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(runs)
+}
+
+/// Get a pipeline run with each step's metrics and the combined totals
+#[tauri::command]
+pub async fn get_agent_pipeline_run_with_metrics(
+    db: State<'_, AgentDb>,
+    id: i64,
+) -> Result<AgentPipelineRunWithMetrics, String> {
+    let run = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_pipeline_run(&conn, id)?
+    };
+
+    let mut steps = Vec::new();
+    for run_id in &run.run_ids {
+        let step_run = get_agent_run(db.clone(), *run_id).await?;
+        steps.push(get_agent_run_with_metrics(step_run).await);
+    }
+
+    let metrics = AgentRunMetrics::combine(steps.iter().filter_map(|s| s.metrics.as_ref()));
+
+    Ok(AgentPipelineRunWithMetrics {
+        run,
+        steps,
+        metrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_run(status: &str, result: Option<&str>, is_error: Option<bool>) -> AgentRun {
+        serde_json::from_value(serde_json::json!({
+            "agent_id": 1,
+            "agent_name": "Reviewer",
+            "agent_icon": "bot",
+            "task": "review",
+            "model": "sonnet",
+            "project_path": "/tmp/project",
+            "session_id": "",
+            "status": status,
+            "created_at": "2024-01-01 00:00:00",
+            "result": result,
+            "result_is_error": is_error,
+            "use_worktree": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_a_clean_result_feeds_the_next_step() {
+        assert_eq!(
+            step_result(&finished_run("completed", Some("done"), Some(false))),
+            Ok("done".to_string())
+        );
+        // Crashed runs used to be marked completed without ever reporting a result
+        assert!(step_result(&finished_run("completed", None, None)).is_err());
+        assert!(step_result(&finished_run("completed", Some("boom"), Some(true))).is_err());
+        assert!(step_result(&finished_run("cancelled", None, None)).is_err());
+    }
+
+    #[test]
+    fn renders_step_tasks_from_templates_and_previous_results() {
+        let step = |template: Option<&str>| PipelineStep {
+            agent_id: 1,
+            model: None,
+            task_template: template.map(str::to_string),
+        };
+        assert_eq!(
+            render_step_task(&step(None), 0, "build it", None),
+            "build it"
+        );
+        assert_eq!(
+            render_step_task(&step(None), 1, "build it", Some("built")),
+            "built"
+        );
+        assert_eq!(
+            render_step_task(
+                &step(Some("Review {{previous_result}} for: {{task}}")),
+                1,
+                "build it",
+                Some("the diff")
+            ),
+            "Review the diff for: build it"
+        );
+    }
+}
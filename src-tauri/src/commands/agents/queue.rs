@@ -0,0 +1,445 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use super::database::{get_agent, mark_finished_runs};
+use super::types::{AgentDb, AgentQueueConfig, AgentRun};
+use crate::commands::budget::RunBudget;
+
+/// How often the dispatcher wakes up on its own to check schedules
+const DISPATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+const MAX_CONCURRENT_RUNS_KEY: &str = "agent_queue_max_concurrent_runs";
+const MAX_RUNS_PER_PROJECT_KEY: &str = "agent_queue_max_runs_per_project";
+
+/// Global state used to wake the agent run dispatcher
+pub struct AgentQueueState {
+    notify: Arc<Notify>,
+}
+
+impl Default for AgentQueueState {
+    fn default() -> Self {
+        Self {
+            notify: Arc::new(Notify::new()),
+        }
+    }
+}
+
+/// Format a timestamp the way queue columns store it, so they compare correctly as strings
+pub(crate) fn to_db_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Ask the dispatcher to run a pass as soon as possible
+pub fn notify_dispatcher(app: &AppHandle) {
+    if let Some(state) = app.try_state::<AgentQueueState>() {
+        state.notify.notify_one();
+    }
+}
+
+/// Insert a run in the `pending` state, holding it to the agent's budget
+///
+/// `unattended` runs are started by a schedule or pipeline, with nobody to answer
+/// permission prompts.
+pub(crate) fn insert_pending_run(
+    conn: &Connection,
+    agent_id: i64,
+    agent_name: &str,
+    agent_icon: &str,
+    task: &str,
+    model: &str,
+    project_path: &str,
+    scheduled_at: Option<&str>,
+    unattended: bool,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, scheduled_at, budget, unattended)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, '', 'pending', ?7, (SELECT budget FROM agents WHERE id = ?1), ?8)",
+        params![agent_id, agent_name, agent_icon, task, model, project_path, scheduled_at, unattended],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Queue a run for an agent and return its run ID
+///
+/// `budget` replaces the agent's own budget for this run.
+pub(crate) async fn enqueue_agent_run(
+    db: &State<'_, AgentDb>,
+    agent_id: i64,
+    project_path: &str,
+    task: &str,
+    model: Option<String>,
+    scheduled_at: Option<String>,
+    use_worktree: bool,
+    budget: Option<RunBudget>,
+    unattended: bool,
+) -> Result<i64, String> {
+    let agent = get_agent(db.clone(), agent_id).await?;
+    let execution_model = model.unwrap_or(agent.model.clone());
+
+    // Normalize to UTC so the dispatcher can compare timestamps as strings
+    let scheduled_at = scheduled_at
+        .map(|s| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|t| to_db_timestamp(t.with_timezone(&Utc)))
+                .map_err(|e| format!("Invalid scheduled_at '{}': {}", s, e))
+        })
+        .transpose()?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let run_id = insert_pending_run(
+        &conn,
+        agent_id,
+        &agent.name,
+        &agent.icon,
+        task,
+        &execution_model,
+        project_path,
+        scheduled_at.as_deref(),
+        unattended,
+    )
+    .map_err(|e| e.to_string())?;
+
+    if use_worktree {
+        conn.execute(
+            "UPDATE agent_runs SET use_worktree = 1 WHERE id = ?1",
+            params![run_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(budget) = budget.filter(|budget| !budget.is_unlimited()) {
+        let budget_json = serde_json::to_string(&budget).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE agent_runs SET budget = ?1 WHERE id = ?2",
+            params![budget_json, run_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    info!(
+        "Queued agent '{}' run {} (scheduled_at: {:?}, worktree: {})",
+        agent.name, run_id, scheduled_at, use_worktree
+    );
+    Ok(run_id)
+}
+
+/// Read the concurrency limits from app settings
+pub(crate) fn load_queue_config(conn: &Connection) -> AgentQueueConfig {
+    let read = |key: &str| -> Option<i64> {
+        conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+        .and_then(|v| v.parse().ok())
+    };
+
+    let defaults = AgentQueueConfig::default();
+    AgentQueueConfig {
+        max_concurrent_runs: read(MAX_CONCURRENT_RUNS_KEY).unwrap_or(defaults.max_concurrent_runs),
+        max_runs_per_project: read(MAX_RUNS_PER_PROJECT_KEY)
+            .unwrap_or(defaults.max_runs_per_project),
+    }
+}
+
+/// Pick the pending runs that may start now without exceeding the limits
+fn select_runs_to_start(conn: &Connection, config: &AgentQueueConfig) -> Result<Vec<i64>, String> {
+    let mut per_project: HashMap<String, i64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT project_path, COUNT(*) FROM agent_runs WHERE status = 'running' GROUP BY project_path")
+            .map_err(|e| e.to_string())?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        per_project.extend(counts);
+    }
+    let mut running: i64 = per_project.values().sum();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_path FROM agent_runs
+             WHERE status = 'pending' AND (scheduled_at IS NULL OR scheduled_at <= ?1)
+             ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let pending = stmt
+        .query_map(params![to_db_timestamp(Utc::now())], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut to_start = Vec::new();
+    for (run_id, project_path) in pending {
+        if running >= config.max_concurrent_runs {
+            break;
+        }
+        let project_running = per_project.entry(project_path).or_insert(0);
+        // A busy project doesn't block runs queued for other projects
+        if *project_running >= config.max_runs_per_project {
+            continue;
+        }
+        *project_running += 1;
+        running += 1;
+        to_start.push(run_id);
+    }
+
+    Ok(to_start)
+}
+
+/// Run one dispatcher pass: queue due schedules, then start whatever fits the limits
+pub(crate) async fn dispatch_pending_runs(app: &AppHandle) -> Result<Vec<i64>, String> {
+    let to_start = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        super::schedule::enqueue_due_schedules(&conn, Utc::now())?;
+        let config = load_queue_config(&conn);
+        select_runs_to_start(&conn, &config)?
+    };
+
+    for run_id in &to_start {
+        if let Err(e) = super::execution::start_agent_run(app, *run_id).await {
+            error!("Failed to start queued agent run {}: {}", run_id, e);
+            let db = app.state::<AgentDb>();
+            if let Ok(conn) = db.0.lock() {
+                let _ = conn.execute(
+                    "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                    params![run_id],
+                );
+            }
+            let _ = app.emit("agent-complete", false);
+            let _ = app.emit(&format!("agent-complete:{}", run_id), false);
+        }
+    }
+
+    Ok(to_start)
+}
+
+/// Start the background dispatcher
+///
+/// Pending runs are read from `agents.db` on every pass, so runs queued before an app restart
+/// are picked up by the first one.
+pub fn start_dispatcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Runs left `running` by a previous session can't be reattached; release their slots
+        {
+            let db = app.state::<AgentDb>();
+            let conn = db.0.lock();
+            match conn {
+                Ok(conn) => match mark_finished_runs(&conn) {
+                    Ok(ids) if !ids.is_empty() => {
+                        info!("Released {} stale agent runs on startup", ids.len())
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to reconcile stale agent runs: {}", e),
+                },
+                Err(e) => warn!("Failed to lock agent database: {}", e),
+            }
+        }
+
+        let notify = app.state::<AgentQueueState>().notify.clone();
+        loop {
+            match dispatch_pending_runs(&app).await {
+                Ok(started) if !started.is_empty() => {
+                    info!("Dispatcher started agent runs: {:?}", started)
+                }
+                Ok(_) => {}
+                Err(e) => error!("Agent run dispatch failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = notify.notified() => {}
+                _ = tokio::time::sleep(DISPATCH_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+/// Get the agent run queue limits
+#[tauri::command]
+pub async fn get_agent_queue_config(db: State<'_, AgentDb>) -> Result<AgentQueueConfig, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_queue_config(&conn))
+}
+
+/// Set the agent run queue limits
+#[tauri::command]
+pub async fn set_agent_queue_config(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    max_concurrent_runs: i64,
+    max_runs_per_project: i64,
+) -> Result<AgentQueueConfig, String> {
+    if max_concurrent_runs < 1 || max_runs_per_project < 1 {
+        return Err("Concurrency limits must be at least 1".to_string());
+    }
+
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        for (key, value) in [
+            (MAX_CONCURRENT_RUNS_KEY, max_concurrent_runs),
+            (MAX_RUNS_PER_PROJECT_KEY, max_runs_per_project),
+        ] {
+            conn.execute(
+                "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = ?2",
+                params![key, value.to_string()],
+            )
+            .map_err(|e| format!("Failed to save queue config: {}", e))?;
+        }
+    }
+
+    // Raised limits may let queued runs start right away
+    notify_dispatcher(&app);
+
+    Ok(AgentQueueConfig {
+        max_concurrent_runs,
+        max_runs_per_project,
+    })
+}
+
+/// List runs waiting in the queue, oldest first
+#[tauri::command]
+pub async fn list_agent_queue(db: State<'_, AgentDb>) -> Result<Vec<AgentRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_runs WHERE status = 'pending' ORDER BY created_at ASC, id ASC",
+            AgentRun::SELECT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let runs = stmt
+        .query_map([], AgentRun::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(runs)
+}
+
+/// Remove a run from the queue before it starts
+#[tauri::command]
+pub async fn cancel_queued_agent_run(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<bool, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'pending'",
+            params![run_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+    if updated > 0 {
+        let _ = app.emit(&format!("agent-cancelled:{}", run_id), true);
+    }
+
+    Ok(updated > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE agent_runs (
+                id INTEGER PRIMARY KEY,
+                project_path TEXT NOT NULL,
+                status TEXT NOT NULL,
+                scheduled_at TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn add_run(conn: &Connection, id: i64, project_path: &str, status: &str) {
+        conn.execute(
+            "INSERT INTO agent_runs (id, project_path, status) VALUES (?1, ?2, ?3)",
+            params![id, project_path, status],
+        )
+        .unwrap();
+    }
+
+    fn limits(max_concurrent_runs: i64, max_runs_per_project: i64) -> AgentQueueConfig {
+        AgentQueueConfig {
+            max_concurrent_runs,
+            max_runs_per_project,
+        }
+    }
+
+    #[test]
+    fn test_global_limit_counts_running_runs() {
+        let conn = runs_db();
+        add_run(&conn, 1, "/a", "running");
+        for id in 2..=5 {
+            add_run(&conn, id, &format!("/p{}", id), "pending");
+        }
+        assert_eq!(select_runs_to_start(&conn, &limits(3, 5)).unwrap(), [2, 3]);
+        assert!(select_runs_to_start(&conn, &limits(1, 5))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_per_project_limit_skips_busy_projects() {
+        let conn = runs_db();
+        add_run(&conn, 1, "/a", "running");
+        add_run(&conn, 2, "/a", "pending");
+        add_run(&conn, 3, "/a", "pending");
+        add_run(&conn, 4, "/b", "pending");
+        add_run(&conn, 5, "/b", "pending");
+        add_run(&conn, 6, "/c", "pending");
+
+        // "/a" is full, but the runs queued behind it for other projects still start
+        assert_eq!(select_runs_to_start(&conn, &limits(10, 1)).unwrap(), [4, 6]);
+        assert_eq!(
+            select_runs_to_start(&conn, &limits(10, 2)).unwrap(),
+            [2, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_future_scheduled_runs_wait() {
+        let conn = runs_db();
+        add_run(&conn, 1, "/a", "pending");
+        add_run(&conn, 2, "/a", "pending");
+        add_run(&conn, 3, "/a", "pending");
+        let now = Utc::now();
+        conn.execute(
+            "UPDATE agent_runs SET scheduled_at = ?1 WHERE id = 1",
+            params![to_db_timestamp(now + chrono::Duration::hours(1))],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE agent_runs SET scheduled_at = ?1 WHERE id = 2",
+            params![to_db_timestamp(now - chrono::Duration::minutes(1))],
+        )
+        .unwrap();
+
+        assert_eq!(
+            select_runs_to_start(&conn, &limits(10, 10)).unwrap(),
+            [2, 3]
+        );
+    }
+}
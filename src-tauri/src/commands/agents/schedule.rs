@@ -0,0 +1,435 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike, Utc};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, State};
+
+use super::queue::{insert_pending_run, notify_dispatcher, to_db_timestamp};
+use super::types::{AgentDb, AgentSchedule};
+
+/// Upper bound on how far ahead `next_after` searches (a little over a leap year of minutes)
+const MAX_SEARCH_MINUTES: i64 = 366 * 24 * 60 + 60;
+
+/// A parsed 5-field cron expression (`minute hour day-of-month month day-of-week`)
+///
+/// Supports `*`, `*/n`, `a-b`, `a-b/n` and comma lists, plus the `@hourly`, `@daily`,
+/// `@midnight`, `@weekly`, `@monthly` and `@yearly` shortcuts. Times are local.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Invalid cron expression '{}': expected 5 fields, got {}",
+                expr,
+                fields.len()
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        // Both 0 and 7 mean Sunday
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            day_of_month_restricted: fields[2] != "*",
+            day_of_week_restricted: fields[4] != "*",
+        })
+    }
+
+    /// Whether the schedule fires at the given (minute-resolution) local time
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        if !self.minutes[time.minute() as usize]
+            || !self.hours[time.hour() as usize]
+            || !self.months[time.month() as usize]
+        {
+            return false;
+        }
+
+        let dom = self.days_of_month[time.day() as usize];
+        let dow = self.days_of_week[time.weekday().num_days_from_sunday() as usize];
+
+        // Standard cron semantics: when both day fields are restricted, either may match
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// The first time strictly after `after` at which the schedule fires
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = after.with_timezone(&Local).naive_local();
+        let mut candidate = local
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))?
+            + Duration::minutes(1);
+
+        for _ in 0..MAX_SEARCH_MINUTES {
+            if self.matches(&candidate) {
+                // Skip local times that don't exist (DST gaps)
+                if let Some(resolved) = Local.from_local_datetime(&candidate).earliest() {
+                    return Some(resolved.with_timezone(&Utc));
+                }
+            }
+            candidate += Duration::minutes(1);
+        }
+
+        None
+    }
+}
+
+/// Parse a single cron field into a lookup table indexed by value
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step '{}' in cron field '{}'", step, field))?;
+                if step == 0 {
+                    return Err(format!("Step must be positive in cron field '{}'", field));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, field)?, parse_value(b, field)?)
+        } else {
+            let value = parse_value(range, field)?;
+            // `n/step` means "from n to the end of the range"
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Value out of range {}-{} in cron field '{}'",
+                min, max, field
+            ));
+        }
+
+        let mut value = start;
+        while value <= end {
+            allowed[value as usize] = true;
+            value += step;
+        }
+    }
+
+    Ok(allowed)
+}
+
+fn parse_value(value: &str, field: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in cron field '{}'", value, field))
+}
+
+/// Map a row from `agent_schedules`
+fn schedule_from_row(row: &rusqlite::Row) -> rusqlite::Result<AgentSchedule> {
+    Ok(AgentSchedule {
+        id: Some(row.get(0)?),
+        agent_id: row.get(1)?,
+        project_path: row.get(2)?,
+        task: row.get(3)?,
+        model: row.get(4)?,
+        cron_expr: row.get(5)?,
+        enabled: row.get(6)?,
+        last_run_at: row.get(7)?,
+        next_run_at: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+const SCHEDULE_COLUMNS: &str =
+    "id, agent_id, project_path, task, model, cron_expr, enabled, last_run_at, next_run_at, created_at";
+
+/// Queue a pending run for every enabled schedule that is due, then advance its next run time
+pub(crate) fn enqueue_due_schedules(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<i64>, String> {
+    let now_str = to_db_timestamp(now);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.agent_id, s.project_path, s.task, COALESCE(s.model, a.model), s.cron_expr, a.name, a.icon
+             FROM agent_schedules s JOIN agents a ON a.id = s.agent_id
+             WHERE s.enabled = 1 AND s.next_run_at IS NOT NULL AND s.next_run_at <= ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let due = stmt
+        .query_map(params![now_str], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    drop(stmt);
+
+    let mut run_ids = Vec::new();
+    for (schedule_id, agent_id, project_path, task, model, cron_expr, agent_name, agent_icon) in due
+    {
+        let run_id = insert_pending_run(
+            conn,
+            agent_id,
+            &agent_name,
+            &agent_icon,
+            &task,
+            &model,
+            &project_path,
+            None,
+            true,
+        )
+        .map_err(|e| e.to_string())?;
+        run_ids.push(run_id);
+
+        // A schedule whose expression no longer parses is disabled rather than retried every pass
+        let next_run_at = match CronSchedule::parse(&cron_expr) {
+            Ok(schedule) => schedule.next_after(now).map(to_db_timestamp),
+            Err(e) => {
+                log::error!("Disabling agent schedule {}: {}", schedule_id, e);
+                None
+            }
+        };
+
+        conn.execute(
+            "UPDATE agent_schedules SET last_run_at = ?1, next_run_at = ?2, enabled = ?3 WHERE id = ?4",
+            params![now_str, next_run_at, next_run_at.is_some(), schedule_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        log::info!(
+            "Queued scheduled run {} for agent '{}' (schedule {})",
+            run_id,
+            agent_name,
+            schedule_id
+        );
+    }
+
+    Ok(run_ids)
+}
+
+/// List agent schedules (optionally filtered by agent_id)
+#[tauri::command]
+pub async fn list_agent_schedules(
+    db: State<'_, AgentDb>,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentSchedule>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let schedules = if let Some(aid) = agent_id {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_schedules WHERE agent_id = ?1 ORDER BY created_at DESC",
+                SCHEDULE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![aid], schedule_from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>();
+        rows
+    } else {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_schedules ORDER BY created_at DESC",
+                SCHEDULE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], schedule_from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>();
+        rows
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(schedules)
+}
+
+/// Create a recurring agent run
+#[tauri::command]
+pub async fn create_agent_schedule(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    project_path: String,
+    task: String,
+    model: Option<String>,
+    cron_expr: String,
+) -> Result<AgentSchedule, String> {
+    let schedule = CronSchedule::parse(&cron_expr)?;
+    let next_run_at = schedule
+        .next_after(Utc::now())
+        .map(to_db_timestamp)
+        .ok_or_else(|| format!("Cron expression '{}' never fires", cron_expr))?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agent_schedules (agent_id, project_path, task, model, cron_expr, next_run_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![agent_id, project_path, task, model, cron_expr, next_run_at],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let id = conn.last_insert_rowid();
+    conn.query_row(
+        &format!("SELECT {} FROM agent_schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+        params![id],
+        schedule_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Enable or disable a schedule; enabling recomputes its next run from now
+#[tauri::command]
+pub async fn set_agent_schedule_enabled(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+    enabled: bool,
+) -> Result<AgentSchedule, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let cron_expr: String = conn
+        .query_row(
+            "SELECT cron_expr FROM agent_schedules WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let next_run_at = if enabled {
+        CronSchedule::parse(&cron_expr)?
+            .next_after(Utc::now())
+            .map(to_db_timestamp)
+    } else {
+        None
+    };
+
+    conn.execute(
+        "UPDATE agent_schedules SET enabled = ?1, next_run_at = ?2 WHERE id = ?3",
+        params![enabled, next_run_at, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let schedule = conn
+        .query_row(
+            &format!("SELECT {} FROM agent_schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+            params![id],
+            schedule_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+
+    drop(conn);
+    notify_dispatcher(&app);
+
+    Ok(schedule)
+}
+
+/// Delete a schedule (runs it already queued are unaffected)
+#[tauri::command]
+pub async fn delete_agent_schedule(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM agent_schedules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_nightly() {
+        let schedule = CronSchedule::parse("30 2 * * *").unwrap();
+        assert!(schedule.matches(&at(2025, 6, 1, 2, 30)));
+        assert!(!schedule.matches(&at(2025, 6, 1, 3, 30)));
+        assert_eq!(schedule, CronSchedule::parse("30 2 * * *").unwrap());
+    }
+
+    #[test]
+    fn test_parse_steps_ranges_and_lists() {
+        let schedule = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        // 2025-06-02 is a Monday, 2025-06-01 a Sunday
+        assert!(schedule.matches(&at(2025, 6, 2, 9, 45)));
+        assert!(!schedule.matches(&at(2025, 6, 2, 9, 50)));
+        assert!(!schedule.matches(&at(2025, 6, 1, 9, 45)));
+
+        let sundays = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert!(sundays.matches(&at(2025, 6, 1, 0, 0)));
+
+        let list = CronSchedule::parse("0 6,18 1,15 * *").unwrap();
+        assert!(list.matches(&at(2025, 6, 15, 18, 0)));
+        assert!(!list.matches(&at(2025, 6, 14, 18, 0)));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+
+    #[test]
+    fn test_next_after_is_strictly_later() {
+        let schedule = CronSchedule::parse("@hourly").unwrap();
+        let now = Utc::now();
+        let next = schedule.next_after(now).unwrap();
+        assert!(next > now);
+        assert!(next - now <= Duration::hours(1));
+        assert_eq!(next.with_timezone(&Local).minute(), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use dirs;
 use log::{debug, info, warn};
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use std::io::{BufRead, BufReader};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tokio;
@@ -35,7 +35,7 @@ pub async fn read_session_jsonl(session_id: &str, project_path: &str) -> Result<
 
 /// Get agent run with real-time metrics
 pub async fn get_agent_run_with_metrics(run: AgentRun) -> AgentRunWithMetrics {
-    match read_session_jsonl(&run.session_id, &run.project_path).await {
+    match read_session_jsonl(&run.session_id, run.working_dir()).await {
         Ok(jsonl_content) => {
             let metrics = AgentRunMetrics::from_jsonl(&jsonl_content);
             AgentRunWithMetrics {
@@ -91,35 +91,15 @@ pub async fn list_running_sessions(
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     // First get all running sessions from the database
-    let mut stmt = conn.prepare(
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
-         FROM agent_runs WHERE status = 'running' ORDER BY process_started_at DESC"
-    ).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_runs WHERE status = 'running' ORDER BY process_started_at DESC",
+            AgentRun::SELECT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
 
     let mut runs = stmt
-        .query_map([], |row| {
-            Ok(AgentRun {
-                id: Some(row.get(0)?),
-                agent_id: row.get(1)?,
-                agent_name: row.get(2)?,
-                agent_icon: row.get(3)?,
-                task: row.get(4)?,
-                model: row.get(5)?,
-                project_path: row.get(6)?,
-                session_id: row.get(7)?,
-                status: row
-                    .get::<_, String>(8)
-                    .unwrap_or_else(|_| "pending".to_string()),
-                pid: row
-                    .get::<_, Option<i64>>(9)
-                    .ok()
-                    .flatten()
-                    .map(|p| p as u32),
-                process_started_at: row.get(10)?,
-                created_at: row.get(11)?,
-                completed_at: row.get(12)?,
-            })
-        })
+        .query_map([], AgentRun::from_row)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -177,12 +157,15 @@ pub async fn kill_agent_session(
     if !killed_via_registry {
         let pid_result = {
             let conn = db.0.lock().map_err(|e| e.to_string())?;
+            // Runs that are not running (anymore) have nothing to kill
             conn.query_row(
                 "SELECT pid FROM agent_runs WHERE id = ?1 AND status = 'running'",
                 params![run_id],
                 |row| row.get::<_, Option<i64>>(0),
             )
+            .optional()
             .map_err(|e| e.to_string())?
+            .flatten()
         };
 
         if let Some(pid) = pid_result {
@@ -314,7 +297,7 @@ pub async fn get_session_output(
             "Session file not found for {}, trying legacy method",
             run.session_id
         );
-        match read_session_jsonl(&run.session_id, &run.project_path).await {
+        match read_session_jsonl(&run.session_id, run.working_dir()).await {
             Ok(content) => Ok(content),
             Err(_) => {
                 // Final fallback to live output
@@ -341,7 +324,7 @@ pub async fn stream_session_output(
     }
 
     let session_id = run.session_id.clone();
-    let project_path = run.project_path.clone();
+    let project_path = run.working_dir().to_string();
 
     // Spawn a task to monitor the file
     tokio::spawn(async move {
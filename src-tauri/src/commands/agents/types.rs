@@ -1,8 +1,9 @@
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
 use std::sync::Mutex;
 
+use crate::stream_json::{parse_line, StreamMessage};
+
 /// Represents a CC Agent stored in the database
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Agent {
@@ -36,6 +37,143 @@ pub struct AgentRun {
     pub process_started_at: Option<String>,
     pub created_at: String,
     pub completed_at: Option<String>,
+    pub tool_policy: Option<String>, // JSON of the AgentToolPolicy the run was started with
+    pub scheduled_at: Option<String>, // RFC3339 UTC; pending runs wait until this time
+    pub result: Option<String>,      // Text of the final stream-json `result` message
+    pub result_is_error: Option<bool>,
+    pub use_worktree: bool, // Run in a dedicated git worktree instead of project_path
+    pub worktree_path: Option<String>,
+    pub worktree_branch: Option<String>,
+    pub worktree_base: Option<String>, // Commit the worktree branch was created from
+    pub worktree_status: Option<String>, // 'active', 'merged', 'cherry_picked', 'discarded'
+    pub budget: Option<String>,        // JSON of the RunBudget enforced on the run
+    #[serde(default)]
+    pub unattended: bool, // Started by a schedule or pipeline; never asks for permissions
+}
+
+impl AgentRun {
+    /// Column list matching `from_row`
+    pub(crate) const SELECT_COLUMNS: &'static str = "id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at, tool_policy, scheduled_at, result, result_is_error, use_worktree, worktree_path, worktree_branch, worktree_base, worktree_status, budget, unattended";
+
+    /// Map a row selected with `SELECT_COLUMNS`
+    pub(crate) fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: Some(row.get(0)?),
+            agent_id: row.get(1)?,
+            agent_name: row.get(2)?,
+            agent_icon: row.get(3)?,
+            task: row.get(4)?,
+            model: row.get(5)?,
+            project_path: row.get(6)?,
+            session_id: row.get(7)?,
+            status: row
+                .get::<_, String>(8)
+                .unwrap_or_else(|_| "pending".to_string()),
+            pid: row
+                .get::<_, Option<i64>>(9)
+                .ok()
+                .flatten()
+                .map(|p| p as u32),
+            process_started_at: row.get(10)?,
+            created_at: row.get(11)?,
+            completed_at: row.get(12)?,
+            tool_policy: row.get(13)?,
+            scheduled_at: row.get(14)?,
+            result: row.get(15)?,
+            result_is_error: row.get(16)?,
+            use_worktree: row.get::<_, Option<bool>>(17)?.unwrap_or(false),
+            worktree_path: row.get(18)?,
+            worktree_branch: row.get(19)?,
+            worktree_base: row.get(20)?,
+            worktree_status: row.get(21)?,
+            budget: row.get(22)?,
+            unattended: row.get::<_, Option<bool>>(23)?.unwrap_or(false),
+        })
+    }
+
+    /// Directory the agent process runs in: its worktree if it has one
+    pub fn working_dir(&self) -> &str {
+        self.worktree_path.as_deref().unwrap_or(&self.project_path)
+    }
+}
+
+/// A recurring agent run driven by a cron expression
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentSchedule {
+    pub id: Option<i64>,
+    pub agent_id: i64,
+    pub project_path: String,
+    pub task: String,
+    pub model: Option<String>, // Falls back to the agent's model
+    pub cron_expr: String,
+    pub enabled: bool,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+    pub created_at: String,
+}
+
+/// One step of an agent pipeline
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipelineStep {
+    pub agent_id: i64,
+    pub model: Option<String>, // Overrides the agent's model for this step
+    /// Task for this step. `{{task}}` is replaced with the pipeline's task and
+    /// `{{previous_result}}` with the previous step's result. When unset, the first step
+    /// gets the pipeline's task and later steps get the previous result.
+    pub task_template: Option<String>,
+}
+
+/// A chain of agents where each step's result feeds the next step's task
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentPipeline {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<PipelineStep>,
+    pub stop_on_failure: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// An execution of an agent pipeline
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentPipelineRun {
+    pub id: Option<i64>,
+    pub pipeline_id: i64,
+    pub pipeline_name: String,
+    pub project_path: String,
+    pub task: String,
+    pub status: String, // 'running', 'completed', 'failed', 'cancelled', 'interrupted'
+    pub current_step: i64,
+    pub run_ids: Vec<i64>, // agent_runs started for each step, in order
+    pub error: Option<String>,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// Pipeline run with its step runs and metrics summed across steps
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentPipelineRunWithMetrics {
+    #[serde(flatten)]
+    pub run: AgentPipelineRun,
+    pub steps: Vec<AgentRunWithMetrics>,
+    pub metrics: AgentRunMetrics,
+}
+
+/// Concurrency limits applied by the agent run dispatcher
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentQueueConfig {
+    pub max_concurrent_runs: i64,
+    pub max_runs_per_project: i64,
+}
+
+impl Default for AgentQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_runs: 4,
+            max_runs_per_project: 2,
+        }
+    }
 }
 
 /// Represents runtime metrics calculated from JSONL
@@ -107,48 +245,56 @@ impl AgentRunMetrics {
         let mut total_tokens = 0i64;
         let mut cost_usd = 0.0f64;
         let mut message_count = 0i64;
+        // The result repeats the totals of the turns before it, so it only counts when
+        // the turns themselves carried none
+        let mut result_tokens = 0i64;
+        let mut result_cost_usd = 0.0f64;
         let mut start_time: Option<chrono::DateTime<chrono::Utc>> = None;
         let mut end_time: Option<chrono::DateTime<chrono::Utc>> = None;
 
-        for line in jsonl_content.lines() {
-            if let Ok(json) = serde_json::from_str::<JsonValue>(line) {
-                message_count += 1;
-
-                // Track timestamps
-                if let Some(timestamp_str) = json.get("timestamp").and_then(|t| t.as_str()) {
-                    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(timestamp_str) {
-                        let utc_time = timestamp.with_timezone(&chrono::Utc);
-                        if start_time.is_none() || utc_time < start_time.unwrap() {
-                            start_time = Some(utc_time);
-                        }
-                        if end_time.is_none() || utc_time > end_time.unwrap() {
-                            end_time = Some(utc_time);
-                        }
-                    }
-                }
+        for message in jsonl_content.lines().filter_map(parse_line) {
+            message_count += 1;
 
-                // Extract token usage - check both top-level and nested message.usage
-                let usage = json
-                    .get("usage")
-                    .or_else(|| json.get("message").and_then(|m| m.get("usage")));
-
-                if let Some(usage) = usage {
-                    if let Some(input_tokens) = usage.get("input_tokens").and_then(|t| t.as_i64()) {
-                        total_tokens += input_tokens;
+            // Track timestamps
+            if let Some(timestamp_str) = message.timestamp() {
+                if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(timestamp_str) {
+                    let utc_time = timestamp.with_timezone(&chrono::Utc);
+                    if start_time.is_none() || utc_time < start_time.unwrap() {
+                        start_time = Some(utc_time);
                     }
-                    if let Some(output_tokens) = usage.get("output_tokens").and_then(|t| t.as_i64())
-                    {
-                        total_tokens += output_tokens;
+                    if end_time.is_none() || utc_time > end_time.unwrap() {
+                        end_time = Some(utc_time);
                     }
                 }
+            }
 
-                // Extract cost information
-                if let Some(cost) = json.get("cost").and_then(|c| c.as_f64()) {
+            let is_result = matches!(message, StreamMessage::Result(_));
+            if let Some(usage) = message.usage() {
+                let tokens = usage.input_tokens.unwrap_or(0) as i64
+                    + usage.output_tokens.unwrap_or(0) as i64;
+                if is_result {
+                    result_tokens += tokens;
+                } else {
+                    total_tokens += tokens;
+                }
+            }
+
+            if let Some(cost) = message.cost_usd() {
+                if is_result {
+                    result_cost_usd += cost;
+                } else {
                     cost_usd += cost;
                 }
             }
         }
 
+        if total_tokens == 0 {
+            total_tokens = result_tokens;
+        }
+        if cost_usd == 0.0 {
+            cost_usd = result_cost_usd;
+        }
+
         let duration_ms = match (start_time, end_time) {
             (Some(start), Some(end)) => Some((end - start).num_milliseconds()),
             _ => None,
@@ -169,4 +315,64 @@ impl AgentRunMetrics {
             },
         }
     }
+
+    /// Sum metrics across several runs, e.g. the steps of a pipeline
+    pub fn combine<'a>(metrics: impl IntoIterator<Item = &'a AgentRunMetrics>) -> Self {
+        fn add<T: std::ops::Add<Output = T>>(total: Option<T>, value: Option<T>) -> Option<T> {
+            match (total, value) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            }
+        }
+
+        metrics.into_iter().fold(
+            Self {
+                duration_ms: None,
+                total_tokens: None,
+                cost_usd: None,
+                message_count: None,
+            },
+            |total, m| Self {
+                duration_ms: add(total.duration_ms, m.duration_ms),
+                total_tokens: add(total.total_tokens, m.total_tokens),
+                cost_usd: add(total.cost_usd, m.cost_usd),
+                message_count: add(total.message_count, m.message_count),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_count_turns_once_alongside_the_result() {
+        let jsonl = [
+            r#"{"type":"system","subtype":"init","session_id":"s","timestamp":"2025-01-01T00:00:00Z"}"#,
+            r#"{"type":"assistant","message":{"content":[],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"assistant","message":{"content":[],"usage":{"input_tokens":20,"output_tokens":5}}}"#,
+            r#"{"type":"result","subtype":"success","total_cost_usd":0.5,"usage":{"input_tokens":30,"output_tokens":10},"timestamp":"2025-01-01T00:00:02Z"}"#,
+        ]
+        .join("\n");
+
+        let metrics = AgentRunMetrics::from_jsonl(&jsonl);
+        assert_eq!(metrics.total_tokens, Some(40));
+        assert_eq!(metrics.cost_usd, Some(0.5));
+        assert_eq!(metrics.message_count, Some(4));
+        assert_eq!(metrics.duration_ms, Some(2000));
+    }
+
+    #[test]
+    fn metrics_fall_back_to_the_result_totals() {
+        let jsonl = [
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"hi"}]}}"#,
+            r#"{"type":"result","subtype":"success","total_cost_usd":0.25,"usage":{"input_tokens":7,"output_tokens":3}}"#,
+        ]
+        .join("\n");
+
+        let metrics = AgentRunMetrics::from_jsonl(&jsonl);
+        assert_eq!(metrics.total_tokens, Some(10));
+        assert_eq!(metrics.cost_usd, Some(0.25));
+    }
 }
@@ -0,0 +1,327 @@
+use log::{info, warn};
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::database::get_agent_run;
+use super::types::{AgentDb, AgentRun};
+use crate::commands::git::{git_stdout, run_git, GitDiffSummary};
+
+/// A git worktree an agent run works in, on its own throwaway branch
+pub(crate) struct AgentWorktree {
+    /// The project's directory inside the worktree, which is the root unless the project
+    /// is a subdirectory of its repository
+    pub path: String,
+    pub branch: String,
+    pub base: String,
+}
+
+/// Where a run's worktree lives: `.git/anyon-worktrees/agent-run-<id>` of the project's
+/// repository, so nothing appears next to the project
+///
+/// Uses the common git directory, so a project that is itself a worktree still gets one.
+fn worktree_root(project_path: &str, run_id: i64) -> Result<PathBuf, String> {
+    let git_dir = git_stdout(project_path, &["rev-parse", "--git-common-dir"], None)?;
+    // Relative to the project when it isn't absolute
+    Ok(Path::new(project_path)
+        .join(git_dir)
+        .join("anyon-worktrees")
+        .join(format!("agent-run-{}", run_id)))
+}
+
+/// Create a worktree for a run on a new branch from the project's HEAD
+///
+/// Only committed work is visible to the agent; uncommitted changes stay in the project.
+pub(crate) fn create_agent_worktree(
+    project_path: &str,
+    run_id: i64,
+) -> Result<AgentWorktree, String> {
+    let base = git_stdout(project_path, &["rev-parse", "--verify", "HEAD"], None).map_err(|e| {
+        format!(
+            "Worktree isolation needs a git repository with a commit: {}",
+            e
+        )
+    })?;
+    let branch = format!("anyon/agent-run-{}", run_id);
+    let root = worktree_root(project_path, run_id)?;
+    let root_str = root.to_string_lossy().to_string();
+    // e.g. "packages/web/" when the project is a subdirectory of a monorepo
+    let prefix = git_stdout(project_path, &["rev-parse", "--show-prefix"], None)?;
+
+    git_stdout(
+        project_path,
+        &["worktree", "add", "-b", &branch, &root_str, &base],
+        None,
+    )?;
+    info!(
+        "Created worktree {} on branch {} for run {}",
+        root_str, branch, run_id
+    );
+
+    Ok(AgentWorktree {
+        path: root.join(prefix).to_string_lossy().to_string(),
+        branch,
+        base,
+    })
+}
+
+/// Load a run and check its worktree can be reviewed
+async fn load_finished_worktree_run(
+    db: &State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<AgentRun, String> {
+    let run = get_agent_run(db.clone(), run_id).await?;
+    if run.worktree_path.is_none() || run.worktree_status.as_deref() != Some("active") {
+        return Err(format!("Agent run {} has no active worktree", run_id));
+    }
+    if run.status == "running" || run.status == "pending" {
+        return Err(format!("Agent run {} hasn't finished yet", run_id));
+    }
+    Ok(run)
+}
+
+/// Commit whatever the agent left uncommitted so it becomes part of the branch
+fn commit_pending_changes(run: &AgentRun) -> Result<(), String> {
+    let worktree = run.working_dir();
+    let status = git_stdout(worktree, &["status", "--porcelain"], None)?;
+    if status.is_empty() {
+        return Ok(());
+    }
+
+    git_stdout(worktree, &["add", "-A"], None)?;
+    let message = format!(
+        "Agent run {} ({}): {}",
+        run.id.unwrap_or_default(),
+        run.agent_name,
+        run.task.lines().next().unwrap_or_default()
+    );
+    git_stdout(worktree, &["commit", "-q", "-m", &message], None)?;
+    Ok(())
+}
+
+/// Remove the worktree and its branch, and record how it was resolved
+fn remove_agent_worktree(
+    db: &State<'_, AgentDb>,
+    run: &AgentRun,
+    resolution: &str,
+) -> Result<(), String> {
+    if let Some(path) = &run.worktree_path {
+        // The run worked in the project's subdirectory; git removes the whole worktree
+        let root = git_stdout(path, &["rev-parse", "--show-toplevel"], None)
+            .unwrap_or_else(|_| path.clone());
+        if let Err(e) = git_stdout(
+            &run.project_path,
+            &["worktree", "remove", "--force", &root],
+            None,
+        ) {
+            warn!("Failed to remove worktree {}: {}", root, e);
+        }
+    }
+    if let Some(branch) = &run.worktree_branch {
+        if let Err(e) = git_stdout(&run.project_path, &["branch", "-D", branch], None) {
+            warn!("Failed to delete branch {}: {}", branch, e);
+        }
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agent_runs SET worktree_status = ?1 WHERE id = ?2",
+        params![resolution, run.id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Diff a worktree, including uncommitted and untracked files, against `base`
+///
+/// The working tree is staged into a temporary index, so the worktree's own index and
+/// branch are left as they are.
+fn worktree_diff_summary(worktree: &str, base: &str) -> Result<GitDiffSummary, String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let index_file = temp_dir.path().join("index");
+    let real_index = git_stdout(worktree, &["rev-parse", "--git-path", "index"], None)?;
+    let real_index = Path::new(worktree).join(real_index);
+    if real_index.exists() {
+        std::fs::copy(&real_index, &index_file).map_err(|e| e.to_string())?;
+    }
+    git_stdout(worktree, &["add", "-A"], Some(&index_file))?;
+
+    let commits = git_stdout(
+        worktree,
+        &["rev-list", "--count", &format!("{}..HEAD", base)],
+        None,
+    )?;
+    let stat_summary = git_stdout(
+        worktree,
+        &["diff", "--cached", "--stat", base],
+        Some(&index_file),
+    )?;
+    let changed_files: Vec<String> = git_stdout(
+        worktree,
+        &["diff", "--cached", "--name-only", base],
+        Some(&index_file),
+    )?
+    .lines()
+    .filter(|l| !l.is_empty())
+    .map(|l| l.to_string())
+    .collect();
+
+    Ok(GitDiffSummary {
+        commits_to_rollback: commits.parse().unwrap_or(0),
+        files_changed: changed_files.len() as u32,
+        changed_files,
+        stat_summary,
+    })
+}
+
+/// Get what an isolated run changed relative to the commit its worktree started from
+#[tauri::command]
+pub async fn get_agent_worktree_diff(
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<GitDiffSummary, String> {
+    let run = load_finished_worktree_run(&db, run_id).await?;
+    let base = run.worktree_base.clone().unwrap_or_default();
+    worktree_diff_summary(run.working_dir(), &base)
+}
+
+/// Merge an isolated run's branch into the project's current branch
+#[tauri::command]
+pub async fn merge_agent_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    let run = load_finished_worktree_run(&db, run_id).await?;
+    commit_pending_changes(&run)?;
+    let branch = run.worktree_branch.clone().unwrap_or_default();
+    info!("Merging {} into {}", branch, run.project_path);
+
+    let message = format!("Merge agent run {} ({})", run_id, run.agent_name);
+    let output = run_git(
+        &run.project_path,
+        &["merge", "--no-ff", "-m", &message, &branch],
+        None,
+    )?;
+    if !output.status.success() {
+        // Leave the project as it was and keep the worktree so the user can retry
+        let _ = run_git(&run.project_path, &["merge", "--abort"], None);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Err(format!(
+            "git merge failed: {}{}",
+            stdout.trim(),
+            stderr.trim()
+        ));
+    }
+
+    remove_agent_worktree(&db, &run, "merged")
+}
+
+/// Apply an isolated run's commits onto the project's current branch without a merge commit
+#[tauri::command]
+pub async fn cherry_pick_agent_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    let run = load_finished_worktree_run(&db, run_id).await?;
+    commit_pending_changes(&run)?;
+    let branch = run.worktree_branch.clone().unwrap_or_default();
+    let base = run.worktree_base.clone().unwrap_or_default();
+
+    let range = format!("{}..{}", base, branch);
+    if git_stdout(&run.project_path, &["rev-list", "--count", &range], None)? == "0" {
+        info!(
+            "Agent run {} made no changes; nothing to cherry-pick",
+            run_id
+        );
+        return remove_agent_worktree(&db, &run, "cherry_picked");
+    }
+
+    info!("Cherry-picking {} into {}", range, run.project_path);
+    let output = run_git(&run.project_path, &["cherry-pick", &range], None)?;
+    if !output.status.success() {
+        let _ = run_git(&run.project_path, &["cherry-pick", "--abort"], None);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git cherry-pick failed: {}", stderr.trim()));
+    }
+
+    remove_agent_worktree(&db, &run, "cherry_picked")
+}
+
+/// Throw away an isolated run's worktree and branch
+#[tauri::command]
+pub async fn discard_agent_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    let run = load_finished_worktree_run(&db, run_id).await?;
+    info!("Discarding worktree of agent run {}", run_id);
+    remove_agent_worktree(&db, &run, "discarded")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn diff_includes_uncommitted_work_without_committing_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        git(&project, &["init", "-q"]);
+        std::fs::write(project.join("a.txt"), "one").unwrap();
+        git(&project, &["add", "a.txt"]);
+        git(&project, &["commit", "-q", "-m", "init"]);
+
+        let worktree = create_agent_worktree(project.to_str().unwrap(), 7).unwrap();
+        let path = Path::new(&worktree.path);
+        std::fs::write(path.join("b.txt"), "committed by the agent").unwrap();
+        git(path, &["add", "b.txt"]);
+        git(path, &["commit", "-q", "-m", "agent"]);
+        std::fs::write(path.join("a.txt"), "edited").unwrap();
+        std::fs::write(path.join("c.txt"), "untracked").unwrap();
+
+        let head = git_stdout(&worktree.path, &["rev-parse", "HEAD"], None).unwrap();
+        let summary = worktree_diff_summary(&worktree.path, &worktree.base).unwrap();
+        assert_eq!(summary.commits_to_rollback, 1);
+        assert_eq!(summary.changed_files, vec!["a.txt", "b.txt", "c.txt"]);
+
+        // Nothing was committed or staged in the worktree
+        assert_eq!(
+            git_stdout(&worktree.path, &["rev-parse", "HEAD"], None).unwrap(),
+            head
+        );
+        assert_eq!(
+            git_stdout(&worktree.path, &["status", "--porcelain"], None).unwrap(),
+            "M a.txt\n?? c.txt"
+        );
+    }
+
+    #[test]
+    fn worktree_keeps_the_project_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("monorepo");
+        let project = repo.join("packages").join("web");
+        std::fs::create_dir_all(&project).unwrap();
+        git(&repo, &["init", "-q"]);
+        std::fs::write(project.join("index.js"), "").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        let worktree = create_agent_worktree(project.to_str().unwrap(), 3).unwrap();
+        let path = Path::new(&worktree.path);
+        assert!(path.join("index.js").exists());
+        assert!(path.ends_with("packages/web"));
+        // Inside the repository's git directory, not next to the project
+        let root = git_stdout(&worktree.path, &["rev-parse", "--show-toplevel"], None).unwrap();
+        assert_eq!(
+            std::fs::canonicalize(root).unwrap(),
+            std::fs::canonicalize(repo.join(".git/anyon-worktrees/agent-run-3")).unwrap()
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
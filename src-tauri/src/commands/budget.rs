@@ -0,0 +1,581 @@
+//! Run budgets
+//! Cost, token, wall-clock and turn limits for Claude runs, enforced live from the run's
+//! stream-json output. A run over its budget is interrupted, a `budget-exceeded` event is
+//! emitted and the reason is stored in `run_budget_events`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::agents::AgentDb;
+use crate::stream_json::StreamMessage;
+
+/// How long an interrupted run may take to exit before it is killed
+///
+/// Windowless processes on Windows get no Ctrl+C, so they are killed right away.
+pub(crate) const INTERRUPT_GRACE_PERIOD: Duration = if cfg!(unix) {
+    Duration::from_secs(10)
+} else {
+    Duration::ZERO
+};
+
+/// Limits for a single run; unset limits don't apply
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunBudget {
+    pub max_cost_usd: Option<f64>,
+    /// Input plus output tokens over all turns
+    pub max_tokens: Option<u64>,
+    pub max_duration_secs: Option<u64>,
+    /// Assistant turns, i.e. model requests; always per run, even in a shared budget
+    pub max_turns: Option<u32>,
+}
+
+impl RunBudget {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_cost_usd.is_none()
+            && self.max_tokens.is_none()
+            && self.max_duration_secs.is_none()
+            && self.max_turns.is_none()
+    }
+
+    /// What is left after `used`, for a run that shares this budget with earlier ones.
+    /// The turn limit stays per run.
+    pub fn remaining(&self, used: &BudgetUsage) -> RunBudget {
+        RunBudget {
+            max_cost_usd: self.max_cost_usd.map(|max| (max - used.cost_usd).max(0.0)),
+            max_tokens: self
+                .max_tokens
+                .map(|max| max.saturating_sub(used.total_tokens)),
+            max_duration_secs: self
+                .max_duration_secs
+                .map(|max| max.saturating_mul(1000).saturating_sub(used.duration_ms) / 1000),
+            max_turns: self.max_turns,
+        }
+    }
+
+    /// Which limit `used` went over, if any
+    pub fn exceeded_by(&self, used: &BudgetUsage) -> Option<BudgetExceeded> {
+        let (limit, reason) =
+            if let Some(max) = self.max_cost_usd.filter(|max| used.cost_usd > *max) {
+                (
+                    "cost",
+                    format!("Cost ${:.4} exceeded the ${:.2} budget", used.cost_usd, max),
+                )
+            } else if let Some(max) = self.max_tokens.filter(|max| used.total_tokens > *max) {
+                (
+                    "tokens",
+                    format!(
+                        "{} tokens exceeded the {} token budget",
+                        used.total_tokens, max
+                    ),
+                )
+            } else if let Some(max) = self.max_turns.filter(|max| used.turns > *max) {
+                (
+                    "turns",
+                    format!("{} turns exceeded the {} turn budget", used.turns, max),
+                )
+            } else if let Some(max) = self
+                .max_duration_secs
+                .filter(|max| used.duration_ms >= max.saturating_mul(1000))
+            {
+                (
+                    "duration",
+                    format!(
+                        "{}s exceeded the {}s time budget",
+                        used.duration_ms / 1000,
+                        max
+                    ),
+                )
+            } else {
+                return None;
+            };
+
+        Some(BudgetExceeded {
+            limit: limit.to_string(),
+            reason,
+            usage: used.clone(),
+        })
+    }
+}
+
+/// What a run has used so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BudgetUsage {
+    pub cost_usd: f64,
+    pub total_tokens: u64,
+    pub turns: u32,
+    pub duration_ms: u64,
+}
+
+/// Which limit a run hit
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetExceeded {
+    pub limit: String, // "cost", "tokens", "duration" or "turns"
+    pub reason: String,
+    pub usage: BudgetUsage,
+}
+
+/// Sums a run's usage from its messages and checks it against the budget
+#[derive(Debug)]
+pub struct BudgetTracker {
+    budget: RunBudget,
+    started: Instant,
+    /// Tokens and estimated cost per assistant message; partial output repeats a message
+    turns: HashMap<String, (u64, f64)>,
+    /// Reported by the result message, replacing the estimate
+    total_cost_usd: Option<f64>,
+    finished: bool,
+    exceeded: Option<BudgetExceeded>,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: RunBudget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            turns: HashMap::new(),
+            total_cost_usd: None,
+            finished: false,
+            exceeded: None,
+        }
+    }
+
+    /// Account for a message; returns the reason the first time the budget is exceeded
+    pub fn observe(&mut self, message: &StreamMessage) -> Option<BudgetExceeded> {
+        match message {
+            StreamMessage::Assistant(turn) => {
+                let key = turn
+                    .message
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| format!("#{}", self.turns.len()));
+                let (tokens, cost) = match &turn.message.usage {
+                    Some(usage) => (
+                        usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0),
+                        turn.message
+                            .model
+                            .as_deref()
+                            .map(|model| super::usage::calculate_cost(model, usage))
+                            .unwrap_or(0.0),
+                    ),
+                    None => (0, 0.0),
+                };
+                self.turns.insert(key, (tokens, cost));
+            }
+            StreamMessage::Result(result) => {
+                self.total_cost_usd = result.total_cost_usd.or(self.total_cost_usd);
+            }
+            _ => {}
+        }
+        self.check()
+    }
+
+    /// Check the wall-clock limit, which quiet runs can hit without sending anything
+    pub fn check_duration(&mut self) -> Option<BudgetExceeded> {
+        self.check()
+    }
+
+    /// Stop checking once the run has ended
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    pub fn exceeded(&self) -> Option<&BudgetExceeded> {
+        self.exceeded.as_ref()
+    }
+
+    /// Time until the wall-clock limit, if there is one
+    pub fn time_left(&self) -> Option<Duration> {
+        self.budget
+            .max_duration_secs
+            .map(|secs| Duration::from_secs(secs).saturating_sub(self.started.elapsed()))
+    }
+
+    pub fn usage(&self) -> BudgetUsage {
+        BudgetUsage {
+            cost_usd: self
+                .total_cost_usd
+                .unwrap_or_else(|| self.turns.values().map(|(_, cost)| cost).sum()),
+            total_tokens: self.turns.values().map(|(tokens, _)| tokens).sum(),
+            turns: self.turns.len() as u32,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+
+    fn check(&mut self) -> Option<BudgetExceeded> {
+        if self.finished || self.exceeded.is_some() {
+            return None;
+        }
+
+        let exceeded = self.budget.exceeded_by(&self.usage())?;
+        self.exceeded = Some(exceeded.clone());
+        Some(exceeded)
+    }
+}
+
+/// Call `on_exceeded` if the run is still going when its time budget runs out
+pub(crate) fn spawn_duration_check<F>(tracker: Arc<Mutex<BudgetTracker>>, on_exceeded: F)
+where
+    F: FnOnce(BudgetExceeded) + Send + 'static,
+{
+    let Some(time_left) = tracker.lock().unwrap().time_left() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(time_left).await;
+        let exceeded = tracker.lock().unwrap().check_duration();
+        if let Some(exceeded) = exceeded {
+            on_exceeded(exceeded);
+        }
+    });
+}
+
+/// A run stopped for going over its budget
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetEvent {
+    pub id: Option<i64>,
+    pub project_path: String,
+    pub session_id: Option<String>,
+    pub agent_run_id: Option<i64>,
+    pub limit: String,
+    pub reason: String,
+    pub cost_usd: f64,
+    pub total_tokens: u64,
+    pub turns: u32,
+    pub duration_ms: u64,
+    pub created_at: Option<String>,
+}
+
+impl BudgetEvent {
+    pub fn new(
+        project_path: &str,
+        session_id: Option<String>,
+        agent_run_id: Option<i64>,
+        exceeded: &BudgetExceeded,
+    ) -> Self {
+        Self {
+            id: None,
+            project_path: project_path.to_string(),
+            session_id,
+            agent_run_id,
+            limit: exceeded.limit.clone(),
+            reason: exceeded.reason.clone(),
+            cost_usd: exceeded.usage.cost_usd,
+            total_tokens: exceeded.usage.total_tokens,
+            turns: exceeded.usage.turns,
+            duration_ms: exceeded.usage.duration_ms,
+            created_at: None,
+        }
+    }
+}
+
+/// Initialize run_budget_events table
+pub fn init_budget_db(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS run_budget_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_path TEXT NOT NULL,
+            session_id TEXT,
+            agent_run_id INTEGER,
+            limit_kind TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            cost_usd REAL NOT NULL,
+            total_tokens INTEGER NOT NULL,
+            turns INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_run_budget_events_project ON run_budget_events(project_path)",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn record_budget_event(conn: &Connection, event: &BudgetEvent) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO run_budget_events (project_path, session_id, agent_run_id, limit_kind, reason, cost_usd, total_tokens, turns, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            event.project_path,
+            event.session_id,
+            event.agent_run_id,
+            event.limit,
+            event.reason,
+            event.cost_usd,
+            event.total_tokens as i64,
+            event.turns,
+            event.duration_ms as i64
+        ],
+    )?;
+    Ok(())
+}
+
+/// Record the event and emit `budget-exceeded`, scoped to the session when it is known
+pub(crate) fn report_budget_exceeded(app: &AppHandle, event: &BudgetEvent) {
+    let db = app.state::<AgentDb>();
+    match db.0.lock() {
+        Ok(conn) => {
+            if let Err(e) = record_budget_event(&conn, event) {
+                log::error!("Failed to record budget event: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to record budget event: {}", e),
+    }
+
+    if let Some(session_id) = &event.session_id {
+        let _ = app.emit(&format!("budget-exceeded:{}", session_id), event);
+    }
+    let _ = app.emit("budget-exceeded", event);
+}
+
+/// Ask a run to stop, as Ctrl+C would
+///
+/// Only the run's own `Child` may kill it afterwards: once the run is reaped its PID can
+/// belong to another process.
+pub(crate) fn send_interrupt(pid: u32) {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill only sends a signal; the caller still owns the unreaped child
+        unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) };
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Interrupt a run, then call `kill` once the grace period is over
+///
+/// `kill` ends the run through its owned `Child` if it is still running, and returns
+/// whether it had to.
+pub(crate) fn interrupt_process<F, Fut>(pid: u32, kill: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = bool> + Send + 'static,
+{
+    send_interrupt(pid);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INTERRUPT_GRACE_PERIOD).await;
+        if kill().await {
+            log::warn!("Run with PID {} ignored the interrupt, killed it", pid);
+        }
+    });
+}
+
+/// Get the budget new runs of an agent start with
+#[tauri::command]
+pub async fn get_agent_budget(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<Option<RunBudget>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let budget: Option<String> = conn
+        .query_row(
+            "SELECT budget FROM agents WHERE id = ?1",
+            params![agent_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    budget
+        .map(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+/// Set or clear the budget new runs of an agent start with
+#[tauri::command]
+pub async fn set_agent_budget(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    budget: Option<RunBudget>,
+) -> Result<(), String> {
+    let budget_json = budget
+        .filter(|budget| !budget.is_unlimited())
+        .map(|budget| serde_json::to_string(&budget))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agents SET budget = ?1 WHERE id = ?2",
+        params![budget_json, agent_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// List the runs stopped for going over budget, newest first
+#[tauri::command]
+pub async fn list_budget_events(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<Vec<BudgetEvent>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_path, session_id, agent_run_id, limit_kind, reason, cost_usd, total_tokens, turns, duration_ms, created_at
+             FROM run_budget_events
+             WHERE ?1 IS NULL OR project_path = ?1
+             ORDER BY id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let events = stmt
+        .query_map(params![project_path], |row| {
+            Ok(BudgetEvent {
+                id: Some(row.get(0)?),
+                project_path: row.get(1)?,
+                session_id: row.get(2)?,
+                agent_run_id: row.get(3)?,
+                limit: row.get(4)?,
+                reason: row.get(5)?,
+                cost_usd: row.get(6)?,
+                total_tokens: row.get::<_, i64>(7)? as u64,
+                turns: row.get(8)?,
+                duration_ms: row.get::<_, i64>(9)? as u64,
+                created_at: Some(row.get(10)?),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream_json::parse_line;
+
+    fn assistant(id: &str, output_tokens: u64) -> StreamMessage {
+        parse_line(&format!(
+            r#"{{"type":"assistant","message":{{"id":"{}","model":"claude-sonnet-4","content":[],"usage":{{"input_tokens":100,"output_tokens":{}}}}}}}"#,
+            id, output_tokens
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn stops_once_turns_or_tokens_run_out() {
+        let mut tracker = BudgetTracker::new(RunBudget {
+            max_turns: Some(2),
+            ..Default::default()
+        });
+        assert!(tracker.observe(&assistant("m1", 10)).is_none());
+        // Partial messages repeat the same turn
+        assert!(tracker.observe(&assistant("m1", 20)).is_none());
+        assert!(tracker.observe(&assistant("m2", 20)).is_none());
+        let exceeded = tracker.observe(&assistant("m3", 20)).unwrap();
+        assert_eq!(exceeded.limit, "turns");
+        assert_eq!(exceeded.usage.total_tokens, 360);
+        // Reported once
+        assert!(tracker.observe(&assistant("m4", 20)).is_none());
+
+        let mut tracker = BudgetTracker::new(RunBudget {
+            max_tokens: Some(200),
+            ..Default::default()
+        });
+        assert!(tracker.observe(&assistant("m1", 50)).is_none());
+        assert_eq!(
+            tracker.observe(&assistant("m2", 50)).unwrap().limit,
+            "tokens"
+        );
+    }
+
+    #[test]
+    fn result_cost_replaces_the_estimate() {
+        let mut tracker = BudgetTracker::new(RunBudget {
+            max_cost_usd: Some(0.5),
+            ..Default::default()
+        });
+        assert!(tracker.observe(&assistant("m1", 10)).is_none());
+        let result =
+            parse_line(r#"{"type":"result","subtype":"success","total_cost_usd":0.75}"#).unwrap();
+        let exceeded = tracker.observe(&result).unwrap();
+        assert_eq!(exceeded.limit, "cost");
+        assert_eq!(exceeded.usage.cost_usd, 0.75);
+    }
+
+    #[test]
+    fn duration_check_fires_once_the_time_is_up() {
+        let budget = RunBudget {
+            max_duration_secs: Some(0),
+            ..Default::default()
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let tracker = Arc::new(Mutex::new(BudgetTracker::new(budget.clone())));
+        spawn_duration_check(tracker.clone(), move |exceeded| {
+            sender.send(exceeded).unwrap();
+        });
+        let exceeded = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(exceeded.limit, "duration");
+        assert_eq!(
+            tracker.lock().unwrap().exceeded().unwrap().limit,
+            "duration"
+        );
+
+        // A run that already ended isn't stopped
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let tracker = Arc::new(Mutex::new(BudgetTracker::new(budget)));
+        tracker.lock().unwrap().finish();
+        spawn_duration_check(tracker, move |exceeded| {
+            sender.send(exceeded).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        // Nor is one without a time limit
+        let tracker = Arc::new(Mutex::new(BudgetTracker::new(RunBudget::default())));
+        spawn_duration_check(tracker, |_| panic!("no time limit"));
+    }
+
+    #[test]
+    fn shared_budgets_shrink_with_use() {
+        let budget = RunBudget {
+            max_cost_usd: Some(1.0),
+            max_tokens: Some(1_000),
+            max_duration_secs: Some(60),
+            max_turns: Some(5),
+        };
+        let used = BudgetUsage {
+            cost_usd: 0.25,
+            total_tokens: 1_200,
+            turns: 9,
+            duration_ms: 20_500,
+        };
+        assert_eq!(
+            budget.remaining(&used),
+            RunBudget {
+                max_cost_usd: Some(0.75),
+                max_tokens: Some(0),
+                max_duration_secs: Some(39),
+                max_turns: Some(5),
+            }
+        );
+        assert_eq!(budget.exceeded_by(&used).unwrap().limit, "tokens");
+        assert!(budget
+            .exceeded_by(&BudgetUsage {
+                duration_ms: 59_999,
+                ..Default::default()
+            })
+            .is_none());
+
+        // Huge limits don't overflow
+        let budget = RunBudget {
+            max_duration_secs: Some(u64::MAX),
+            ..Default::default()
+        };
+        let used = BudgetUsage {
+            duration_ms: 1_000,
+            ..Default::default()
+        };
+        assert!(budget.exceeded_by(&used).is_none());
+        assert_eq!(
+            budget.remaining(&used).max_duration_secs,
+            Some((u64::MAX - 1_000) / 1000)
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Automatic git checkpoints around Claude runs
+//!
+//! Before each run the whole working tree (including uncommitted and untracked files) is
+//! written to a commit built from a temporary index, so the user's branch, index and stash
+//! are never touched. The commit is kept alive by a hidden ref:
+//! `refs/anyon/checkpoints/<session_id>/<commit_sha>`
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::git::{create_hidden_command, git_stdout, run_git};
+
+const CHECKPOINT_REF_PREFIX: &str = "refs/anyon/checkpoints";
+const CHECKPOINT_AUTHOR_NAME: &str = "Anyon Checkpoint";
+const CHECKPOINT_AUTHOR_EMAIL: &str = "checkpoint@anyon.local";
+
+/// A snapshot of the working tree taken before a Claude run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Checkpoint {
+    /// Commit SHA of the snapshot, used to restore it
+    pub id: String,
+    pub session_id: String,
+    /// HEAD of the user's branch when the snapshot was taken
+    pub head_sha: Option<String>,
+    /// First line of the prompt that ran after the snapshot
+    pub label: String,
+    pub created_at: u64, // Unix timestamp
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CheckpointRestoreResult {
+    /// Snapshot of the tree as it was just before restoring, so the restore can be undone
+    pub backup_checkpoint_id: String,
+    pub files_restored: u32,
+    pub files_removed: Vec<String>,
+    /// True if HEAD moved since the checkpoint; commits made by the run are left in place
+    pub head_moved: bool,
+}
+
+fn head_sha(project_path: &str) -> Option<String> {
+    git_stdout(project_path, &["rev-parse", "--verify", "-q", "HEAD"], None).ok()
+}
+
+/// Session IDs become part of a ref name, so only allow characters that are always valid there
+fn validate_session_id(session_id: &str) -> Result<(), String> {
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid session ID: {}", session_id));
+    }
+    Ok(())
+}
+
+/// Write the current working tree to a detached commit and return its SHA
+///
+/// Returns `Ok(None)` when the project isn't a git repository.
+pub fn snapshot_working_tree(project_path: &str, label: &str) -> Result<Option<String>, String> {
+    let is_repo = run_git(project_path, &["rev-parse", "--is-inside-work-tree"], None)
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !is_repo {
+        return Ok(None);
+    }
+
+    // Start from a copy of the real index so unchanged files don't have to be re-hashed
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let index_file = temp_dir.path().join("index");
+    let real_index = git_stdout(project_path, &["rev-parse", "--git-path", "index"], None)?;
+    let real_index = Path::new(project_path).join(real_index);
+    if real_index.exists() {
+        std::fs::copy(&real_index, &index_file).map_err(|e| e.to_string())?;
+    }
+
+    git_stdout(project_path, &["add", "-A"], Some(&index_file))?;
+    let tree = git_stdout(project_path, &["write-tree"], Some(&index_file))?;
+
+    let message = format!("checkpoint: {}", label.lines().next().unwrap_or_default());
+    let head = head_sha(project_path);
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    if let Some(head) = head.as_deref() {
+        args.extend(["-p", head]);
+    }
+
+    let mut cmd = create_hidden_command("git");
+    cmd.args(&args)
+        .current_dir(project_path)
+        .env("GIT_AUTHOR_NAME", CHECKPOINT_AUTHOR_NAME)
+        .env("GIT_AUTHOR_EMAIL", CHECKPOINT_AUTHOR_EMAIL)
+        .env("GIT_COMMITTER_NAME", CHECKPOINT_AUTHOR_NAME)
+        .env("GIT_COMMITTER_EMAIL", CHECKPOINT_AUTHOR_EMAIL);
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute git commit-tree: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git commit-tree failed: {}", stderr.trim()));
+    }
+
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Attach a snapshot to a session under the hidden checkpoint refs
+pub fn save_checkpoint_ref(
+    project_path: &str,
+    session_id: &str,
+    commit_sha: &str,
+) -> Result<(), String> {
+    validate_session_id(session_id)?;
+    let ref_name = format!("{}/{}/{}", CHECKPOINT_REF_PREFIX, session_id, commit_sha);
+    git_stdout(project_path, &["update-ref", &ref_name, commit_sha], None)?;
+    log::info!("Saved checkpoint {} for session {}", commit_sha, session_id);
+    Ok(())
+}
+
+fn find_checkpoint_session(project_path: &str, checkpoint_id: &str) -> Result<String, String> {
+    let refs = git_stdout(
+        project_path,
+        &["for-each-ref", "--format=%(refname)", CHECKPOINT_REF_PREFIX],
+        None,
+    )?;
+    refs.lines()
+        .filter_map(|r| r.strip_prefix(CHECKPOINT_REF_PREFIX))
+        .filter_map(|r| r.trim_start_matches('/').split_once('/'))
+        .find(|(_, sha)| *sha == checkpoint_id)
+        .map(|(session_id, _)| session_id.to_string())
+        .ok_or_else(|| format!("Checkpoint {} not found", checkpoint_id))
+}
+
+/// List a session's checkpoints, newest first
+#[tauri::command]
+pub async fn list_checkpoints(
+    project_path: String,
+    session_id: String,
+) -> Result<Vec<Checkpoint>, String> {
+    validate_session_id(&session_id)?;
+    log::info!("Listing checkpoints for session {}", session_id);
+
+    let prefix = format!("{}/{}/", CHECKPOINT_REF_PREFIX, session_id);
+    let output = git_stdout(
+        &project_path,
+        &[
+            "for-each-ref",
+            "--sort=-creatordate",
+            "--format=%(objectname)|%(parent)|%(creatordate:unix)|%(contents:subject)",
+            &prefix,
+        ],
+        None,
+    )?;
+
+    let checkpoints = output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, '|').collect();
+            if parts.len() != 4 {
+                return None;
+            }
+            Some(Checkpoint {
+                id: parts[0].to_string(),
+                session_id: session_id.clone(),
+                head_sha: Some(parts[1].to_string()).filter(|s| !s.is_empty()),
+                label: parts[3]
+                    .strip_prefix("checkpoint: ")
+                    .unwrap_or(parts[3])
+                    .to_string(),
+                created_at: parts[2].parse().unwrap_or(0),
+            })
+        })
+        .collect();
+
+    Ok(checkpoints)
+}
+
+/// Restore the working tree to a checkpoint
+///
+/// Files are put back exactly as they were before the run, including uncommitted edits, and
+/// files created since are removed. The branch is not moved. The current tree is saved as a
+/// new checkpoint first, so the restore itself can be undone.
+#[tauri::command]
+pub async fn restore_checkpoint(
+    project_path: String,
+    checkpoint_id: String,
+) -> Result<CheckpointRestoreResult, String> {
+    log::info!("Restoring checkpoint {} in {}", checkpoint_id, project_path);
+
+    let session_id = find_checkpoint_session(&project_path, &checkpoint_id)?;
+    let checkpoint_head = git_stdout(
+        &project_path,
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("{}^", checkpoint_id),
+        ],
+        None,
+    )
+    .ok();
+
+    let backup = snapshot_working_tree(
+        &project_path,
+        &format!(
+            "before restoring {}",
+            &checkpoint_id[..checkpoint_id.len().min(7)]
+        ),
+    )?
+    .ok_or("Not a git repository")?;
+    save_checkpoint_ref(&project_path, &session_id, &backup)?;
+
+    // Git reports paths relative to the repository root, which the project may be below
+    let repo_root = git_stdout(&project_path, &["rev-parse", "--show-toplevel"], None)?;
+
+    // Files that exist now but not in the checkpoint were created after it
+    let removed = git_stdout(
+        &repo_root,
+        &[
+            "diff",
+            "--name-only",
+            "--no-renames",
+            "--diff-filter=D",
+            &backup,
+            &checkpoint_id,
+        ],
+        None,
+    )?;
+    let files_removed: Vec<String> = removed
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect();
+    for file in &files_removed {
+        let path = Path::new(&repo_root).join(file);
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    if !files_removed.is_empty() {
+        // Drop index entries the run staged for those files; HEAD's version (if any) stays
+        let mut args = vec!["reset", "-q", "--"];
+        args.extend(files_removed.iter().map(|f| f.as_str()));
+        if let Err(e) = git_stdout(&repo_root, &args, None) {
+            log::warn!("Failed to unstage removed files: {}", e);
+        }
+    }
+
+    // Write the checkpoint's files through a temporary index to leave the real one alone
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let index_file = temp_dir.path().join("index");
+    git_stdout(
+        &repo_root,
+        &["read-tree", &checkpoint_id],
+        Some(&index_file),
+    )?;
+    git_stdout(
+        &repo_root,
+        &["checkout-index", "-a", "-f"],
+        Some(&index_file),
+    )?;
+    let files_restored = git_stdout(&repo_root, &["ls-files"], Some(&index_file))?
+        .lines()
+        .count() as u32;
+
+    let head_moved = head_sha(&project_path) != checkpoint_head;
+    log::info!(
+        "Restored checkpoint {} ({} files, {} removed, head moved: {})",
+        checkpoint_id,
+        files_restored,
+        files_removed.len(),
+        head_moved
+    );
+
+    Ok(CheckpointRestoreResult {
+        backup_checkpoint_id: backup,
+        files_restored,
+        files_removed,
+        head_moved,
+    })
+}
+
+/// Delete all checkpoints of a session
+#[tauri::command]
+pub async fn delete_checkpoints(project_path: String, session_id: String) -> Result<u32, String> {
+    let checkpoints = list_checkpoints(project_path.clone(), session_id.clone()).await?;
+    for checkpoint in &checkpoints {
+        let ref_name = format!("{}/{}/{}", CHECKPOINT_REF_PREFIX, session_id, checkpoint.id);
+        git_stdout(&project_path, &["update-ref", "-d", &ref_name], None)?;
+    }
+    Ok(checkpoints.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_manual_edits_and_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let project = root.to_str().unwrap().to_string();
+        git(root, &["init", "-q"]);
+        std::fs::write(root.join("a.txt"), "committed").unwrap();
+        git(root, &["add", "a.txt"]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        let head = head_sha(&project).unwrap();
+
+        // Manual, uncommitted edits before the run
+        std::fs::write(root.join("a.txt"), "manual edit").unwrap();
+        std::fs::write(root.join("notes.txt"), "untracked").unwrap();
+
+        let sha = snapshot_working_tree(&project, "fix the bug")
+            .unwrap()
+            .unwrap();
+        save_checkpoint_ref(&project, "session-1", &sha).unwrap();
+        assert_eq!(head_sha(&project).unwrap(), head);
+
+        // The run breaks things
+        std::fs::write(root.join("a.txt"), "broken").unwrap();
+        std::fs::write(root.join("new.txt"), "generated").unwrap();
+        std::fs::remove_file(root.join("notes.txt")).unwrap();
+
+        let checkpoints = list_checkpoints(project.clone(), "session-1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].label, "fix the bug");
+        assert_eq!(checkpoints[0].head_sha.as_deref(), Some(head.as_str()));
+
+        let result = restore_checkpoint(project.clone(), sha).await.unwrap();
+        assert_eq!(result.files_removed, vec!["new.txt".to_string()]);
+        assert!(!result.head_moved);
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "manual edit"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("notes.txt")).unwrap(),
+            "untracked"
+        );
+        assert!(!root.join("new.txt").exists());
+
+        // The pre-restore state was kept as another checkpoint
+        let checkpoints = list_checkpoints(project, "session-1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(checkpoints.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_in_a_project_below_the_repo_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let app_dir = root.join("apps").join("web");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        let project = app_dir.to_str().unwrap().to_string();
+        git(root, &["init", "-q"]);
+        std::fs::write(app_dir.join("a.txt"), "committed").unwrap();
+        git(root, &["add", "-A"]);
+        git(root, &["commit", "-q", "-m", "init"]);
+
+        let sha = snapshot_working_tree(&project, "run").unwrap().unwrap();
+        save_checkpoint_ref(&project, "session-1", &sha).unwrap();
+
+        std::fs::write(app_dir.join("a.txt"), "broken").unwrap();
+        std::fs::write(app_dir.join("new.txt"), "generated").unwrap();
+
+        let result = restore_checkpoint(project, sha).await.unwrap();
+        assert_eq!(result.files_removed, vec!["apps/web/new.txt".to_string()]);
+        assert_eq!(
+            std::fs::read_to_string(app_dir.join("a.txt")).unwrap(),
+            "committed"
+        );
+        assert!(!app_dir.join("new.txt").exists());
+    }
+}
@@ -6,7 +6,11 @@ use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
 use super::helpers::{create_system_command, find_claude_binary};
-use super::shared::ClaudeProcessState;
+use super::shared::{ClaudeProcess, ClaudeProcessState, SessionInput};
+use super::{ClaudeRunOptions, PermissionMode, SessionTarget};
+use crate::commands::budget::{self, BudgetEvent, BudgetExceeded, BudgetTracker, RunBudget};
+use crate::commands::permission_prompt::{permission_prompt_for, PermissionPrompt};
+use crate::stream_json::{parse_line, StreamMessage};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -33,6 +37,8 @@ pub async fn execute_claude_code(
     prompt: String,
     model: String,
     execution_mode: Option<String>,
+    input_streaming: Option<bool>,
+    options: Option<ClaudeRunOptions>,
 ) -> Result<(), String> {
     log::info!(
         "Starting new Claude Code session in: {} with model: {}, execution_mode: {:?}",
@@ -41,28 +47,11 @@ pub async fn execute_claude_code(
         execution_mode
     );
 
-    let claude_path = find_claude_binary(&app)?;
-
-    // Build args without -p prompt (prompt will be sent via stdin to avoid Windows batch file escaping issues)
-    let mut args = vec![
-        "--model".to_string(),
-        model.clone(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--include-partial-messages".to_string(),
-        "--verbose".to_string(),
-    ];
-
-    // Add permission mode based on execution_mode
-    if execution_mode.as_deref() == Some("plan") {
-        args.push("--permission-mode".to_string());
-        args.push("plan".to_string());
-    } else {
-        args.push("--dangerously-skip-permissions".to_string());
-    }
-
-    let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    let options = options
+        .unwrap_or_default()
+        .model(model)
+        .input_streaming(input_streaming.unwrap_or(false));
+    start_claude_run(app, project_path, prompt, execution_mode, options).await
 }
 
 #[tauri::command]
@@ -72,6 +61,8 @@ pub async fn continue_claude_code(
     prompt: String,
     model: String,
     execution_mode: Option<String>,
+    input_streaming: Option<bool>,
+    options: Option<ClaudeRunOptions>,
 ) -> Result<(), String> {
     log::info!(
         "Continuing Claude Code conversation in: {} with model: {}, execution_mode: {:?}",
@@ -80,29 +71,12 @@ pub async fn continue_claude_code(
         execution_mode
     );
 
-    let claude_path = find_claude_binary(&app)?;
-
-    // Build args without -p prompt (prompt will be sent via stdin to avoid Windows batch file escaping issues)
-    let mut args = vec![
-        "-c".to_string(), // Continue flag
-        "--model".to_string(),
-        model.clone(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--include-partial-messages".to_string(),
-        "--verbose".to_string(),
-    ];
-
-    // Add permission mode based on execution_mode
-    if execution_mode.as_deref() == Some("plan") {
-        args.push("--permission-mode".to_string());
-        args.push("plan".to_string());
-    } else {
-        args.push("--dangerously-skip-permissions".to_string());
-    }
-
-    let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    let options = options
+        .unwrap_or_default()
+        .model(model)
+        .session(SessionTarget::Continue)
+        .input_streaming(input_streaming.unwrap_or(false));
+    start_claude_run(app, project_path, prompt, execution_mode, options).await
 }
 
 #[tauri::command]
@@ -113,6 +87,8 @@ pub async fn resume_claude_code(
     prompt: String,
     model: String,
     execution_mode: Option<String>,
+    input_streaming: Option<bool>,
+    options: Option<ClaudeRunOptions>,
 ) -> Result<(), String> {
     log::info!(
         "Resuming Claude Code session: {} in: {} with model: {}, execution_mode: {:?}",
@@ -122,30 +98,71 @@ pub async fn resume_claude_code(
         execution_mode
     );
 
-    let claude_path = find_claude_binary(&app)?;
+    let options = options
+        .unwrap_or_default()
+        .model(model)
+        .session(SessionTarget::Resume(session_id))
+        .input_streaming(input_streaming.unwrap_or(false));
+    start_claude_run(app, project_path, prompt, execution_mode, options).await
+}
 
-    // Build args without -p prompt (prompt will be sent via stdin to avoid Windows batch file escaping issues)
-    let mut args = vec![
-        "--resume".to_string(),
-        session_id.clone(),
-        "--model".to_string(),
-        model.clone(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--include-partial-messages".to_string(),
-        "--verbose".to_string(),
-    ];
-
-    // Add permission mode based on execution_mode
-    if execution_mode.as_deref() == Some("plan") {
-        args.push("--permission-mode".to_string());
-        args.push("plan".to_string());
-    } else {
-        args.push("--dangerously-skip-permissions".to_string());
+/// Set how a run's tool calls are checked, from its execution mode
+///
+/// Plan mode stays in plan mode. Otherwise tool calls are approved in the app when
+/// interactive permissions are on and someone is watching. Otherwise they are not checked
+/// at all, unless the run names its allowed tools. The returned prompt must be kept until
+/// the run ends.
+fn with_permissions(
+    app: &AppHandle,
+    project_path: &str,
+    execution_mode: Option<&str>,
+    options: ClaudeRunOptions,
+) -> Result<(ClaudeRunOptions, Option<PermissionPrompt>), String> {
+    if execution_mode == Some("plan") {
+        return Ok((options.permission_mode(PermissionMode::Plan), None));
     }
+    let permission_prompt = if options.unattended {
+        None
+    } else {
+        permission_prompt_for(app, project_path)?
+    };
+    let options = match &permission_prompt {
+        Some(prompt) => options.permission_prompt(prompt),
+        None => options.skip_checks_unless_restricted(),
+    };
+    Ok((options, permission_prompt))
+}
 
-    let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+/// Shared tail of execute, continue and resume
+///
+/// The prompt is sent via stdin rather than `-p` to avoid Windows batch file escaping issues.
+async fn start_claude_run(
+    app: AppHandle,
+    project_path: String,
+    prompt: String,
+    execution_mode: Option<String>,
+    options: ClaudeRunOptions,
+) -> Result<(), String> {
+    let claude_path = find_claude_binary(&app)?;
+    let (options, permission_prompt) = with_permissions(
+        &app,
+        &project_path,
+        execution_mode.as_deref(),
+        options.partial_messages(true),
+    )?;
+
+    let cmd = create_system_command(&claude_path, &options, &project_path);
+    spawn_claude_process(
+        app,
+        cmd,
+        prompt,
+        options.model,
+        project_path,
+        options.input_streaming,
+        options.budget,
+        permission_prompt,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -158,71 +175,117 @@ pub async fn cancel_claude_execution(
         session_id
     );
 
-    let mut killed = false;
-    let mut attempted_methods = Vec::new();
-
-    // Method 1: Try to find and kill via ProcessRegistry using session ID
-    if let Some(sid) = &session_id {
-        let registry = app.state::<crate::process::ProcessRegistryState>();
-        match registry.0.get_claude_session_by_id(sid) {
-            Ok(Some(process_info)) => {
-                log::info!(
-                    "Found process in registry for session {}: run_id={}, PID={}",
-                    sid,
-                    process_info.run_id,
-                    process_info.pid
-                );
-                match registry.0.kill_process(process_info.run_id).await {
-                    Ok(success) => {
-                        if success {
-                            log::info!("Successfully killed process via registry");
-                            killed = true;
-                        } else {
-                            log::warn!("Registry kill returned false");
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to kill via registry: {}", e);
-                    }
+    let claude_state = app.state::<ClaudeProcessState>();
+
+    // Only the named session is cancelled. Without a session ID we can only target runs
+    // that haven't reported one yet, so sessions in other tabs are never touched.
+    let run_ids: Vec<i64> = match &session_id {
+        Some(sid) => {
+            let run_id = match claude_state.find_run_id(sid).await {
+                Some(run_id) => Some(run_id),
+                None => {
+                    let registry = app.state::<crate::process::ProcessRegistryState>();
+                    registry
+                        .0
+                        .get_claude_session_by_id(sid)?
+                        .map(|process_info| process_info.run_id)
                 }
-                attempted_methods.push("registry");
-            }
-            Ok(None) => {
-                log::warn!("Session {} not found in ProcessRegistry", sid);
-            }
-            Err(e) => {
-                log::error!("Error querying ProcessRegistry: {}", e);
-            }
+            };
+            run_id.into_iter().collect()
         }
+        None => {
+            let processes = claude_state.processes.lock().await;
+            processes
+                .iter()
+                .filter(|(_, process)| process.session_id.is_none())
+                .map(|(run_id, _)| *run_id)
+                .collect()
+        }
+    };
+
+    let mut killed = false;
+    for run_id in &run_ids {
+        if kill_claude_run(&app, *run_id).await {
+            killed = true;
+        }
+    }
+
+    // Always emit cancellation events for UI consistency
+    if let Some(sid) = session_id {
+        let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        let _ = app.emit(&format!("claude-complete:{}", sid), false);
+    }
+
+    // Also emit generic events for backward compatibility
+    let _ = app.emit("claude-cancelled", true);
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let _ = app.emit("claude-complete", false);
+
+    if killed {
+        log::info!("Claude process cancellation completed successfully");
+    } else if run_ids.is_empty() {
+        log::warn!("No active Claude process found to cancel");
+    } else {
+        log::warn!(
+            "Claude process cancellation attempted but process may have already exited. Runs: {:?}",
+            run_ids
+        );
     }
 
-    // Method 2: Try the legacy approach via ClaudeProcessState
-    if !killed {
+    Ok(())
+}
+
+/// Cancel every Claude run started in the given project, e.g. when its dev workflow is stopped
+pub(crate) async fn cancel_claude_runs_for_project(
+    app: &AppHandle,
+    project_path: &str,
+) -> Result<(), String> {
+    let runs: Vec<(i64, Option<String>)> = {
         let claude_state = app.state::<ClaudeProcessState>();
-        let mut current_process = claude_state.current_process.lock().await;
-
-        if let Some(mut child) = current_process.take() {
-            // Try to get the PID before killing
-            let pid = child.id();
-            log::info!(
-                "Attempting to kill Claude process via ClaudeProcessState with PID: {:?}",
-                pid
-            );
-
-            // Kill the process
-            match child.kill().await {
-                Ok(_) => {
-                    log::info!("Successfully killed Claude process via ClaudeProcessState");
-                    killed = true;
-                }
-                Err(e) => {
-                    log::error!(
-                        "Failed to kill Claude process via ClaudeProcessState: {}",
-                        e
-                    );
-
-                    // Method 3: If we have a PID, try system kill as last resort
-                    if let Some(pid) = pid {
+        let processes = claude_state.processes.lock().await;
+        processes
+            .iter()
+            .filter(|(_, process)| process.project_path == project_path)
+            .map(|(run_id, process)| (*run_id, process.session_id.clone()))
+            .collect()
+    };
+
+    for (run_id, session_id) in runs {
+        kill_claude_run(app, run_id).await;
+        if let Some(sid) = session_id {
+            let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
+            let _ = app.emit(&format!("claude-complete:{}", sid), false);
+        }
+    }
+
+    Ok(())
+}
+
+/// Kill a single Claude run, leaving every other running session untouched
+async fn kill_claude_run(app: &AppHandle, run_id: i64) -> bool {
+    let claude_state = app.state::<ClaudeProcessState>();
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+
+    let killed = if let Some(mut process) = claude_state.take(run_id).await {
+        let pid = process.child.id();
+        log::info!(
+            "Attempting to kill Claude run {} via ClaudeProcessState with PID: {:?}",
+            run_id,
+            pid
+        );
+
+        match process.child.kill().await {
+            Ok(_) => {
+                log::info!("Successfully killed Claude run {}", run_id);
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to kill Claude run {}: {}", run_id, e);
+
+                // If we have a PID, try system kill as last resort
+                match pid {
+                    Some(pid) => {
                         log::info!("Attempting system kill as last resort for PID: {}", pid);
                         let kill_result = if cfg!(target_os = "windows") {
                             create_hidden_command("taskkill")
@@ -237,48 +300,36 @@ pub async fn cancel_claude_execution(
                         match kill_result {
                             Ok(output) if output.status.success() => {
                                 log::info!("Successfully killed process via system command");
-                                killed = true;
+                                true
                             }
                             Ok(output) => {
                                 let stderr = String::from_utf8_lossy(&output.stderr);
                                 log::error!("System kill failed: {}", stderr);
+                                false
                             }
                             Err(e) => {
                                 log::error!("Failed to execute system kill command: {}", e);
+                                false
                             }
                         }
                     }
+                    None => false,
                 }
             }
-            attempted_methods.push("claude_state");
-        } else {
-            log::warn!("No active Claude process in ClaudeProcessState");
         }
-    }
-
-    if !killed && attempted_methods.is_empty() {
-        log::warn!("No active Claude process found to cancel");
-    }
-
-    // Always emit cancellation events for UI consistency
-    if let Some(sid) = session_id {
-        let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = app.emit(&format!("claude-complete:{}", sid), false);
-    }
-
-    // Also emit generic events for backward compatibility
-    let _ = app.emit("claude-cancelled", true);
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    let _ = app.emit("claude-complete", false);
-
-    if killed {
-        log::info!("Claude process cancellation completed successfully");
-    } else if !attempted_methods.is_empty() {
-        log::warn!("Claude process cancellation attempted but process may have already exited. Attempted methods: {:?}", attempted_methods);
-    }
+    } else {
+        // Not tracked locally any more (already exiting); fall back to the registry's PID kill
+        match registry.0.kill_process(run_id).await {
+            Ok(success) => success,
+            Err(e) => {
+                log::warn!("Failed to kill run {} via registry: {}", run_id, e);
+                false
+            }
+        }
+    };
 
-    Ok(())
+    let _ = registry.0.unregister_process(run_id);
+    killed
 }
 
 #[tauri::command]
@@ -301,33 +352,271 @@ pub async fn get_claude_session_output(
     }
 }
 
+/// How a tracked Claude run ended
+enum RunOutcome {
+    Exited(std::process::ExitStatus),
+    WaitFailed,
+    Cancelled,
+}
+
+/// A user turn as a line of stream-json input
+fn user_message_line(text: &str) -> String {
+    let message = serde_json::json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": [{ "type": "text", "text": text }],
+        },
+    });
+    format!("{}\n", message)
+}
+
+/// A control request asking Claude to stop the current turn, as a line of stream-json input
+fn interrupt_request_line() -> String {
+    let request = serde_json::json!({
+        "type": "control_request",
+        "request_id": uuid::Uuid::new_v4().to_string(),
+        "request": { "subtype": "interrupt" },
+    });
+    format!("{}\n", request)
+}
+
+/// A run's stdin handle and PID, looked up without holding the process map any longer
+async fn session_input(
+    claude_state: &ClaudeProcessState,
+    run_id: i64,
+) -> (Option<SessionInput>, Option<u32>) {
+    let processes = claude_state.processes.lock().await;
+    processes
+        .get(&run_id)
+        .map(|process| (Some(process.stdin.clone()), process.child.id()))
+        .unwrap_or((None, None))
+}
+
+/// Whether a run still takes input, and its PID
+async fn session_input_state(
+    claude_state: &ClaudeProcessState,
+    run_id: i64,
+) -> (bool, Option<u32>) {
+    let (input, pid) = session_input(claude_state, run_id).await;
+    let takes_input = match input {
+        Some(input) => input.lock().await.is_some(),
+        None => false,
+    };
+    (takes_input, pid)
+}
+
+/// Write a line to a run's stdin, if it still takes input
+async fn write_session_input(
+    claude_state: &ClaudeProcessState,
+    run_id: i64,
+    line: &str,
+) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+
+    let (input, _) = session_input(claude_state, run_id).await;
+    let input = input.ok_or("Claude session has already exited")?;
+    let mut input = input.lock().await;
+    let stdin = input
+        .as_mut()
+        .ok_or("Session is not accepting input; resume it to continue")?;
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to session: {}", e))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush session input: {}", e))
+}
+
+/// Close a run's stdin so it exits once it has handled every message already written
+async fn close_session_input(claude_state: &ClaudeProcessState, run_id: i64) -> bool {
+    match session_input(claude_state, run_id).await {
+        (Some(input), _) => input.lock().await.take().is_some(),
+        (None, _) => false,
+    }
+}
+
+/// Send a user message to a running input-streaming session
+///
+/// The message joins the run in progress instead of starting a new `--resume` process. A
+/// session keeps taking input between turns until `end_session_input` is called or it
+/// exits; resume it after that.
+#[tauri::command]
+pub async fn send_session_message(
+    app: AppHandle,
+    session_id: String,
+    text: String,
+) -> Result<(), String> {
+    let claude_state = app.state::<ClaudeProcessState>();
+    let run_id = claude_state
+        .find_run_id(&session_id)
+        .await
+        .ok_or_else(|| format!("No running Claude session: {}", session_id))?;
+
+    log::info!("Sending message to Claude session {}", session_id);
+    write_session_input(&claude_state, run_id, &user_message_line(&text)).await
+}
+
+/// Stop what a running session is doing without cancelling it outright
+///
+/// Input-streaming sessions get an interrupt control request and report a result as usual.
+/// Other runs are sent SIGINT, which ends them.
+#[tauri::command]
+pub async fn interrupt_session(app: AppHandle, session_id: String) -> Result<(), String> {
+    let claude_state = app.state::<ClaudeProcessState>();
+    let run_id = claude_state
+        .find_run_id(&session_id)
+        .await
+        .ok_or_else(|| format!("No running Claude session: {}", session_id))?;
+
+    log::info!("Interrupting Claude session {}", session_id);
+    let (takes_input, pid) = session_input_state(&claude_state, run_id).await;
+    if takes_input {
+        return write_session_input(&claude_state, run_id, &interrupt_request_line()).await;
+    }
+
+    let pid = pid.ok_or("Claude session has already exited")?;
+    #[cfg(unix)]
+    {
+        // SAFETY: kill only sends a signal to the run's own process
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) } != 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        Err("Only input-streaming sessions can be interrupted on this platform".to_string())
+    }
+}
+
+/// Stop taking input for a running input-streaming session
+///
+/// The run finishes the messages it already has and then exits; resume it to continue.
+#[tauri::command]
+pub async fn end_session_input(app: AppHandle, session_id: String) -> Result<(), String> {
+    let claude_state = app.state::<ClaudeProcessState>();
+    let run_id = claude_state
+        .find_run_id(&session_id)
+        .await
+        .ok_or_else(|| format!("No running Claude session: {}", session_id))?;
+
+    if close_session_input(&claude_state, run_id).await {
+        log::info!("Closed input of Claude session {}", session_id);
+    }
+    Ok(())
+}
+
+/// Record and announce that a run went over its budget, then stop it
+///
+/// Input-streaming runs get an interrupt request and their input closed, so they report a
+/// result and exit; other runs are interrupted like `interrupt_session` does and killed if
+/// they don't exit.
+async fn stop_claude_run_over_budget(
+    app: &AppHandle,
+    run_id: i64,
+    project_path: &str,
+    session_id: Option<String>,
+    exceeded: &BudgetExceeded,
+) {
+    log::warn!(
+        "Claude run {} went over budget: {}",
+        run_id,
+        exceeded.reason
+    );
+    budget::report_budget_exceeded(
+        app,
+        &BudgetEvent::new(project_path, session_id, None, exceeded),
+    );
+
+    let claude_state = app.state::<ClaudeProcessState>();
+    let (takes_input, pid) = session_input_state(&claude_state, run_id).await;
+    if takes_input
+        && write_session_input(&claude_state, run_id, &interrupt_request_line())
+            .await
+            .is_ok()
+    {
+        close_session_input(&claude_state, run_id).await;
+        return;
+    }
+    if let Some(pid) = pid {
+        let app = app.clone();
+        budget::interrupt_process(pid, move || async move {
+            let claude_state = app.state::<ClaudeProcessState>();
+            let mut processes = claude_state.processes.lock().await;
+            let Some(process) = processes.get_mut(&run_id) else {
+                return false;
+            };
+            matches!(process.child.try_wait(), Ok(None)) && process.child.start_kill().is_ok()
+        });
+    }
+}
+
 async fn spawn_claude_process(
     app: AppHandle,
     mut cmd: Command,
     prompt: String,
     model: String,
     project_path: String,
+    input_streaming: bool,
+    budget: Option<RunBudget>,
+    permission_prompt: Option<PermissionPrompt>,
 ) -> Result<(), String> {
     use std::sync::Mutex;
     use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
+    // Snapshot the working tree so this turn can be rolled back; a failure here must not
+    // block the run
+    let checkpoint_sha = {
+        let project_path = project_path.clone();
+        let prompt = prompt.clone();
+        tokio::task::spawn_blocking(move || {
+            crate::commands::checkpoint::snapshot_working_tree(&project_path, &prompt)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|snapshot| snapshot)
+    };
+    let checkpoint_sha = match checkpoint_sha {
+        Ok(sha) => sha,
+        Err(e) => {
+            log::warn!("Failed to create checkpoint before Claude run: {}", e);
+            None
+        }
+    };
+
     // Spawn the process
     let mut child = cmd
         .spawn()
         .map_err(|e| format!("Failed to spawn Claude: {}", e))?;
 
     // Write prompt to stdin (avoids Windows batch file escaping issues with special characters)
+    let mut open_stdin = None;
     if let Some(mut stdin) = child.stdin.take() {
+        let input = if input_streaming {
+            user_message_line(&prompt)
+        } else {
+            prompt.clone()
+        };
         stdin
-            .write_all(prompt.as_bytes())
+            .write_all(input.as_bytes())
             .await
             .map_err(|e| format!("Failed to write prompt to stdin: {}", e))?;
         stdin
             .flush()
             .await
             .map_err(|e| format!("Failed to flush stdin: {}", e))?;
-        drop(stdin); // Close stdin to signal EOF
-        log::debug!("Wrote prompt to Claude stdin and closed it");
+        if input_streaming {
+            // Kept for follow-up messages until the input is ended or the run exits
+            open_stdin = Some(stdin);
+            log::debug!("Wrote prompt to Claude stdin, keeping it open");
+        } else {
+            drop(stdin); // Close stdin to signal EOF
+            log::debug!("Wrote prompt to Claude stdin and closed it");
+        }
     }
 
     // Get stdout and stderr
@@ -344,76 +633,169 @@ async fn spawn_claude_process(
 
     // We'll extract the session ID from Claude's init message
     let session_id_holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    let run_id_holder: Arc<Mutex<Option<i64>>> = Arc::new(Mutex::new(None));
+    // ...and the run's cost from its result message
+    let cost_holder: Arc<Mutex<Option<f64>>> = Arc::new(Mutex::new(None));
+    // Sums the run's usage when it has a budget
+    let budget_tracker = budget.map(|budget| Arc::new(Mutex::new(BudgetTracker::new(budget))));
 
-    // Store the child process in the global state (for backward compatibility)
+    // Allocate the run ID up front so the process can be tracked before Claude reports its session
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let run_id = registry.0.generate_id()?;
+    if let Some(prompt) = &permission_prompt {
+        prompt.set_run_id(run_id);
+    }
+
+    // Track the child by run ID so concurrent sessions never replace each other
     let claude_state = app.state::<ClaudeProcessState>();
-    {
-        let mut current_process = claude_state.current_process.lock().await;
-        // If there's already a process running, kill it first
-        if let Some(mut existing_child) = current_process.take() {
-            log::warn!("Killing existing Claude process before starting new one");
-            let _ = existing_child.kill().await;
-        }
-        *current_process = Some(child);
+    claude_state.processes.lock().await.insert(
+        run_id,
+        ClaudeProcess {
+            child,
+            project_path: project_path.clone(),
+            session_id: None,
+            stdin: Arc::new(tokio::sync::Mutex::new(open_stdin)),
+        },
+    );
+    log::info!("Tracking Claude process PID {} as run {}", pid, run_id);
+
+    // Runs can go over their time budget without sending anything
+    if let Some(tracker) = &budget_tracker {
+        let app_handle = app.clone();
+        let project_path = project_path.clone();
+        let session_id_holder = session_id_holder.clone();
+        budget::spawn_duration_check(tracker.clone(), move |exceeded| {
+            tauri::async_runtime::spawn(async move {
+                let session_id = session_id_holder.lock().unwrap().clone();
+                stop_claude_run_over_budget(
+                    &app_handle,
+                    run_id,
+                    &project_path,
+                    session_id,
+                    &exceeded,
+                )
+                .await;
+            });
+        });
     }
 
     // Spawn tasks to read stdout and stderr
     let app_handle = app.clone();
     let session_id_holder_clone = session_id_holder.clone();
-    let run_id_holder_clone = run_id_holder.clone();
-    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let processes_clone = claude_state.processes.clone();
     let registry_clone = registry.0.clone();
     let project_path_clone = project_path.clone();
     let prompt_clone = prompt.clone();
     let model_clone = model.clone();
+    let cost_holder_clone = cost_holder.clone();
+    let checkpoint_sha_clone = checkpoint_sha.clone();
+    // Shared with the wait task, which keeps the run registered until the process exits
+    let permission_prompt = permission_prompt.map(Arc::new);
+    let permission_prompt_clone = permission_prompt.clone();
+    let budget_tracker_clone = budget_tracker.clone();
     let stdout_task = tokio::spawn(async move {
         let mut lines = stdout_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
             log::debug!("Claude stdout: {}", line);
 
-            // Parse the line to check for init message with session ID
-            if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
-                if msg["type"] == "system" && msg["subtype"] == "init" {
-                    if let Some(claude_session_id) = msg["session_id"].as_str() {
+            let message = parse_line(&line);
+
+            // Check for the init message with the session ID and for the final result
+            if let Some(message) = &message {
+                if let Some(claude_session_id) = message.init_session_id() {
+                    let is_new_session = {
                         let mut session_id_guard = session_id_holder_clone.lock().unwrap();
                         if session_id_guard.is_none() {
                             *session_id_guard = Some(claude_session_id.to_string());
-                            log::info!("Extracted Claude session ID: {}", claude_session_id);
-
-                            // Now register with ProcessRegistry using Claude's session ID
-                            match registry_clone.register_claude_session(
-                                claude_session_id.to_string(),
-                                pid,
-                                project_path_clone.clone(),
-                                prompt_clone.clone(),
-                                model_clone.clone(),
+                            true
+                        } else {
+                            false
+                        }
+                    };
+
+                    if is_new_session {
+                        log::info!("Extracted Claude session ID: {}", claude_session_id);
+
+                        if let Some(process) = processes_clone.lock().await.get_mut(&run_id) {
+                            process.session_id = Some(claude_session_id.to_string());
+                        }
+                        if let Some(prompt) = &permission_prompt_clone {
+                            prompt.set_session_id(claude_session_id);
+                        }
+
+                        // The checkpoint is filed under the session once its ID is known
+                        if let Some(sha) = &checkpoint_sha_clone {
+                            match crate::commands::checkpoint::save_checkpoint_ref(
+                                &project_path_clone,
+                                claude_session_id,
+                                sha,
                             ) {
-                                Ok(run_id) => {
-                                    log::info!("Registered Claude session with run_id: {}", run_id);
-                                    let mut run_id_guard = run_id_holder_clone.lock().unwrap();
-                                    *run_id_guard = Some(run_id);
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to register Claude session: {}", e);
+                                Ok(()) => {
+                                    let _ = app_handle.emit(
+                                        &format!("checkpoint-created:{}", claude_session_id),
+                                        sha,
+                                    );
                                 }
+                                Err(e) => log::warn!("Failed to save checkpoint: {}", e),
+                            }
+                        }
+
+                        // Now register with ProcessRegistry using Claude's session ID
+                        match registry_clone.register_claude_session(
+                            run_id,
+                            claude_session_id.to_string(),
+                            pid,
+                            project_path_clone.clone(),
+                            prompt_clone.clone(),
+                            model_clone.clone(),
+                        ) {
+                            Ok(run_id) => {
+                                log::info!("Registered Claude session with run_id: {}", run_id);
+                            }
+                            Err(e) => {
+                                log::error!("Failed to register Claude session: {}", e);
                             }
                         }
                     }
+                } else if let StreamMessage::Result(result) = message {
+                    if let Some(cost) = result.total_cost_usd {
+                        *cost_holder_clone.lock().unwrap() = Some(cost);
+                    }
                 }
             }
 
-            // Store live output in registry if we have a run_id
-            if let Some(run_id) = *run_id_holder_clone.lock().unwrap() {
-                let _ = registry_clone.append_live_output(run_id, &line);
+            // Stop the run as soon as it goes over its budget
+            let exceeded = match (&budget_tracker_clone, &message) {
+                (Some(tracker), Some(message)) => tracker.lock().unwrap().observe(message),
+                _ => None,
+            };
+            if let Some(exceeded) = exceeded {
+                let session_id = session_id_holder_clone.lock().unwrap().clone();
+                stop_claude_run_over_budget(
+                    &app_handle,
+                    run_id,
+                    &project_path_clone,
+                    session_id,
+                    &exceeded,
+                )
+                .await;
             }
 
-            // Emit the line to the frontend with session isolation if we have session ID
+            // Store live output in registry (ignored until the session is registered)
+            let _ = registry_clone.append_live_output(run_id, &line);
+
+            // Emit the line to the frontend with session isolation if we have session ID,
+            // once raw and once parsed
             if let Some(ref session_id) = *session_id_holder_clone.lock().unwrap() {
                 let _ = app_handle.emit(&format!("claude-output:{}", session_id), &line);
+                if let Some(message) = &message {
+                    let _ = app_handle.emit(&format!("claude-message:{}", session_id), message);
+                }
             }
-            // Also emit to the generic event for backward compatibility
+            // Also emit to the generic events for backward compatibility
             let _ = app_handle.emit("claude-output", &line);
+            if let Some(message) = &message {
+                let _ = app_handle.emit("claude-message", message);
+            }
         }
     });
 
@@ -434,9 +816,8 @@ async fn spawn_claude_process(
 
     // Wait for the process to complete
     let app_handle_wait = app.clone();
-    let claude_state_wait = claude_state.current_process.clone();
+    let processes_wait = claude_state.processes.clone();
     let session_id_holder_clone3 = session_id_holder.clone();
-    let run_id_holder_clone2 = run_id_holder.clone();
     let registry_clone2 = registry.0.clone();
     let project_path_for_routing = project_path.clone();
     let prompt_for_routing = prompt.clone();
@@ -444,69 +825,142 @@ async fn spawn_claude_process(
     tokio::spawn(async move {
         let _ = stdout_task.await;
         let _ = stderr_task.await;
-
-        // Get the child from the state to wait on it
-        let mut current_process = claude_state_wait.lock().await;
-        if let Some(mut child) = current_process.take() {
-            match child.wait().await {
-                Ok(status) => {
-                    log::info!("Claude process exited with status: {}", status);
-
-                    // === Dev workflow auto-routing ===
-                    // Use a blocking thread spawn to avoid Send trait issues
-                    let app_for_routing = app_handle_wait.clone();
-                    let project_for_routing = project_path_for_routing.clone();
-                    let prompt_str = prompt_for_routing.clone();
-                    let model_str = model_for_routing.clone();
-                    let success = status.success();
-                    std::thread::spawn(move || {
-                        tauri::async_runtime::block_on(async move {
-                            if let Err(e) = crate::commands::dev_workflow::on_claude_complete(
-                                &app_for_routing,
-                                &project_for_routing,
-                                &prompt_str,
-                                success,
-                                &model_str,
-                            )
-                            .await
-                            {
-                                log::error!("Dev workflow auto-routing failed: {}", e);
-                            }
-                        });
-                    });
-                    // === End auto-routing ===
-
-                    // Add a small delay to ensure all messages are processed
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                    if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                        let _ = app_handle_wait
-                            .emit(&format!("claude-complete:{}", session_id), status.success());
-                    }
-                    // Also emit to the generic event for backward compatibility
-                    let _ = app_handle_wait.emit("claude-complete", status.success());
-                }
-                Err(e) => {
-                    log::error!("Failed to wait for Claude process: {}", e);
-                    // Add a small delay to ensure all messages are processed
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                    if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                        let _ =
-                            app_handle_wait.emit(&format!("claude-complete:{}", session_id), false);
-                    }
-                    // Also emit to the generic event for backward compatibility
-                    let _ = app_handle_wait.emit("claude-complete", false);
+        let (budget_exceeded, total_tokens) = match budget_tracker {
+            Some(tracker) => {
+                let mut tracker = tracker.lock().unwrap();
+                tracker.finish();
+                (
+                    tracker.exceeded().map(|exceeded| exceeded.reason.clone()),
+                    Some(tracker.usage().total_tokens),
+                )
+            }
+            None => (None, None),
+        };
+
+        // Poll only this run's child so the lock is never held while waiting
+        let outcome = loop {
+            {
+                let mut processes = processes_wait.lock().await;
+                match processes.get_mut(&run_id) {
+                    Some(process) => match process.child.try_wait() {
+                        Ok(Some(status)) => {
+                            processes.remove(&run_id);
+                            break RunOutcome::Exited(status);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            log::error!("Failed to wait for Claude process: {}", e);
+                            processes.remove(&run_id);
+                            break RunOutcome::WaitFailed;
+                        }
+                    },
+                    // Removed by cancel_claude_execution
+                    None => break RunOutcome::Cancelled,
                 }
             }
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        };
+
+        let success = match &outcome {
+            RunOutcome::Exited(status) => {
+                log::info!("Claude run {} exited with status: {}", run_id, status);
+                status.success() && budget_exceeded.is_none()
+            }
+            RunOutcome::WaitFailed => false,
+            RunOutcome::Cancelled => {
+                log::info!("Claude run {} was cancelled", run_id);
+                false
+            }
+        };
+
+        if !matches!(outcome, RunOutcome::WaitFailed) {
+            // === Dev workflow auto-routing ===
+            // Routed with this run's own project and prompt, so parallel sessions don't interfere
+            // Use a blocking thread spawn to avoid Send trait issues
+            let app_for_routing = app_handle_wait.clone();
+            let project_for_routing = project_path_for_routing.clone();
+            let prompt_str = prompt_for_routing.clone();
+            let model_str = model_for_routing.clone();
+            let run_summary = crate::commands::dev_workflow::ClaudeRunSummary {
+                success,
+                cancelled: matches!(outcome, RunOutcome::Cancelled),
+                session_id: session_id_holder_clone3.lock().unwrap().clone(),
+                exit_code: match &outcome {
+                    RunOutcome::Exited(status) => status.code(),
+                    _ => None,
+                },
+                cost_usd: *cost_holder.lock().unwrap(),
+                total_tokens,
+                budget_exceeded,
+            };
+            std::thread::spawn(move || {
+                tauri::async_runtime::block_on(async move {
+                    if let Err(e) = crate::commands::dev_workflow::on_claude_complete(
+                        &app_for_routing,
+                        &project_for_routing,
+                        &prompt_str,
+                        &run_summary,
+                        &model_str,
+                    )
+                    .await
+                    {
+                        log::error!("Dev workflow auto-routing failed: {}", e);
+                    }
+                });
+            });
+            // === End auto-routing ===
         }
 
-        // Unregister from ProcessRegistry if we have a run_id
-        if let Some(run_id) = *run_id_holder_clone2.lock().unwrap() {
-            let _ = registry_clone2.unregister_process(run_id);
+        // Cancellation emits its own completion events
+        if !matches!(outcome, RunOutcome::Cancelled) {
+            // Add a small delay to ensure all messages are processed
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            let session_id = session_id_holder_clone3.lock().unwrap().clone();
+            if let Some(session_id) = session_id {
+                let _ = app_handle_wait.emit(&format!("claude-complete:{}", session_id), success);
+            }
+            // Also emit to the generic event for backward compatibility
+            let _ = app_handle_wait.emit("claude-complete", success);
         }
 
-        // Clear the process from state
-        *current_process = None;
+        // Unregister from ProcessRegistry
+        let _ = registry_clone2.unregister_process(run_id);
+        drop(permission_prompt);
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_messages_are_single_stream_json_lines() {
+        let line = user_message_line("fix the \"login\" bug\nthen run tests");
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+
+        let message: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(message["type"], "user");
+        assert_eq!(message["message"]["role"], "user");
+        assert_eq!(message["message"]["content"][0]["type"], "text");
+        assert_eq!(
+            message["message"]["content"][0]["text"],
+            "fix the \"login\" bug\nthen run tests"
+        );
+    }
+
+    #[test]
+    fn interrupt_requests_get_their_own_request_id() {
+        let first = interrupt_request_line();
+        let second = interrupt_request_line();
+        assert!(first.ends_with('\n'));
+
+        let request: serde_json::Value = serde_json::from_str(first.trim_end()).unwrap();
+        assert_eq!(request["type"], "control_request");
+        assert_eq!(request["request"]["subtype"], "interrupt");
+        let other: serde_json::Value = serde_json::from_str(second.trim_end()).unwrap();
+        assert_ne!(request["request_id"], other["request_id"]);
+    }
+}
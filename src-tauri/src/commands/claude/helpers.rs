@@ -6,6 +6,7 @@ use std::process::Stdio;
 use tauri::AppHandle;
 use tokio::process::Command;
 
+use super::run_options::ClaudeRunOptions;
 use super::shared::{JsonlEntry, MessageContent};
 
 #[cfg(target_os = "windows")]
@@ -102,17 +103,15 @@ pub(crate) fn create_command_with_env(program: &str) -> Command {
     crate::claude_binary::create_tokio_command_with_env(program)
 }
 
-/// Creates a system binary command with the given arguments
+/// Creates a system binary command for a run with the given options
 pub(crate) fn create_system_command(
     claude_path: &str,
-    args: Vec<String>,
+    options: &ClaudeRunOptions,
     project_path: &str,
 ) -> Command {
     let mut cmd = create_command_with_env(claude_path);
 
-    for arg in args {
-        cmd.arg(arg);
-    }
+    cmd.args(options.to_args()).envs(&options.env);
 
     cmd.current_dir(project_path)
         .stdin(Stdio::piped()) // Use piped stdin to avoid Windows batch file argument escaping issues
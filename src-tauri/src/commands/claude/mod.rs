@@ -7,7 +7,8 @@
 //! - `helpers`: Internal helper functions
 //! - `projects`: Project management (list, create, get sessions)
 //! - `sessions`: Session management (open, load history)
-//! - `execution`: Claude process execution (execute, continue, resume, cancel)
+//! - `execution`: Claude process execution (execute, continue, resume, cancel, send input)
+//! - `run_options`: Command-line options shared by every way of starting a run
 //! - `filesystem`: File operations (list, search, read)
 //! - `settings`: Settings and configuration (Claude settings, system prompt, git operations)
 
@@ -16,6 +17,7 @@ pub mod execution;
 pub mod filesystem;
 mod helpers;
 pub mod projects;
+pub mod run_options;
 pub mod sessions;
 pub mod settings;
 mod shared;
@@ -26,6 +28,8 @@ pub use shared::{
     Session,
 };
 
+pub use run_options::{ClaudeRunOptions, PermissionMode, SessionTarget};
+
 // Re-export all public functions
 
 // Projects
@@ -37,7 +41,7 @@ pub use sessions::{load_session_history, open_new_session};
 // Execution
 pub use execution::{
     cancel_claude_execution, continue_claude_code, execute_claude_code, get_claude_session_output,
-    list_running_claude_sessions, resume_claude_code,
+    interrupt_session, list_running_claude_sessions, resume_claude_code, send_session_message,
 };
 
 // Filesystem
@@ -216,11 +216,7 @@ pub async fn create_project(path: String) -> Result<Project, String> {
     // This prevents duplicate projects when the same folder is accessed via different paths
     let normalized_path = normalize_path(&path);
 
-    log::info!(
-        "Normalized path: {} -> {}",
-        path,
-        normalized_path
-    );
+    log::info!("Normalized path: {} -> {}", path, normalized_path);
 
     // Encode the path to create a project ID
     // Replace both forward and backward slashes, and colons (for Windows drive letters)
@@ -0,0 +1,273 @@
+//! Command-line options for a Claude Code run
+//!
+//! The Tauri commands, agent runs and web mode all describe their runs with
+//! [`ClaudeRunOptions`] and turn it into CLI arguments with [`ClaudeRunOptions::to_args`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::commands::budget::RunBudget;
+use crate::commands::permission_prompt::PermissionPrompt;
+
+/// Which conversation a run belongs to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SessionTarget {
+    #[default]
+    New,
+    /// The most recent conversation in the project (`-c`)
+    Continue,
+    Resume(String),
+}
+
+/// How the run's tool calls are checked
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PermissionMode {
+    /// The CLI's own checks, narrowed by the allowed and disallowed tools
+    #[default]
+    Default,
+    Plan,
+    SkipChecks,
+}
+
+/// Options for one Claude Code run
+///
+/// The fields a user can set deserialize from the frontend and web requests; the rest are
+/// decided by whoever starts the run, through the builder methods.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClaudeRunOptions {
+    #[serde(skip)]
+    pub model: String,
+    #[serde(skip)]
+    pub session: SessionTarget,
+    /// Passed with `-p`; without it the prompt is written to stdin
+    #[serde(skip)]
+    pub print_prompt: Option<String>,
+    /// Replaces the default system prompt
+    #[serde(skip)]
+    pub system_prompt: Option<String>,
+    #[serde(skip)]
+    pub permission_mode: PermissionMode,
+    /// MCP config and tool name of the in-app permission prompt
+    #[serde(skip)]
+    pub permission_prompt: Option<(String, String)>,
+    #[serde(skip)]
+    pub partial_messages: bool,
+    #[serde(skip)]
+    pub input_streaming: bool,
+    /// Nobody is watching the run, so it never waits on an in-app permission prompt
+    #[serde(skip)]
+    pub unattended: bool,
+
+    /// Directories outside the project the run may access
+    pub add_dirs: Vec<String>,
+    pub allowed_tools: Vec<String>,
+    pub disallowed_tools: Vec<String>,
+    pub max_turns: Option<u32>,
+    pub append_system_prompt: Option<String>,
+    /// Used when the main model is overloaded
+    pub fallback_model: Option<String>,
+    pub mcp_config: Option<String>,
+    /// Extra environment variables for the Claude process
+    pub env: HashMap<String, String>,
+    /// Enforced by the app while the run streams its output; not a CLI flag
+    pub budget: Option<RunBudget>,
+}
+
+impl ClaudeRunOptions {
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn session(mut self, session: SessionTarget) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn print_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.print_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    pub fn permission_mode(mut self, mode: PermissionMode) -> Self {
+        self.permission_mode = mode;
+        self
+    }
+
+    /// Skip the CLI's permission checks unless the run names its allowed tools
+    ///
+    /// Skipping checks would let such a run use every other tool too, so it keeps the
+    /// CLI's own checks instead.
+    pub fn skip_checks_unless_restricted(mut self) -> Self {
+        if self.allowed_tools.is_empty() {
+            self.permission_mode = PermissionMode::SkipChecks;
+        }
+        self
+    }
+
+    /// Ask the app about tool calls the run is not already allowed to make
+    pub(crate) fn permission_prompt(mut self, prompt: &PermissionPrompt) -> Self {
+        self.permission_prompt = Some(prompt.mcp_tool());
+        self
+    }
+
+    pub fn partial_messages(mut self, enabled: bool) -> Self {
+        self.partial_messages = enabled;
+        self
+    }
+
+    /// Read stream-json messages from stdin so more can be sent while the run is going
+    pub fn input_streaming(mut self, enabled: bool) -> Self {
+        self.input_streaming = enabled;
+        self
+    }
+
+    pub fn unattended(mut self, unattended: bool) -> Self {
+        self.unattended = unattended;
+        self
+    }
+
+    pub fn budget(mut self, budget: Option<RunBudget>) -> Self {
+        self.budget = budget.filter(|budget| !budget.is_unlimited());
+        self
+    }
+
+    /// Restrict the run to the given tools, keeping any the user already listed
+    pub fn tools(mut self, allowed: &[String], disallowed: &[String]) -> Self {
+        self.allowed_tools.extend(allowed.iter().cloned());
+        self.disallowed_tools.extend(disallowed.iter().cloned());
+        self
+    }
+
+    /// The CLI arguments for this run; the output is always stream-json
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        match &self.session {
+            SessionTarget::New => {}
+            SessionTarget::Continue => args.push("-c".to_string()),
+            SessionTarget::Resume(session_id) => {
+                args.push("--resume".to_string());
+                args.push(session_id.clone());
+            }
+        }
+        if let Some(prompt) = &self.print_prompt {
+            args.push("-p".to_string());
+            args.push(prompt.clone());
+        }
+        if let Some(system_prompt) = &self.system_prompt {
+            args.push("--system-prompt".to_string());
+            args.push(system_prompt.clone());
+        }
+        if !self.model.is_empty() {
+            args.push("--model".to_string());
+            args.push(self.model.clone());
+        }
+        if let Some(fallback_model) = &self.fallback_model {
+            args.push("--fallback-model".to_string());
+            args.push(fallback_model.clone());
+        }
+
+        args.push("--output-format".to_string());
+        args.push("stream-json".to_string());
+        if self.partial_messages {
+            args.push("--include-partial-messages".to_string());
+        }
+        args.push("--verbose".to_string());
+        if self.input_streaming {
+            args.push("--input-format".to_string());
+            args.push("stream-json".to_string());
+        }
+
+        match self.permission_mode {
+            PermissionMode::Default => {}
+            PermissionMode::Plan => {
+                args.push("--permission-mode".to_string());
+                args.push("plan".to_string());
+            }
+            PermissionMode::SkipChecks => args.push("--dangerously-skip-permissions".to_string()),
+        }
+        if !self.allowed_tools.is_empty() {
+            args.push("--allowedTools".to_string());
+            args.push(self.allowed_tools.join(","));
+        }
+        if !self.disallowed_tools.is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.push(self.disallowed_tools.join(","));
+        }
+
+        for dir in &self.add_dirs {
+            args.push("--add-dir".to_string());
+            args.push(dir.clone());
+        }
+        if let Some(max_turns) = self.max_turns {
+            args.push("--max-turns".to_string());
+            args.push(max_turns.to_string());
+        }
+        if let Some(append_system_prompt) = &self.append_system_prompt {
+            args.push("--append-system-prompt".to_string());
+            args.push(append_system_prompt.clone());
+        }
+        for config in self
+            .mcp_config
+            .iter()
+            .chain(self.permission_prompt.as_ref().map(|(config, _)| config))
+        {
+            args.push("--mcp-config".to_string());
+            args.push(config.clone());
+        }
+        if let Some((_, tool)) = &self.permission_prompt {
+            args.push("--permission-prompt-tool".to_string());
+            args.push(tool.clone());
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_resume_args_with_advanced_flags() {
+        let options: ClaudeRunOptions = serde_json::from_str(
+            r#"{"add_dirs":["../shared"],"disallowed_tools":["WebFetch"],"max_turns":5,"mcp_config":"mcp.json","env":{"FOO":"1"}}"#,
+        )
+        .unwrap();
+        let args = options
+            .model("sonnet")
+            .session(SessionTarget::Resume("abc".to_string()))
+            .permission_mode(PermissionMode::Plan)
+            .to_args();
+
+        assert_eq!(&args[..4], ["--resume", "abc", "--model", "sonnet"]);
+        for pair in [
+            ["--permission-mode", "plan"],
+            ["--disallowedTools", "WebFetch"],
+            ["--add-dir", "../shared"],
+            ["--max-turns", "5"],
+            ["--mcp-config", "mcp.json"],
+        ] {
+            assert!(args.windows(2).any(|w| w == pair), "missing {:?}", pair);
+        }
+        assert!(!args.contains(&"--include-partial-messages".to_string()));
+        assert!(!args.contains(&"--allowedTools".to_string()));
+    }
+
+    #[test]
+    fn skips_checks_only_without_allowed_tools() {
+        let options = ClaudeRunOptions::default().skip_checks_unless_restricted();
+        assert_eq!(options.permission_mode, PermissionMode::SkipChecks);
+
+        let options: ClaudeRunOptions =
+            serde_json::from_str(r#"{"allowed_tools":["Read"]}"#).unwrap();
+        let args = options.skip_checks_unless_restricted().to_args();
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--allowedTools", "Read"]));
+    }
+}
@@ -1,17 +1,47 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::process::Child;
+use tokio::process::{Child, ChildStdin};
 use tokio::sync::Mutex;
 
-/// Global state to track current Claude process
+/// A run's stdin, with its own lock so writing to it never holds the process map
+pub type SessionInput = Arc<Mutex<Option<ChildStdin>>>;
+
+/// A running Claude process owned by `ClaudeProcessState`
+pub struct ClaudeProcess {
+    pub child: Child,
+    pub project_path: String,
+    /// Claude's session ID, known once the `system/init` message arrives
+    pub session_id: Option<String>,
+    /// Open while an input-streaming run still takes user messages
+    pub stdin: SessionInput,
+}
+
+/// Global state to track running Claude processes, keyed by run ID
 pub struct ClaudeProcessState {
-    pub current_process: Arc<Mutex<Option<Child>>>,
+    pub processes: Arc<Mutex<HashMap<i64, ClaudeProcess>>>,
+}
+
+impl ClaudeProcessState {
+    /// Find the run ID of the process serving the given Claude session
+    pub async fn find_run_id(&self, session_id: &str) -> Option<i64> {
+        let processes = self.processes.lock().await;
+        processes
+            .iter()
+            .find(|(_, process)| process.session_id.as_deref() == Some(session_id))
+            .map(|(run_id, _)| *run_id)
+    }
+
+    /// Remove a process from the map, handing ownership to the caller
+    pub async fn take(&self, run_id: i64) -> Option<ClaudeProcess> {
+        self.processes.lock().await.remove(&run_id)
+    }
 }
 
 impl Default for ClaudeProcessState {
     fn default() -> Self {
         Self {
-            current_process: Arc::new(Mutex::new(None)),
+            processes: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -0,0 +1,1289 @@
+//! Dev server launch planning
+//! Detects a project's runner and framework and works out the command, port flags and
+//! readiness signal
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tauri::State;
+
+use super::agents::AgentDb;
+
+/// Framework a project's dev server runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Framework {
+    Vite,
+    NextJs,
+    CreateReactApp,
+    Astro,
+    SvelteKit,
+    Nuxt,
+    Remix,
+    ExpoWeb,
+    Django,
+    Flask,
+    FastApi,
+    Trunk,
+    Cargo,
+    Go,
+    Static,
+    Unknown,
+}
+
+impl Framework {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Framework::Vite => "vite",
+            Framework::NextJs => "next_js",
+            Framework::CreateReactApp => "create_react_app",
+            Framework::Astro => "astro",
+            Framework::SvelteKit => "svelte_kit",
+            Framework::Nuxt => "nuxt",
+            Framework::Remix => "remix",
+            Framework::ExpoWeb => "expo_web",
+            Framework::Django => "django",
+            Framework::Flask => "flask",
+            Framework::FastApi => "fast_api",
+            Framework::Trunk => "trunk",
+            Framework::Cargo => "cargo",
+            Framework::Go => "go",
+            Framework::Static => "static",
+            Framework::Unknown => "unknown",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(JsonValue::String(value.to_string())).ok()
+    }
+
+    /// Output line that means the server is accepting requests (matched after ANSI codes
+    /// are stripped)
+    fn ready_pattern(&self) -> &'static str {
+        match self {
+            Framework::Vite | Framework::SvelteKit => r"Local:\s+https?://|ready in \d+",
+            Framework::NextJs => r"(?i)✓ Ready|ready in \d+|started server on",
+            Framework::CreateReactApp => r"You can now view|Compiled successfully",
+            Framework::Astro => r"(?i)Local\s+https?://|astro\s+v[\d.]+\s+ready",
+            Framework::Nuxt => r"Local:\s+https?://",
+            Framework::Remix => r"Local:\s+https?://|\[remix-serve\]\s+https?://|ready in \d+",
+            Framework::ExpoWeb => r"Web is waiting on|Waiting on https?://",
+            Framework::Django => r"Starting development server at https?://",
+            Framework::Flask => r"Running on https?://",
+            Framework::FastApi => r"Uvicorn running on https?://|Application startup complete",
+            Framework::Trunk => r"(?i)server listening at",
+            Framework::Cargo | Framework::Go => {
+                r"(?i)listening on|server (is )?running|started server|serving on"
+            }
+            // Served in-process; ready as soon as the listener is bound
+            Framework::Static => r"$^",
+            Framework::Unknown => r"(?i)Local:\s+https?://|ready in|listening on|started server on",
+        }
+    }
+}
+
+/// Per-project settings that replace what framework detection would pick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevServerOverrides {
+    pub project_path: String,
+    /// package.json script for Node projects; for other runners, the command line to run
+    /// instead of the default (e.g. `python app.py`)
+    pub script: Option<String>,
+    /// Arguments passed to the script instead of the framework's port/host flags.
+    /// `{port}` and `{host}` are replaced with the assigned values.
+    pub args: Option<Vec<String>>,
+    pub framework: Option<Framework>,
+    pub updated_at: Option<String>,
+}
+
+/// How a project's dev server will be started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchPlan {
+    pub runner: String, // "node", "python", "rust", "go", "static"
+    pub framework: Framework,
+    pub package_manager: String, // Tool that runs the project, e.g. npm, uv, cargo
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub host: String,      // Address the server binds to and the proxy connects to
+    pub port: Option<u16>, // Known up front when the command pins it; otherwise detected
+    pub ready_pattern: String,
+    pub static_root: Option<String>, // Set for sites the app serves itself; no process runs
+}
+
+/// How a project's production build is made and where its output lands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildPlan {
+    pub runner: String,
+    pub framework: Framework,
+    pub program: String, // Empty when the project is served as it is
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub output_dirs: Vec<String>, // Relative to the project, most likely first
+}
+
+/// What a finished build left behind to serve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildOutput {
+    /// A directory of files with an `index.html` at its root
+    Static(PathBuf),
+    /// The `server.js` of a Next.js `output: "standalone"` build
+    NextStandalone(PathBuf),
+}
+
+/// A kind of project the dev server knows how to start
+///
+/// Runners are tried in `RUNNERS` order; the first whose `detect` matches starts the project.
+pub trait DevRunner: Sync {
+    /// Short name, e.g. "node" or "python"
+    fn name(&self) -> &'static str;
+
+    /// Tool that would run the project (package manager, interpreter, ...), or `None` if
+    /// this runner doesn't apply
+    fn detect(&self, project_path: &Path) -> Option<String>;
+
+    /// Tool to use when the framework was forced by an override but `detect` found nothing
+    fn default_tool(&self) -> &'static str;
+
+    fn frameworks(&self) -> &'static [Framework];
+
+    fn detect_framework(&self, project_path: &Path) -> Framework;
+
+    fn plan(
+        &self,
+        project_path: &Path,
+        tool: &str,
+        framework: Framework,
+        overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan;
+
+    /// How to make a production build that can be previewed, if this runner has one
+    fn plan_build(
+        &self,
+        _project_path: &Path,
+        _tool: &str,
+        _framework: Framework,
+    ) -> Option<BuildPlan> {
+        None
+    }
+}
+
+pub static RUNNERS: &[&dyn DevRunner] = &[
+    &NodeRunner,
+    &PythonRunner,
+    &RustRunner,
+    &GoRunner,
+    &StaticRunner,
+];
+
+/// Find the runner for a project along with the tool it would use
+pub fn detect_runner(project_path: &Path) -> Option<(&'static dyn DevRunner, String)> {
+    RUNNERS
+        .iter()
+        .find_map(|runner| runner.detect(project_path).map(|tool| (*runner, tool)))
+}
+
+/// Initialize dev_server_settings table
+pub fn init_dev_launcher_db(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dev_server_settings (
+            project_path TEXT PRIMARY KEY,
+            script TEXT,
+            args TEXT,
+            framework TEXT,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn load_dev_server_overrides(
+    conn: &Connection,
+    project_path: &str,
+) -> rusqlite::Result<Option<DevServerOverrides>> {
+    conn.query_row(
+        "SELECT project_path, script, args, framework, updated_at
+         FROM dev_server_settings WHERE project_path = ?1",
+        params![project_path],
+        |row| {
+            let args: Option<String> = row.get(2)?;
+            let framework: Option<String> = row.get(3)?;
+            Ok(DevServerOverrides {
+                project_path: row.get(0)?,
+                script: row.get(1)?,
+                args: args.and_then(|a| serde_json::from_str(&a).ok()),
+                framework: framework.as_deref().and_then(Framework::parse),
+                updated_at: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn read_package_json(project_path: &Path) -> Option<JsonValue> {
+    let content = std::fs::read_to_string(project_path.join("package.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn has_dependency(package_json: &JsonValue, name: &str) -> bool {
+    ["dependencies", "devDependencies"]
+        .iter()
+        .any(|key| package_json.get(key).and_then(|d| d.get(name)).is_some())
+}
+
+fn has_config(project_path: &Path, stem: &str) -> bool {
+    ["js", "mjs", "cjs", "ts", "mts"]
+        .iter()
+        .any(|ext| project_path.join(format!("{}.{}", stem, ext)).exists())
+}
+
+fn has_script(package_json: Option<&JsonValue>, name: &str) -> bool {
+    package_json
+        .and_then(|p| p.get("scripts"))
+        .and_then(|s| s.get(name))
+        .is_some()
+}
+
+/// Work out a project's framework
+pub fn detect_framework_in(project_path: &Path) -> Framework {
+    detect_runner(project_path)
+        .map(|(runner, _)| runner.detect_framework(project_path))
+        .unwrap_or(Framework::Unknown)
+}
+
+/// Runner, tool and framework for a project, honoring a forced framework
+fn resolve_runner(
+    project_path: &Path,
+    overrides: Option<&DevServerOverrides>,
+) -> Result<(&'static dyn DevRunner, String, Framework), String> {
+    match overrides.and_then(|o| o.framework) {
+        Some(framework) => {
+            let runner = RUNNERS
+                .iter()
+                .find(|r| r.frameworks().contains(&framework))
+                .copied()
+                .unwrap_or(&NodeRunner);
+            let tool = runner
+                .detect(project_path)
+                .unwrap_or_else(|| runner.default_tool().to_string());
+            Ok((runner, tool, framework))
+        }
+        None => {
+            let (runner, tool) = detect_runner(project_path).ok_or_else(|| {
+                format!(
+                    "No supported project type detected in {}",
+                    project_path.display()
+                )
+            })?;
+            Ok((runner, tool, runner.detect_framework(project_path)))
+        }
+    }
+}
+
+/// Build the launch plan for a project
+///
+/// `port` is the port to ask the server for, if any. Overrides replace the script and the
+/// framework's flags; their args only pin the port when they use `{port}`.
+pub fn plan_launch(
+    project_path: &Path,
+    overrides: Option<&DevServerOverrides>,
+    port: Option<u16>,
+) -> Result<LaunchPlan, String> {
+    let (runner, tool, framework) = resolve_runner(project_path, overrides)?;
+    Ok(runner.plan(project_path, &tool, framework, overrides, port))
+}
+
+/// Build plan for a project's production preview
+///
+/// Only the framework is taken from overrides; their script and args are for the dev server.
+pub fn plan_build(
+    project_path: &Path,
+    overrides: Option<&DevServerOverrides>,
+) -> Result<BuildPlan, String> {
+    let (runner, tool, framework) = resolve_runner(project_path, overrides)?;
+    runner
+        .plan_build(project_path, &tool, framework)
+        .ok_or_else(|| {
+            format!(
+                "No production build known for {} projects ({})",
+                runner.name(),
+                framework.as_str()
+            )
+        })
+}
+
+/// Find what a finished build produced, trying the plan's output directories in order
+pub fn find_build_output(project_path: &Path, plan: &BuildPlan) -> Option<BuildOutput> {
+    plan.output_dirs.iter().find_map(|dir| {
+        let dir = project_path.join(dir);
+        if plan.framework == Framework::NextJs && dir.join("server.js").is_file() {
+            Some(BuildOutput::NextStandalone(dir.join("server.js")))
+        } else if dir.join("index.html").is_file() {
+            Some(BuildOutput::Static(dir))
+        } else {
+            None
+        }
+    })
+}
+
+/// Launch plan that serves a build's output on `port`
+pub fn plan_build_server(output: &BuildOutput, port: u16) -> LaunchPlan {
+    let host = "127.0.0.1".to_string();
+    match output {
+        BuildOutput::Static(root) => LaunchPlan {
+            runner: StaticRunner.name().to_string(),
+            framework: Framework::Static,
+            package_manager: StaticRunner.default_tool().to_string(),
+            program: String::new(),
+            args: Vec::new(),
+            env: Vec::new(),
+            host,
+            port: Some(port),
+            ready_pattern: Framework::Static.ready_pattern().to_string(),
+            static_root: Some(root.to_string_lossy().to_string()),
+        },
+        // The standalone server changes into its own directory, so it can run from anywhere
+        BuildOutput::NextStandalone(server_js) => LaunchPlan {
+            runner: NodeRunner.name().to_string(),
+            framework: Framework::NextJs,
+            package_manager: "node".to_string(),
+            program: "node".to_string(),
+            args: vec![server_js.to_string_lossy().to_string()],
+            env: vec![
+                ("PORT".to_string(), port.to_string()),
+                ("HOSTNAME".to_string(), host.clone()),
+                ("NODE_ENV".to_string(), "production".to_string()),
+            ],
+            host,
+            port: Some(port),
+            ready_pattern: Framework::NextJs.ready_pattern().to_string(),
+            static_root: None,
+        },
+    }
+}
+
+/// Override args with `{port}`/`{host}` filled in, and the port if they pinned it
+fn override_args(
+    overrides: Option<&DevServerOverrides>,
+    host: &str,
+    port: Option<u16>,
+) -> Option<(Vec<String>, Option<u16>)> {
+    let args = overrides.and_then(|o| o.args.clone())?;
+    let port_text = port.map(|p| p.to_string()).unwrap_or_default();
+    let mut pinned_port = None;
+    let args = args
+        .into_iter()
+        .map(|arg| {
+            if arg.contains("{port}") && port.is_some() {
+                pinned_port = port;
+            }
+            arg.replace("{port}", &port_text).replace("{host}", host)
+        })
+        .collect();
+    Some((args, pinned_port))
+}
+
+/// Program and leading args from a command-line override such as `python app.py`
+fn override_command(overrides: Option<&DevServerOverrides>) -> Option<(String, Vec<String>)> {
+    let script = overrides.and_then(|o| o.script.as_deref())?;
+    let mut parts = script.split_whitespace().map(String::from);
+    let program = parts.next()?;
+    Some((program, parts.collect()))
+}
+
+/// Free port picked by the OS, for servers that need one up front
+fn ephemeral_port() -> Option<u16> {
+    std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .ok()
+}
+
+fn read_text(project_path: &Path, file: &str) -> String {
+    std::fs::read_to_string(project_path.join(file))
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+// ----------------------------------------------------------------------------
+// Node
+// ----------------------------------------------------------------------------
+
+pub struct NodeRunner;
+
+impl DevRunner for NodeRunner {
+    fn name(&self) -> &'static str {
+        "node"
+    }
+
+    fn detect(&self, project_path: &Path) -> Option<String> {
+        let pm = if project_path.join("bun.lockb").exists() {
+            "bun"
+        } else if project_path.join("pnpm-lock.yaml").exists() {
+            "pnpm"
+        } else if project_path.join("yarn.lock").exists() {
+            "yarn"
+        } else if project_path.join("package-lock.json").exists()
+            || project_path.join("package.json").exists()
+        {
+            "npm"
+        } else {
+            return None;
+        };
+        Some(pm.to_string())
+    }
+
+    fn default_tool(&self) -> &'static str {
+        "npm"
+    }
+
+    fn frameworks(&self) -> &'static [Framework] {
+        &[
+            Framework::Vite,
+            Framework::NextJs,
+            Framework::CreateReactApp,
+            Framework::Astro,
+            Framework::SvelteKit,
+            Framework::Nuxt,
+            Framework::Remix,
+            Framework::ExpoWeb,
+            Framework::Unknown,
+        ]
+    }
+
+    /// Meta-frameworks are checked before Vite since most of them depend on it.
+    fn detect_framework(&self, project_path: &Path) -> Framework {
+        let package_json = read_package_json(project_path).unwrap_or(JsonValue::Null);
+        let dep = |name: &str| has_dependency(&package_json, name);
+
+        if dep("next") || has_config(project_path, "next.config") {
+            Framework::NextJs
+        } else if dep("nuxt") || dep("nuxt3") || has_config(project_path, "nuxt.config") {
+            Framework::Nuxt
+        } else if dep("astro") || has_config(project_path, "astro.config") {
+            Framework::Astro
+        } else if dep("@sveltejs/kit") {
+            Framework::SvelteKit
+        } else if dep("@remix-run/dev") || has_config(project_path, "remix.config") {
+            Framework::Remix
+        } else if dep("expo") && dep("react-native-web") {
+            Framework::ExpoWeb
+        } else if dep("react-scripts") {
+            Framework::CreateReactApp
+        } else if dep("vite") || has_config(project_path, "vite.config") {
+            Framework::Vite
+        } else {
+            Framework::Unknown
+        }
+    }
+
+    fn plan(
+        &self,
+        project_path: &Path,
+        package_manager: &str,
+        framework: Framework,
+        overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan {
+        let package_json = read_package_json(project_path);
+
+        // Remix on Vite takes Vite's flags; the classic compiler serves on $PORT
+        let remix_vite = framework == Framework::Remix && has_config(project_path, "vite.config");
+        let host = match framework {
+            Framework::ExpoWeb | Framework::Unknown => "localhost",
+            _ => "127.0.0.1",
+        };
+
+        let default_script = match framework {
+            Framework::CreateReactApp => "start",
+            Framework::ExpoWeb if has_script(package_json.as_ref(), "web") => "web",
+            Framework::ExpoWeb => "start",
+            _ => "dev",
+        };
+        let script = overrides.and_then(|o| o.script.clone()).unwrap_or_else(|| {
+            if !has_script(package_json.as_ref(), default_script)
+                && has_script(package_json.as_ref(), "start")
+            {
+                "start".to_string()
+            } else {
+                default_script.to_string()
+            }
+        });
+
+        let mut env = Vec::new();
+        let mut script_args: Vec<String> = Vec::new();
+        let mut pinned_port = None;
+
+        if let Some((args, pinned)) = override_args(overrides, host, port) {
+            script_args = args;
+            pinned_port = pinned;
+        } else if let Some(port) = port {
+            let port_text = port.to_string();
+            pinned_port = Some(port);
+            match framework {
+                Framework::Vite | Framework::SvelteKit => {
+                    script_args.extend(
+                        ["--port", &port_text, "--strictPort", "--host", host].map(String::from),
+                    );
+                }
+                Framework::Remix if remix_vite => {
+                    script_args.extend(
+                        ["--port", &port_text, "--strictPort", "--host", host].map(String::from),
+                    );
+                }
+                Framework::NextJs => {
+                    script_args.extend(["-p", &port_text, "-H", host].map(String::from));
+                }
+                Framework::Astro | Framework::Nuxt => {
+                    script_args.extend(["--port", &port_text, "--host", host].map(String::from));
+                }
+                Framework::ExpoWeb => {
+                    if script == "start" && !has_script(package_json.as_ref(), "web") {
+                        script_args.push("--web".to_string());
+                    }
+                    script_args.extend(["--port", &port_text].map(String::from));
+                }
+                Framework::CreateReactApp | Framework::Remix => {
+                    env.push(("PORT".to_string(), port_text.clone()));
+                    env.push(("HOST".to_string(), host.to_string()));
+                }
+                _ => {
+                    script_args.extend(["--port", &port_text].map(String::from));
+                }
+            }
+        }
+
+        // Frameworks that open a browser tab on start; the preview shows the page instead
+        if matches!(framework, Framework::CreateReactApp | Framework::ExpoWeb) {
+            env.push(("BROWSER".to_string(), "none".to_string()));
+        }
+
+        let (program, mut args) = match package_manager {
+            "bun" => ("bun", vec!["run".to_string(), script]),
+            "pnpm" => ("pnpm", vec!["run".to_string(), script]),
+            "yarn" => ("yarn", vec![script]),
+            _ => ("npm", vec!["run".to_string(), script]),
+        };
+        if !script_args.is_empty() {
+            // Only npm needs `--` to hand flags to the script; the others pass it through
+            if program == "npm" {
+                args.push("--".to_string());
+            }
+            args.extend(script_args);
+        }
+
+        LaunchPlan {
+            runner: self.name().to_string(),
+            framework,
+            package_manager: package_manager.to_string(),
+            program: program.to_string(),
+            args,
+            env,
+            host: host.to_string(),
+            port: pinned_port,
+            ready_pattern: framework.ready_pattern().to_string(),
+            static_root: None,
+        }
+    }
+
+    /// Output directories are where each framework's `build` script writes by default
+    fn plan_build(
+        &self,
+        project_path: &Path,
+        package_manager: &str,
+        framework: Framework,
+    ) -> Option<BuildPlan> {
+        if !has_script(read_package_json(project_path).as_ref(), "build") {
+            return None;
+        }
+        let output_dirs: &[&str] = match framework {
+            // `output: "export"` writes `out`; `output: "standalone"` needs its server
+            Framework::NextJs => &["out", ".next/standalone"],
+            Framework::CreateReactApp | Framework::SvelteKit => &["build"],
+            Framework::Remix => &["build/client"],
+            Framework::Nuxt => &[".output/public", "dist"],
+            Framework::Vite | Framework::Astro | Framework::ExpoWeb => &["dist"],
+            _ => &["dist", "build", "out"],
+        };
+        let (program, args) = match package_manager {
+            "bun" | "pnpm" => (package_manager, vec!["run", "build"]),
+            "yarn" => ("yarn", vec!["build"]),
+            _ => ("npm", vec!["run", "build"]),
+        };
+
+        Some(BuildPlan {
+            runner: self.name().to_string(),
+            framework,
+            program: program.to_string(),
+            args: args.into_iter().map(String::from).collect(),
+            env: Vec::new(),
+            output_dirs: output_dirs.iter().map(|d| d.to_string()).collect(),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Python
+// ----------------------------------------------------------------------------
+
+pub struct PythonRunner;
+
+impl PythonRunner {
+    /// Dependency manifests, lowercased and joined, for framework sniffing
+    fn manifests(project_path: &Path) -> String {
+        ["requirements.txt", "pyproject.toml", "Pipfile", "setup.py"]
+            .iter()
+            .map(|f| read_text(project_path, f))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Interpreter to run with: the project's virtualenv, its environment manager, or the
+    /// system Python
+    fn interpreter(project_path: &Path, tool: &str) -> (String, Vec<String>) {
+        let venv_python = if cfg!(target_os = "windows") {
+            "Scripts/python.exe"
+        } else {
+            "bin/python"
+        };
+        for venv in [".venv", "venv", "env"] {
+            let python = project_path.join(venv).join(venv_python);
+            if python.exists() {
+                return (python.to_string_lossy().to_string(), Vec::new());
+            }
+        }
+        match tool {
+            "uv" | "poetry" | "pipenv" => (
+                tool.to_string(),
+                vec!["run".to_string(), "python".to_string()],
+            ),
+            _ if cfg!(target_os = "windows") => ("python".to_string(), Vec::new()),
+            _ => ("python3".to_string(), Vec::new()),
+        }
+    }
+
+    /// `module:app` for uvicorn, from the usual FastAPI entry points
+    fn asgi_app(project_path: &Path) -> &'static str {
+        [
+            ("main.py", "main:app"),
+            ("app/main.py", "app.main:app"),
+            ("src/main.py", "src.main:app"),
+            ("app.py", "app:app"),
+        ]
+        .iter()
+        .find(|(file, _)| project_path.join(file).exists())
+        .map(|(_, app)| *app)
+        .unwrap_or("main:app")
+    }
+}
+
+impl DevRunner for PythonRunner {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn detect(&self, project_path: &Path) -> Option<String> {
+        let tool = if project_path.join("uv.lock").exists() {
+            "uv"
+        } else if project_path.join("poetry.lock").exists() {
+            "poetry"
+        } else if project_path.join("Pipfile").exists() {
+            "pipenv"
+        } else if [
+            "manage.py",
+            "requirements.txt",
+            "pyproject.toml",
+            "setup.py",
+        ]
+        .iter()
+        .any(|f| project_path.join(f).exists())
+        {
+            "python"
+        } else {
+            return None;
+        };
+        Some(tool.to_string())
+    }
+
+    fn default_tool(&self) -> &'static str {
+        "python"
+    }
+
+    fn frameworks(&self) -> &'static [Framework] {
+        &[Framework::Django, Framework::Flask, Framework::FastApi]
+    }
+
+    fn detect_framework(&self, project_path: &Path) -> Framework {
+        let manifests = Self::manifests(project_path);
+        if project_path.join("manage.py").exists() || manifests.contains("django") {
+            Framework::Django
+        } else if manifests.contains("fastapi") {
+            Framework::FastApi
+        } else {
+            Framework::Flask
+        }
+    }
+
+    fn plan(
+        &self,
+        project_path: &Path,
+        tool: &str,
+        framework: Framework,
+        overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan {
+        let host = "127.0.0.1";
+        // Python servers take the port on the command line, so it's always pinned
+        let port = port.unwrap_or(match framework {
+            Framework::Flask => 5000,
+            _ => 8000,
+        });
+        let port_text = port.to_string();
+
+        let (mut program, mut args) = Self::interpreter(project_path, tool);
+        let mut env = vec![("PYTHONUNBUFFERED".to_string(), "1".to_string())];
+        if let Some((command, leading)) = override_command(overrides) {
+            program = command;
+            args = leading;
+        }
+
+        let mut pinned_port = Some(port);
+        if let Some((extra, pinned)) = override_args(overrides, host, Some(port)) {
+            args.extend(extra);
+            pinned_port = pinned;
+        } else if override_command(overrides).is_none() {
+            match framework {
+                Framework::Django => {
+                    args.extend([
+                        "manage.py".to_string(),
+                        "runserver".to_string(),
+                        format!("{}:{}", host, port),
+                    ]);
+                }
+                Framework::FastApi => {
+                    args.extend(
+                        [
+                            "-m",
+                            "uvicorn",
+                            Self::asgi_app(project_path),
+                            "--reload",
+                            "--host",
+                            host,
+                            "--port",
+                            &port_text,
+                        ]
+                        .map(String::from),
+                    );
+                }
+                _ => {
+                    env.push(("FLASK_DEBUG".to_string(), "1".to_string()));
+                    args.extend(
+                        ["-m", "flask", "run", "--host", host, "--port", &port_text]
+                            .map(String::from),
+                    );
+                }
+            }
+        } else {
+            // A custom command gets the port the conventional way
+            env.push(("PORT".to_string(), port_text.clone()));
+            pinned_port = None;
+        }
+
+        LaunchPlan {
+            runner: self.name().to_string(),
+            framework,
+            package_manager: tool.to_string(),
+            program,
+            args,
+            env,
+            host: host.to_string(),
+            port: pinned_port,
+            ready_pattern: framework.ready_pattern().to_string(),
+            static_root: None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Rust and Go
+// ----------------------------------------------------------------------------
+
+/// Plan for servers that read `$PORT` by convention; the actual port is read from their
+/// output since nothing guarantees they honor it
+fn port_env_plan(
+    runner: &dyn DevRunner,
+    tool: &str,
+    framework: Framework,
+    default_args: &[&str],
+    overrides: Option<&DevServerOverrides>,
+    port: Option<u16>,
+    default_port: u16,
+) -> LaunchPlan {
+    let host = "127.0.0.1";
+    let port = port.unwrap_or(default_port);
+    let (program, mut args) = override_command(overrides).unwrap_or_else(|| {
+        (
+            tool.to_string(),
+            default_args.iter().map(|a| a.to_string()).collect(),
+        )
+    });
+    let mut pinned_port = None;
+    if let Some((extra, pinned)) = override_args(overrides, host, Some(port)) {
+        args.extend(extra);
+        pinned_port = pinned;
+    }
+
+    LaunchPlan {
+        runner: runner.name().to_string(),
+        framework,
+        package_manager: tool.to_string(),
+        program,
+        args,
+        env: vec![
+            ("PORT".to_string(), port.to_string()),
+            ("HOST".to_string(), host.to_string()),
+        ],
+        host: host.to_string(),
+        port: pinned_port,
+        ready_pattern: framework.ready_pattern().to_string(),
+        static_root: None,
+    }
+}
+
+pub struct RustRunner;
+
+impl DevRunner for RustRunner {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn detect(&self, project_path: &Path) -> Option<String> {
+        if !project_path.join("Cargo.toml").exists() {
+            return None;
+        }
+        let tool = match self.detect_framework(project_path) {
+            Framework::Trunk => "trunk",
+            _ => "cargo",
+        };
+        Some(tool.to_string())
+    }
+
+    fn default_tool(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn frameworks(&self) -> &'static [Framework] {
+        &[Framework::Trunk, Framework::Cargo]
+    }
+
+    /// Trunk serves WASM frontends from an `index.html` next to `Cargo.toml`
+    fn detect_framework(&self, project_path: &Path) -> Framework {
+        if project_path.join("Trunk.toml").exists() || project_path.join("index.html").exists() {
+            Framework::Trunk
+        } else {
+            Framework::Cargo
+        }
+    }
+
+    fn plan(
+        &self,
+        _project_path: &Path,
+        tool: &str,
+        framework: Framework,
+        overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan {
+        if framework != Framework::Trunk {
+            return port_env_plan(self, "cargo", framework, &["run"], overrides, port, 8000);
+        }
+
+        let host = "127.0.0.1";
+        let port = port.or_else(ephemeral_port).unwrap_or(8080);
+        let (program, mut args) = override_command(overrides)
+            .unwrap_or_else(|| ("trunk".to_string(), vec!["serve".to_string()]));
+        let mut pinned_port = Some(port);
+        if let Some((extra, pinned)) = override_args(overrides, host, Some(port)) {
+            args.extend(extra);
+            pinned_port = pinned;
+        } else {
+            args.extend(["--port", &port.to_string(), "--address", host].map(String::from));
+        }
+
+        LaunchPlan {
+            runner: self.name().to_string(),
+            framework,
+            package_manager: tool.to_string(),
+            program,
+            args,
+            env: Vec::new(),
+            host: host.to_string(),
+            port: pinned_port,
+            ready_pattern: framework.ready_pattern().to_string(),
+            static_root: None,
+        }
+    }
+
+    fn plan_build(
+        &self,
+        _project_path: &Path,
+        _tool: &str,
+        framework: Framework,
+    ) -> Option<BuildPlan> {
+        (framework == Framework::Trunk).then(|| BuildPlan {
+            runner: self.name().to_string(),
+            framework,
+            program: "trunk".to_string(),
+            args: vec!["build".to_string(), "--release".to_string()],
+            env: Vec::new(),
+            output_dirs: vec!["dist".to_string()],
+        })
+    }
+}
+
+pub struct GoRunner;
+
+impl DevRunner for GoRunner {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn detect(&self, project_path: &Path) -> Option<String> {
+        project_path
+            .join("go.mod")
+            .exists()
+            .then(|| "go".to_string())
+    }
+
+    fn default_tool(&self) -> &'static str {
+        "go"
+    }
+
+    fn frameworks(&self) -> &'static [Framework] {
+        &[Framework::Go]
+    }
+
+    fn detect_framework(&self, _project_path: &Path) -> Framework {
+        Framework::Go
+    }
+
+    fn plan(
+        &self,
+        _project_path: &Path,
+        tool: &str,
+        framework: Framework,
+        overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan {
+        port_env_plan(self, tool, framework, &["run", "."], overrides, port, 8080)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Static sites
+// ----------------------------------------------------------------------------
+
+/// Plain HTML sites, served by the app itself
+pub struct StaticRunner;
+
+impl StaticRunner {
+    fn root(project_path: &Path) -> Option<std::path::PathBuf> {
+        ["", "public", "site", "www"]
+            .iter()
+            .map(|dir| project_path.join(dir))
+            .find(|dir| dir.join("index.html").exists())
+    }
+}
+
+impl DevRunner for StaticRunner {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    fn detect(&self, project_path: &Path) -> Option<String> {
+        Self::root(project_path).map(|_| "static".to_string())
+    }
+
+    fn default_tool(&self) -> &'static str {
+        "static"
+    }
+
+    fn frameworks(&self) -> &'static [Framework] {
+        &[Framework::Static]
+    }
+
+    fn detect_framework(&self, _project_path: &Path) -> Framework {
+        Framework::Static
+    }
+
+    fn plan(
+        &self,
+        project_path: &Path,
+        tool: &str,
+        framework: Framework,
+        _overrides: Option<&DevServerOverrides>,
+        port: Option<u16>,
+    ) -> LaunchPlan {
+        let root = Self::root(project_path).unwrap_or_else(|| project_path.to_path_buf());
+        LaunchPlan {
+            runner: self.name().to_string(),
+            framework,
+            package_manager: tool.to_string(),
+            program: String::new(),
+            args: Vec::new(),
+            env: Vec::new(),
+            host: "127.0.0.1".to_string(),
+            port: port.or_else(ephemeral_port),
+            ready_pattern: framework.ready_pattern().to_string(),
+            static_root: Some(root.to_string_lossy().to_string()),
+        }
+    }
+
+    /// Nothing to build; the site is served as it is
+    fn plan_build(
+        &self,
+        project_path: &Path,
+        _tool: &str,
+        framework: Framework,
+    ) -> Option<BuildPlan> {
+        let root = Self::root(project_path)?;
+        let relative = root.strip_prefix(project_path).unwrap_or(&root);
+        Some(BuildPlan {
+            runner: self.name().to_string(),
+            framework,
+            program: String::new(),
+            args: Vec::new(),
+            env: Vec::new(),
+            output_dirs: vec![relative.to_string_lossy().to_string()],
+        })
+    }
+}
+
+/// Detect the framework a project uses
+#[tauri::command]
+pub async fn detect_dev_framework(project_path: String) -> Result<Framework, String> {
+    Ok(detect_framework_in(Path::new(&project_path)))
+}
+
+/// Get a project's dev server overrides, if it has any
+#[tauri::command]
+pub async fn get_dev_server_settings(
+    db: State<'_, AgentDb>,
+    project_path: String,
+) -> Result<Option<DevServerOverrides>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_dev_server_overrides(&conn, &project_path).map_err(|e| e.to_string())
+}
+
+/// Save a project's dev server overrides; unset fields fall back to detection
+#[tauri::command]
+pub async fn save_dev_server_settings(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    script: Option<String>,
+    args: Option<Vec<String>>,
+    framework: Option<Framework>,
+) -> Result<DevServerOverrides, String> {
+    let script = script.filter(|s| !s.trim().is_empty());
+    let args_json = args
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO dev_server_settings (project_path, script, args, framework, updated_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))
+         ON CONFLICT(project_path) DO UPDATE SET
+            script = excluded.script,
+            args = excluded.args,
+            framework = excluded.framework,
+            updated_at = excluded.updated_at",
+        params![
+            project_path,
+            script,
+            args_json,
+            framework.map(|f| f.as_str())
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_dev_server_overrides(&conn, &project_path)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Failed to save dev server settings".to_string())
+}
+
+/// Remove a project's dev server overrides
+#[tauri::command]
+pub async fn delete_dev_server_settings(
+    db: State<'_, AgentDb>,
+    project_path: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM dev_server_settings WHERE project_path = ?1",
+        params![project_path],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_with(package_json: &str, files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("package.json"), package_json).unwrap();
+        for file in files {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn detects_meta_frameworks_before_vite() {
+        let kit = project_with(
+            r#"{"devDependencies": {"@sveltejs/kit": "2", "vite": "5"}}"#,
+            &[],
+        );
+        assert_eq!(detect_framework_in(kit.path()), Framework::SvelteKit);
+
+        let next = project_with(r#"{"dependencies": {}}"#, &["next.config.mjs"]);
+        assert_eq!(detect_framework_in(next.path()), Framework::NextJs);
+
+        let cra = project_with(r#"{"dependencies": {"react-scripts": "5"}}"#, &[]);
+        assert_eq!(detect_framework_in(cra.path()), Framework::CreateReactApp);
+    }
+
+    #[test]
+    fn plans_framework_port_flags() {
+        let vite = project_with(
+            r#"{"scripts": {"dev": "vite"}, "devDependencies": {"vite": "5"}}"#,
+            &[],
+        );
+        let plan = plan_launch(vite.path(), None, Some(3100)).unwrap();
+        assert_eq!(plan.framework, Framework::Vite);
+        assert_eq!(
+            plan.args,
+            [
+                "run",
+                "dev",
+                "--",
+                "--port",
+                "3100",
+                "--strictPort",
+                "--host",
+                "127.0.0.1"
+            ]
+        );
+        assert_eq!(plan.port, Some(3100));
+
+        let cra = project_with(
+            r#"{"scripts": {"start": "react-scripts start"}, "dependencies": {"react-scripts": "5"}}"#,
+            &[],
+        );
+        let plan = plan_launch(cra.path(), None, Some(3100)).unwrap();
+        assert_eq!(plan.args, ["run", "start"]);
+        assert!(plan.env.contains(&("PORT".to_string(), "3100".to_string())));
+    }
+
+    #[test]
+    fn overrides_replace_script_and_args() {
+        let dir = project_with(r#"{"devDependencies": {"vite": "5"}}"#, &["yarn.lock"]);
+        let overrides = DevServerOverrides {
+            project_path: dir.path().to_string_lossy().to_string(),
+            script: Some("serve".to_string()),
+            args: Some(vec!["--listen".to_string(), "{host}:{port}".to_string()]),
+            framework: None,
+            updated_at: None,
+        };
+        let plan = plan_launch(dir.path(), Some(&overrides), Some(4000)).unwrap();
+        assert_eq!(plan.args, ["serve", "--listen", "127.0.0.1:4000"]);
+        assert_eq!(plan.port, Some(4000));
+
+        let without_port = DevServerOverrides {
+            args: Some(vec!["--open".to_string()]),
+            ..overrides
+        };
+        assert_eq!(
+            plan_launch(dir.path(), Some(&without_port), Some(4000))
+                .unwrap()
+                .port,
+            None
+        );
+    }
+
+    #[test]
+    fn plans_non_node_projects() {
+        let django = tempfile::tempdir().unwrap();
+        std::fs::write(django.path().join("manage.py"), "").unwrap();
+        std::fs::write(django.path().join("poetry.lock"), "").unwrap();
+        let plan = plan_launch(django.path(), None, Some(8100)).unwrap();
+        assert_eq!(plan.runner, "python");
+        assert_eq!(plan.framework, Framework::Django);
+        assert_eq!(plan.program, "poetry");
+        assert_eq!(
+            plan.args,
+            ["run", "python", "manage.py", "runserver", "127.0.0.1:8100"]
+        );
+        assert_eq!(plan.port, Some(8100));
+
+        let go = tempfile::tempdir().unwrap();
+        std::fs::write(go.path().join("go.mod"), "module example.com/app").unwrap();
+        let plan = plan_launch(go.path(), None, Some(8100)).unwrap();
+        assert_eq!((plan.program.as_str(), plan.port), ("go", None));
+        assert!(plan.env.contains(&("PORT".to_string(), "8100".to_string())));
+
+        let site = tempfile::tempdir().unwrap();
+        std::fs::create_dir(site.path().join("public")).unwrap();
+        std::fs::write(site.path().join("public/index.html"), "<html></html>").unwrap();
+        let plan = plan_launch(site.path(), None, Some(8100)).unwrap();
+        assert_eq!(plan.framework, Framework::Static);
+        assert_eq!(
+            plan.static_root.as_deref(),
+            Some(site.path().join("public").to_string_lossy().as_ref())
+        );
+
+        assert!(plan_launch(tempfile::tempdir().unwrap().path(), None, None).is_err());
+    }
+
+    #[test]
+    fn plans_builds_and_finds_their_output() {
+        let vite = project_with(
+            r#"{"scripts": {"build": "vite build"}, "devDependencies": {"vite": "5"}}"#,
+            &["pnpm-lock.yaml"],
+        );
+        let plan = plan_build(vite.path(), None).unwrap();
+        assert_eq!(plan.program, "pnpm");
+        assert_eq!(plan.args, ["run", "build"]);
+        assert_eq!(find_build_output(vite.path(), &plan), None);
+        std::fs::create_dir(vite.path().join("dist")).unwrap();
+        std::fs::write(vite.path().join("dist/index.html"), "").unwrap();
+        assert_eq!(
+            find_build_output(vite.path(), &plan),
+            Some(BuildOutput::Static(vite.path().join("dist")))
+        );
+
+        let next = project_with(
+            r#"{"scripts": {"build": "next build"}, "dependencies": {"next": "14"}}"#,
+            &[],
+        );
+        let plan = plan_build(next.path(), None).unwrap();
+        std::fs::create_dir_all(next.path().join(".next/standalone")).unwrap();
+        std::fs::write(next.path().join(".next/standalone/server.js"), "").unwrap();
+        let output = find_build_output(next.path(), &plan).unwrap();
+        let server = plan_build_server(&output, 4100);
+        assert_eq!(server.program, "node");
+        assert!(server
+            .env
+            .contains(&("PORT".to_string(), "4100".to_string())));
+
+        let no_script = project_with(r#"{"devDependencies": {"vite": "5"}}"#, &[]);
+        assert!(plan_build(no_script.path(), None).is_err());
+    }
+}
@@ -1,17 +1,28 @@
 //! Dev Server with HTML injection proxy
 //! Provides script injection for element selector functionality
 
-use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
 use std::net::{TcpListener, TcpStream};
 use std::path::Path;
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStderr, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::agents::AgentDb;
+use super::dev_launcher::{
+    detect_runner, find_build_output, load_dev_server_overrides, plan_build, plan_build_server,
+    plan_launch, BuildOutput, BuildPlan, LaunchPlan,
+};
+use super::dev_server_logs::{
+    dev_server_log_tail, record_dev_server_lines, DevServerLogEntry, DevServerLogWriter,
+};
+use super::port_registry::{allocate_port, allocate_port_async, reserve_project_ports, PortKind};
 
 // ============================================================================
 // Types
@@ -25,15 +36,44 @@ pub struct DevServerInfo {
     pub original_url: Option<String>,
     pub proxy_port: Option<u16>,
     pub proxy_url: Option<String>,
+    #[serde(default)]
+    pub status: String, // "building", "running", "restarting", "exited", "crashed", "failed"
+    #[serde(default)]
+    pub mode: String, // "dev", or "build" for a production build preview
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub restart_count: u32,
+    /// Last lines of output, oldest first; only filled in by `get_dev_server_info`
+    #[serde(default)]
+    pub log_tail: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DevServerOutput {
     pub project_path: String,
-    pub output_type: String, // "stdout", "stderr", "info", "port-detected", "error"
+    pub output_type: String, // "stdout", "stderr", "info", "port-detected", "ready", "exited", "crashed", "error"
     pub message: String,
     pub port: Option<u16>,
     pub proxy_url: Option<String>,
+    /// The log entry's seq for "stdout" and "stderr" lines
+    #[serde(default)]
+    pub seq: Option<u64>,
+}
+
+/// A console message or uncaught error reported by the script injected into the preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewConsoleEntry {
+    #[serde(default)]
+    pub seq: u64, // Assigned by the backend, increasing per project
+    pub kind: String,  // "console", "error", "unhandledrejection"
+    pub level: String, // "log", "info", "warn", "error", "debug"
+    pub message: String,
+    pub stack: Option<String>,
+    pub source: Option<String>, // Script URL for uncaught errors
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub page_url: Option<String>,
+    pub timestamp: i64, // Unix ms, from the page
 }
 
 struct DevServerState {
@@ -41,11 +81,12 @@ struct DevServerState {
 }
 
 struct DevServerEntry {
+    id: u64, // Tells a supervisor whether the entry is still the one it started
     process: Option<Child>,
     #[allow(dead_code)]
     proxy_handle: Option<thread::JoinHandle<()>>,
     info: DevServerInfo,
-    stop_flag: Arc<Mutex<bool>>,
+    proxy_cancel: CancellationToken,
 }
 
 // Global state
@@ -53,6 +94,61 @@ lazy_static::lazy_static! {
     static ref DEV_SERVERS: Arc<Mutex<DevServerState>> = Arc::new(Mutex::new(DevServerState {
         servers: HashMap::new(),
     }));
+    static ref PREVIEW_CONSOLE_LOGS: Mutex<HashMap<String, PreviewConsoleBuffer>> =
+        Mutex::new(HashMap::new());
+}
+
+static NEXT_DEV_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Lines of output kept per dev server for `get_dev_server_info`
+const DEV_SERVER_LOG_TAIL_LINES: usize = 200;
+/// Crashes in a row that get restarted before the supervisor gives up
+const MAX_DEV_SERVER_RESTARTS: u32 = 5;
+/// A run that stays up this long is no longer counted as a crash loop
+const DEV_SERVER_STABLE_RUN: Duration = Duration::from_secs(30);
+/// How long a dev server gets to exit after SIGTERM before it's killed
+#[cfg(unix)]
+const DEV_SERVER_KILL_GRACE: Duration = Duration::from_secs(3);
+
+/// Entries kept per project; older ones are dropped first
+const PREVIEW_CONSOLE_CAPACITY: usize = 1000;
+
+/// Longest message or stack kept, so one huge object dump can't crowd out the rest
+const PREVIEW_CONSOLE_MAX_TEXT: usize = 16 * 1024;
+
+#[derive(Default)]
+struct PreviewConsoleBuffer {
+    next_seq: u64,
+    entries: VecDeque<PreviewConsoleEntry>,
+}
+
+fn truncate_text(text: &mut String) {
+    if text.len() > PREVIEW_CONSOLE_MAX_TEXT {
+        let mut end = PREVIEW_CONSOLE_MAX_TEXT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("…[truncated]");
+    }
+}
+
+/// Append entries reported by a project's preview to its ring buffer
+fn record_preview_console(project_path: &str, entries: Vec<PreviewConsoleEntry>) {
+    let mut logs = PREVIEW_CONSOLE_LOGS.lock().unwrap();
+    let buffer = logs.entry(project_path.to_string()).or_default();
+    for mut entry in entries {
+        truncate_text(&mut entry.message);
+        if let Some(stack) = entry.stack.as_mut() {
+            truncate_text(stack);
+        }
+        buffer.next_seq += 1;
+        entry.seq = buffer.next_seq;
+        if buffer.entries.len() == PREVIEW_CONSOLE_CAPACITY {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(entry);
+    }
 }
 
 // ============================================================================
@@ -60,38 +156,101 @@ lazy_static::lazy_static! {
 // ============================================================================
 
 const ELEMENT_SELECTOR_SCRIPT: &str = r####"
-// Console capture - iframe 콘솔 로그를 부모로 전달
+// Console capture - iframe 콘솔 로그를 부모로 전달하고 백엔드 수집기로도 보고
 (() => {
   if (window.__anyonConsoleCapture__) return;
   window.__anyonConsoleCapture__ = true;
 
+  const COLLECTOR_URL = '/__anyon__/console';
+  const sendFetch = window.fetch.bind(window);
+  let queue = [];
+  let flushTimer = null;
+
+  const flush = () => {
+    flushTimer = null;
+    if (!queue.length) return;
+    const batch = queue;
+    queue = [];
+    try {
+      sendFetch(COLLECTOR_URL, { method: 'POST', body: JSON.stringify(batch), keepalive: true })
+        .catch(() => {});
+    } catch (e) {}
+  };
+
+  const report = (entry) => {
+    queue.push(Object.assign({ page_url: location.href, timestamp: Date.now() }, entry));
+    if (queue.length >= 50) {
+      flush();
+    } else if (!flushTimer) {
+      flushTimer = setTimeout(flush, 250);
+    }
+  };
+
+  const serialize = (arg) => {
+    try {
+      if (arg === null) return 'null';
+      if (arg === undefined) return 'undefined';
+      if (typeof arg === 'function') return '[Function]';
+      if (typeof arg === 'object') {
+        if (arg instanceof HTMLElement) return '<' + arg.tagName.toLowerCase() + '>';
+        if (arg instanceof Error) return arg.stack || arg.message;
+        return JSON.stringify(arg, null, 2);
+      }
+      return String(arg);
+    } catch (e) { return '[Unserializable]'; }
+  };
+
   const methods = ['log', 'warn', 'error', 'info', 'debug'];
   methods.forEach(method => {
     const original = console[method];
     console[method] = function(...args) {
       try {
+        const serialized = args.map(serialize);
         window.parent.postMessage({
           type: 'anyon-console',
           level: method,
-          args: args.map(arg => {
-            try {
-              if (arg === null) return 'null';
-              if (arg === undefined) return 'undefined';
-              if (typeof arg === 'function') return '[Function]';
-              if (typeof arg === 'object') {
-                if (arg instanceof HTMLElement) return '<' + arg.tagName.toLowerCase() + '>';
-                if (arg instanceof Error) return arg.stack || arg.message;
-                return JSON.stringify(arg, null, 2);
-              }
-              return String(arg);
-            } catch (e) { return '[Unserializable]'; }
-          }),
+          args: serialized,
           timestamp: Date.now(),
         }, '*');
+        const error = args.find(arg => arg instanceof Error);
+        report({
+          kind: 'console',
+          level: method,
+          message: serialized.join(' '),
+          stack: error ? error.stack : undefined,
+        });
       } catch (e) {}
       original.apply(console, args);
     };
   });
+
+  window.addEventListener('error', (event) => {
+    try {
+      report({
+        kind: 'error',
+        level: 'error',
+        message: event.message || serialize(event.error),
+        stack: event.error && event.error.stack,
+        source: event.filename || undefined,
+        line: event.lineno || undefined,
+        column: event.colno || undefined,
+      });
+    } catch (e) {}
+  });
+
+  window.addEventListener('unhandledrejection', (event) => {
+    try {
+      const reason = event.reason;
+      report({
+        kind: 'unhandledrejection',
+        level: 'error',
+        message: reason instanceof Error ? reason.message : serialize(reason),
+        stack: reason instanceof Error ? reason.stack : undefined,
+      });
+    } catch (e) {}
+  });
+
+  window.addEventListener('pagehide', flush);
 })();
 
 // Element selector
@@ -174,6 +333,67 @@ const ELEMENT_SELECTOR_SCRIPT: &str = r####"
     return path.join(" > ");
   }
 
+  // Where the element comes from, from dev-mode metadata, plus the components around it
+  function getSourceInfo(el) {
+    let source = null;
+    const components = [];
+    const addComponent = (name) => {
+      if (name && components.length < 5 && !components.includes(name)) components.push(name);
+    };
+    const parseLoc = (loc, origin) => {
+      const m = String(loc).match(/^(.*?):(\d+)(?::(\d+))?$/);
+      return m ? { file: m[1], line: +m[2], column: m[3] ? +m[3] : null, origin } : null;
+    };
+
+    // Attributes added by build transforms (react-dev-inspector, vite-plugin-vue-inspector, ...)
+    for (let node = el; node && node.nodeType === Node.ELEMENT_NODE && !source; node = node.parentElement) {
+      const d = node.dataset || {};
+      if (d.sourceFile) {
+        source = { file: d.sourceFile, line: +d.sourceLine || null, column: +d.sourceColumn || null, origin: "data-attribute" };
+      } else if (d.inspectorRelativePath) {
+        source = { file: d.inspectorRelativePath, line: +d.inspectorLine || null, column: +d.inspectorColumn || null, origin: "data-attribute" };
+      } else if (d.vInspector) {
+        source = parseLoc(d.vInspector, "data-attribute");
+      }
+    }
+
+    try {
+      // React keeps the JSX location on dev-mode fibers (up to React 18)
+      const fiberKey = Object.keys(el).find((k) => k.startsWith("__reactFiber$") || k.startsWith("__reactInternalInstance$"));
+      for (let fiber = fiberKey ? el[fiberKey] : null; fiber; fiber = fiber.return) {
+        if (!source && fiber._debugSource) {
+          const s = fiber._debugSource;
+          source = { file: s.fileName, line: s.lineNumber, column: s.columnNumber || null, origin: "react" };
+        }
+        if (fiber.type && typeof fiber.type !== "string") addComponent(fiber.type.displayName || fiber.type.name);
+      }
+
+      for (let node = el; node && node.nodeType === Node.ELEMENT_NODE; node = node.parentElement) {
+        // Vue 3 and Vue 2 know the component's file, but not the line
+        if (node.__vueParentComponent || node.__vue__) {
+          let inst = node.__vueParentComponent;
+          for (; inst; inst = inst.parent) {
+            if (!source && inst.type.__file) source = { file: inst.type.__file, line: null, column: null, origin: "vue" };
+            addComponent(inst.type.name || inst.type.__name);
+          }
+          let vm = node.__vue__;
+          for (; vm; vm = vm.$parent) {
+            if (!source && vm.$options.__file) source = { file: vm.$options.__file, line: null, column: null, origin: "vue" };
+            addComponent(vm.$options.name);
+          }
+          break;
+        }
+        // Svelte 4 counts lines from 0 (and reports `char`), Svelte 5 from 1
+        const loc = node.__svelte_meta && node.__svelte_meta.loc;
+        if (!source && loc) {
+          source = { file: loc.file, line: loc.line + ("char" in loc ? 1 : 0), column: loc.column, origin: "svelte" };
+        }
+      }
+    } catch (e) {}
+
+    return { source, components };
+  }
+
   function updateOverlay(el, isSelected = false) {
     if (!el) {
       if (hoverOverlay) hoverOverlay.style.display = "none";
@@ -311,6 +531,7 @@ const ELEMENT_SELECTOR_SCRIPT: &str = r####"
 
     updateOverlay(el, true);
 
+    const { source, components } = getSourceInfo(el);
     window.parent.postMessage({
       type: "anyon-component-selected",
       component: {
@@ -321,6 +542,10 @@ const ELEMENT_SELECTOR_SCRIPT: &str = r####"
         text: el.textContent?.substring(0, 200) || null,
         html: el.outerHTML.substring(0, 1000),
         rect: el.getBoundingClientRect(),
+        elementId: el.id || null,
+        classes: typeof el.className === "string" ? el.className.trim().split(/\s+/).filter(Boolean) : [],
+        source,
+        components,
       },
     }, "*");
   }
@@ -386,166 +611,101 @@ const ELEMENT_SELECTOR_SCRIPT: &str = r####"
 "####;
 
 // ============================================================================
-// Proxy Server Implementation (Rewritten for chunked/compression support)
+// Proxy Server Implementation (async, streaming)
 // ============================================================================
 
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
 use brotli::Decompressor;
 use flate2::read::{DeflateDecoder, GzDecoder};
-use std::io::{BufRead, BufReader};
-
-/// Parsed HTTP response with full support for chunked encoding and compression
-struct HttpResponse {
-    status_code: u16,
-    headers: HashMap<String, String>,
-    body: Vec<u8>,
-    is_chunked: bool,
-    content_encoding: Option<String>,
+use futures_util::{Stream, StreamExt};
+use hyper::upgrade::OnUpgrade;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use tokio_util::sync::CancellationToken;
+
+use super::preview_network::{capture_body, NetworkCapture};
+
+/// Dev server a proxy forwards to
+#[derive(Clone)]
+struct ProxyTarget {
+    project_path: String,
+    host: String,
+    port: u16,
+    cancel: CancellationToken,
+    client: Client<HttpConnector, Body>,
 }
 
-impl HttpResponse {
-    fn get_header(&self, name: &str) -> Option<&String> {
-        let lower_name = name.to_lowercase();
-        self.headers
-            .iter()
-            .find(|(k, _)| k.to_lowercase() == lower_name)
-            .map(|(_, v)| v)
+/// Largest compressed HTML document buffered for injection; bigger ones pass through untouched
+const MAX_COMPRESSED_HTML_BYTES: usize = 16 * 1024 * 1024;
+
+/// Path the injected script posts console batches to; answered by the proxy itself
+const CONSOLE_COLLECTOR_PATH: &str = "/__anyon__/console";
+
+/// Headers that describe a single connection and must not be forwarded
+const HOP_BY_HOP_HEADERS: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Strip hop-by-hop headers, including any the Connection header lists
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let listed: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    for name in listed.iter().map(String::as_str).chain(HOP_BY_HOP_HEADERS) {
+        headers.remove(name);
     }
 }
 
-/// Read complete HTTP response with chunked encoding support
-fn read_full_response(stream: &mut TcpStream) -> std::io::Result<HttpResponse> {
-    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
-
-    let mut reader = BufReader::new(stream.try_clone()?);
-
-    // 1. Read status line
-    let mut status_line = String::new();
-    reader.read_line(&mut status_line)?;
-
-    let status_code = status_line
-        .split_whitespace()
-        .nth(1)
-        .and_then(|s| s.parse::<u16>().ok())
-        .unwrap_or(200);
-
-    // 2. Read headers until empty line
-    let mut headers = HashMap::new();
-    let mut is_chunked = false;
-    let mut content_length: Option<usize> = None;
-    let mut content_encoding: Option<String> = None;
-
-    loop {
-        let mut line = String::new();
-        reader.read_line(&mut line)?;
-
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            break;
-        }
-
-        if let Some(colon_pos) = trimmed.find(':') {
-            let key = trimmed[..colon_pos].trim().to_string();
-            let value = trimmed[colon_pos + 1..].trim().to_string();
-            let lower_key = key.to_lowercase();
-
-            if lower_key == "transfer-encoding" && value.to_lowercase().contains("chunked") {
-                is_chunked = true;
-            }
-            if lower_key == "content-length" {
-                content_length = value.parse().ok();
-            }
-            if lower_key == "content-encoding" {
-                content_encoding = Some(value.to_lowercase());
-            }
-
-            headers.insert(key, value);
-        }
-    }
-
-    // 3. Read body based on transfer encoding
-    let body = if is_chunked {
-        read_chunked_body(&mut reader)?
-    } else if let Some(len) = content_length {
-        let mut body = vec![0u8; len];
-        reader.read_exact(&mut body)?;
-        body
-    } else {
-        // No content-length, read until connection closes (with limit)
-        let mut body = Vec::new();
-        let mut chunk = [0u8; 4096];
-        loop {
-            match reader.read(&mut chunk) {
-                Ok(0) => break,
-                Ok(n) => {
-                    body.extend_from_slice(&chunk[..n]);
-                    if body.len() > 5_000_000 {
-                        // 5MB limit
-                        break;
-                    }
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
-                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => break,
-                Err(e) => return Err(e),
-            }
-        }
-        body
-    };
-
-    log::debug!(
-        "Proxy: Read response - status={}, chunked={}, encoding={:?}, body_len={}",
-        status_code,
-        is_chunked,
-        content_encoding,
-        body.len()
-    );
-
-    Ok(HttpResponse {
-        status_code,
-        headers,
-        body,
-        is_chunked,
-        content_encoding,
-    })
+fn header_has_token(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|t| t.trim().eq_ignore_ascii_case(token))
 }
 
-/// Read chunked transfer encoding body
-fn read_chunked_body(reader: &mut BufReader<TcpStream>) -> std::io::Result<Vec<u8>> {
-    let mut body = Vec::new();
-
-    loop {
-        // Read chunk size line
-        let mut size_line = String::new();
-        reader.read_line(&mut size_line)?;
-
-        let chunk_size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
-
-        if chunk_size == 0 {
-            // Read trailing CRLF after last chunk
-            let mut trailer = String::new();
-            let _ = reader.read_line(&mut trailer);
-            break;
-        }
-
-        // Read chunk data
-        let mut chunk = vec![0u8; chunk_size];
-        reader.read_exact(&mut chunk)?;
-        body.extend_from_slice(&chunk);
-
-        // Read CRLF after chunk
-        let mut crlf = [0u8; 2];
-        reader.read_exact(&mut crlf)?;
+/// Protocol switch such as a Vite/Next HMR WebSocket
+fn is_upgrade_request(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::UPGRADE)
+        && header_has_token(headers, header::CONNECTION, "upgrade")
+}
 
-        // Safety limit
-        if body.len() > 5_000_000 {
-            break;
-        }
+/// Check if response should have script injected
+///
+/// Only successful HTML documents are touched; everything else streams through as-is.
+fn should_inject_script(status: StatusCode, headers: &HeaderMap) -> bool {
+    if status != StatusCode::OK {
+        log::debug!("Proxy: Skipping injection - status code {}", status);
+        return false;
     }
 
-    Ok(body)
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(|ct| ct.to_lowercase().contains("text/html"))
+        .unwrap_or(false)
 }
 
 /// Decompress response body if compressed
-fn decompress_body(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
+pub(crate) fn decompress_body(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
     let Some(enc) = encoding else {
         return body.to_vec();
     };
@@ -603,62 +763,6 @@ fn decompress_body(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
     }
 }
 
-/// Check if response should have script injected
-fn should_inject_script(response: &HttpResponse, path: &str) -> bool {
-    // Only inject into 200 OK responses
-    if response.status_code != 200 {
-        log::debug!(
-            "Proxy: Skipping injection - status code {}",
-            response.status_code
-        );
-        return false;
-    }
-
-    // Check Content-Type header (case-insensitive)
-    if let Some(ct) = response.get_header("content-type") {
-        let ct_lower = ct.to_lowercase();
-        if ct_lower.contains("text/html") {
-            log::debug!("Proxy: Injection needed - Content-Type is text/html");
-            return true;
-        }
-        // Skip if explicitly not HTML
-        if ct_lower.contains("application/json")
-            || ct_lower.contains("text/css")
-            || ct_lower.contains("text/javascript")
-            || ct_lower.contains("application/javascript")
-            || ct_lower.contains("image/")
-            || ct_lower.contains("font/")
-        {
-            log::debug!("Proxy: Skipping injection - Content-Type is {}", ct);
-            return false;
-        }
-    }
-
-    // Path-based inference
-    if path.ends_with('/') || path.ends_with(".html") || path.ends_with(".htm") {
-        log::debug!(
-            "Proxy: Injection likely needed - path suggests HTML: {}",
-            path
-        );
-        return true;
-    }
-
-    // Skip known non-HTML paths
-    let skip_extensions = [
-        ".js", ".css", ".json", ".map", ".png", ".jpg", ".jpeg", ".gif", ".svg", ".ico", ".woff",
-        ".woff2", ".ttf", ".eot",
-    ];
-    for ext in &skip_extensions {
-        if path.ends_with(ext) {
-            log::debug!("Proxy: Skipping injection - path extension: {}", ext);
-            return false;
-        }
-    }
-
-    // For paths without extension, check body content (after decompression)
-    !path.contains('.') || path.ends_with('/')
-}
-
 /// Inject script into HTML, preferring </body> position
 fn inject_html(html: &str) -> String {
     let script_tag = format!("<script>{}</script>", ELEMENT_SELECTOR_SCRIPT);
@@ -702,189 +806,318 @@ fn inject_html(html: &str) -> String {
     format!("{}{}", html, script_tag)
 }
 
-/// Build HTTP response bytes with proper headers
-fn build_response(status_code: u16, headers: &HashMap<String, String>, body: &[u8]) -> Vec<u8> {
-    let status_text = match status_code {
-        200 => "OK",
-        304 => "Not Modified",
-        404 => "Not Found",
-        500 => "Internal Server Error",
-        _ => "OK",
-    };
+/// Inserts the element selector script before `</body>` while an HTML body streams through
+///
+/// Everything up to the last few bytes of each chunk is forwarded immediately; only enough
+/// is held back to spot a tag split across chunks. Documents without `</body>` get the
+/// script appended at the end.
+struct ScriptInjector {
+    script: Vec<u8>,
+    pending: Vec<u8>,
+    injected: bool,
+}
 
-    let mut response = format!("HTTP/1.1 {} {}\r\n", status_code, status_text);
+impl ScriptInjector {
+    const CLOSE_BODY: &'static [u8] = b"</body>";
 
-    // Add headers (excluding content-length and transfer-encoding, we'll set our own)
-    for (key, value) in headers {
-        let lower_key = key.to_lowercase();
-        if lower_key != "content-length"
-            && lower_key != "transfer-encoding"
-            && lower_key != "content-encoding"
-        // Remove compression since we decompressed
+    fn new() -> Self {
+        Self {
+            script: format!("<script>{}</script>", ELEMENT_SELECTOR_SCRIPT).into_bytes(),
+            pending: Vec::new(),
+            injected: false,
+        }
+    }
+
+    /// Feed the next chunk, returning the bytes that can be sent on
+    fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.injected {
+            return chunk.to_vec();
+        }
+        self.pending.extend_from_slice(chunk);
+
+        if let Some(pos) = self
+            .pending
+            .windows(Self::CLOSE_BODY.len())
+            .position(|w| w.eq_ignore_ascii_case(Self::CLOSE_BODY))
         {
-            response.push_str(&format!("{}: {}\r\n", key, value));
+            let mut out = Vec::with_capacity(self.pending.len() + self.script.len());
+            out.extend_from_slice(&self.pending[..pos]);
+            out.extend_from_slice(&self.script);
+            out.extend_from_slice(&self.pending[pos..]);
+            self.pending.clear();
+            self.injected = true;
+            return out;
         }
+
+        let keep = self.pending.len().min(Self::CLOSE_BODY.len() - 1);
+        let ready = self.pending.len() - keep;
+        self.pending.drain(..ready).collect()
     }
 
-    // Set new content-length
-    response.push_str(&format!("Content-Length: {}\r\n", body.len()));
-    response.push_str("\r\n");
+    /// Flush what's held back once the body has ended
+    fn finish(&mut self) -> Vec<u8> {
+        let mut out = std::mem::take(&mut self.pending);
+        if !self.injected {
+            log::debug!("Proxy: No </body> found, appending script at end of document");
+            out.extend_from_slice(&self.script);
+            self.injected = true;
+        }
+        out
+    }
+}
 
-    let mut bytes = response.into_bytes();
-    bytes.extend_from_slice(body);
-    bytes
+/// Stream an HTML body through a `ScriptInjector`
+fn inject_script_stream(body: Body) -> impl Stream<Item = Result<Bytes, axum::Error>> {
+    futures_util::stream::unfold(
+        (body.into_data_stream(), Some(ScriptInjector::new())),
+        |(mut stream, mut injector)| async move {
+            let current = injector.as_mut()?;
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    Some((Ok(Bytes::from(current.push(&chunk))), (stream, injector)))
+                }
+                Some(Err(e)) => Some((Err(e), (stream, None))),
+                None => Some((Ok(Bytes::from(current.finish())), (stream, None))),
+            }
+        },
+    )
 }
 
-fn handle_proxy_connection(
-    mut client_stream: TcpStream,
-    target_host: &str,
-    target_port: u16,
-) -> std::io::Result<()> {
-    client_stream.set_read_timeout(Some(Duration::from_secs(30)))?;
-    client_stream.set_write_timeout(Some(Duration::from_secs(30)))?;
-
-    // Read client request
-    let mut request_buffer = vec![0u8; 16384]; // Increased buffer for larger requests
-    let bytes_read = client_stream.read(&mut request_buffer)?;
-    if bytes_read == 0 {
-        return Ok(());
+/// A body read into memory, or handed back intact once it outgrew the limit
+enum BufferedBody {
+    Complete(Bytes),
+    TooLarge(Body),
+}
+
+/// Read `body` into memory as long as it stays within `limit` bytes
+///
+/// Past the limit the chunks read so far are chained back in front of the rest of the
+/// stream, so nothing is lost and the caller can still forward it.
+async fn buffer_body(body: Body, limit: usize) -> Result<BufferedBody, axum::Error> {
+    let mut stream = body.into_data_stream();
+    let mut chunks: Vec<Bytes> = Vec::new();
+    let mut total = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        total += chunk.len();
+        chunks.push(chunk);
+        if total > limit {
+            let read = futures_util::stream::iter(chunks.into_iter().map(Ok));
+            return Ok(BufferedBody::TooLarge(Body::from_stream(
+                read.chain(stream),
+            )));
+        }
     }
-    let request = String::from_utf8_lossy(&request_buffer[..bytes_read]);
+    Ok(BufferedBody::Complete(chunks.concat().into()))
+}
 
-    // Parse request to get path
-    let first_line = request.lines().next().unwrap_or("");
-    let parts: Vec<&str> = first_line.split_whitespace().collect();
-    let path = parts.get(1).unwrap_or(&"/").to_string();
+/// Pooled HTTP/1 client to the dev server, shared by every request a proxy forwards
+fn upstream_client() -> Client<HttpConnector, Body> {
+    Client::builder(TokioExecutor::new()).build_http()
+}
 
-    log::debug!("Proxy: Handling request for path: {}", path);
+/// Send one request to the dev server, reusing a pooled connection when one is idle
+///
+/// A `101 Switching Protocols` answer takes its connection out of the pool so it can be
+/// turned into a raw byte pipe.
+async fn send_upstream(
+    target: &ProxyTarget,
+    request: Request,
+) -> Result<hyper::Response<hyper::body::Incoming>, String> {
+    target
+        .client
+        .request(request)
+        .await
+        .map_err(|e| format!("request failed: {}", e))
+}
 
-    // Connect to target server
-    let target_addr = format!("{}:{}", target_host, target_port);
-    let mut target_stream = match TcpStream::connect(&target_addr) {
-        Ok(s) => s,
+/// Copy bytes both ways between an upgraded client and dev server connection
+async fn pipe_upgraded(
+    client: OnUpgrade,
+    upstream: OnUpgrade,
+    cancel: CancellationToken,
+    path: String,
+) {
+    let (client, upstream) = match tokio::try_join!(client, upstream) {
+        Ok(pair) => pair,
         Err(e) => {
-            log::error!(
-                "Proxy: Failed to connect to target {}:{} - {}",
-                target_host,
-                target_port,
-                e
-            );
-            // Send 502 Bad Gateway
-            let error_response = "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n";
-            let _ = client_stream.write_all(error_response.as_bytes());
-            return Ok(());
+            log::debug!("Proxy: Upgrade for {} failed - {}", path, e);
+            return;
         }
     };
-    target_stream.set_read_timeout(Some(Duration::from_secs(30)))?;
-    target_stream.set_write_timeout(Some(Duration::from_secs(30)))?;
-
-    // Forward request (modify Host header for proper routing)
-    let modified_request = request.replace(
-        &format!("Host: localhost:{}", target_port + 10000),
-        &format!("Host: {}:{}", target_host, target_port),
-    );
+    log::debug!("Proxy: Piping upgraded connection for {}", path);
+
+    let mut client = TokioIo::new(client);
+    let mut upstream = TokioIo::new(upstream);
+    tokio::select! {
+        result = tokio::io::copy_bidirectional(&mut client, &mut upstream) => {
+            if let Err(e) = result {
+                log::debug!("Proxy: Upgraded connection for {} closed - {}", path, e);
+            }
+        }
+        _ = cancel.cancelled() => {}
+    }
+}
 
-    // Also request no compression so we don't need to decompress (simpler approach)
-    // But we still support decompression as a fallback
-    let request_with_encoding = if modified_request.to_lowercase().contains("accept-encoding:") {
-        // Remove accept-encoding to avoid compressed responses
-        let re = regex::Regex::new(r"(?i)accept-encoding:[^\r\n]*\r\n").unwrap();
-        re.replace(&modified_request, "Accept-Encoding: identity\r\n")
-            .to_string()
+async fn proxy_request(State(target): State<ProxyTarget>, mut request: Request) -> Response {
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "/".to_string());
+    let method = request.method().clone();
+    log::debug!("Proxy: Handling {} request for path: {}", method, path);
+
+    let client_upgrade = if is_upgrade_request(request.headers()) {
+        Some(hyper::upgrade::on(&mut request))
     } else {
-        modified_request.to_string()
+        None
     };
 
-    target_stream.write_all(request_with_encoding.as_bytes())?;
+    let (mut parts, body) = request.into_parts();
+    // The body streams upstream; the capture keeps a bounded copy as it goes by
+    let (mut capture, body) = NetworkCapture::start(
+        &target.project_path,
+        method.as_str(),
+        format!("http://{}:{}{}", target.host, target.port, path),
+        &path,
+        &format!("{:?}", parts.version),
+        &parts.headers,
+        body,
+    );
+
+    if client_upgrade.is_none() {
+        remove_hop_by_hop_headers(&mut parts.headers);
+    }
+    // Documents come back uncompressed so the script can be injected while streaming;
+    // everything else keeps the browser's Accept-Encoding and passes through untouched
+    if parts
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("text/html"))
+        .unwrap_or(false)
+    {
+        parts.headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_static("identity"),
+        );
+    }
+    if let Ok(host) = HeaderValue::from_str(&format!("{}:{}", target.host, target.port)) {
+        parts.headers.insert(header::HOST, host);
+    }
+    parts.uri = format!("http://{}:{}{}", target.host, target.port, path)
+        .parse()
+        .unwrap_or_default();
 
-    // Read complete response using new chunked-aware parser
-    let response = match read_full_response(&mut target_stream) {
+    let request = Request::from_parts(parts, body);
+    let mut response = match send_upstream(&target, request).await {
         Ok(r) => r,
         Err(e) => {
-            log::error!("Proxy: Failed to read response for {} - {}", path, e);
-            // Send 502 Bad Gateway
-            let error_response = "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n";
-            let _ = client_stream.write_all(error_response.as_bytes());
-            return Ok(());
+            log::error!(
+                "Proxy: Failed to forward {} to {}:{} - {}",
+                path,
+                target.host,
+                target.port,
+                e
+            );
+            capture.fail(e);
+            return StatusCode::BAD_GATEWAY.into_response();
         }
     };
+    capture.response_head(response.status(), response.headers());
+
+    if let Some(client_upgrade) = client_upgrade {
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            let upstream_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(pipe_upgraded(
+                client_upgrade,
+                upstream_upgrade,
+                target.cancel.clone(),
+                path,
+            ));
+        }
+        let (parts, body) = response.into_parts();
+        return Response::from_parts(parts, Body::new(body));
+    }
 
-    // Check if we should inject script
-    if should_inject_script(&response, &path) {
-        // Decompress body if needed
-        let decompressed_body =
-            decompress_body(&response.body, response.content_encoding.as_deref());
-
-        // Convert to string for HTML manipulation
-        let body_str = String::from_utf8_lossy(&decompressed_body);
-
-        // Verify it's actually HTML by checking content
-        let body_lower = body_str.to_lowercase();
-        if body_lower.contains("<!doctype")
-            || body_lower.contains("<html")
-            || body_lower.contains("<head")
-        {
-            log::info!(
-                "Proxy: Injecting element selector script into HTML response for path: {}",
-                path
-            );
+    let (mut parts, body) = response.into_parts();
+    remove_hop_by_hop_headers(&mut parts.headers);
+    let body = capture_body(Body::new(body), capture);
+    if method == Method::HEAD || !should_inject_script(parts.status, &parts.headers) {
+        return Response::from_parts(parts, body);
+    }
 
-            // Inject script
-            let modified_body = inject_html(&body_str);
-            let modified_bytes = modified_body.as_bytes();
+    // The injected script changes the length; the body is re-framed as chunked
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let encoding = parts
+        .headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| v != "identity");
+
+    if let Some(encoding) = encoding {
+        // The server compressed the document anyway; buffer it to decompress and inject
+        let compressed = match buffer_body(body, MAX_COMPRESSED_HTML_BYTES).await {
+            Ok(BufferedBody::Complete(b)) => b,
+            Ok(BufferedBody::TooLarge(body)) => {
+                log::warn!(
+                    "Proxy: Compressed HTML for {} exceeds {} bytes, skipping injection",
+                    path,
+                    MAX_COMPRESSED_HTML_BYTES
+                );
+                return Response::from_parts(parts, body);
+            }
+            Err(e) => {
+                log::error!("Proxy: Failed to read response for {} - {}", path, e);
+                return StatusCode::BAD_GATEWAY.into_response();
+            }
+        };
+        let html = decompress_body(&compressed, Some(&encoding));
+        parts.headers.remove(header::CONTENT_ENCODING);
+        log::info!(
+            "Proxy: Injecting element selector script into compressed HTML response for path: {}",
+            path
+        );
+        return Response::from_parts(
+            parts,
+            Body::from(inject_html(&String::from_utf8_lossy(&html))),
+        );
+    }
 
-            log::debug!(
-                "Proxy: Script injection complete, body size: {} -> {}",
-                decompressed_body.len(),
-                modified_bytes.len()
-            );
+    log::info!(
+        "Proxy: Injecting element selector script into HTML response for path: {}",
+        path
+    );
+    Response::from_parts(parts, Body::from_stream(inject_script_stream(body)))
+}
 
-            // Build and send response
-            let final_response =
-                build_response(response.status_code, &response.headers, modified_bytes);
-            client_stream.write_all(&final_response)?;
-        } else {
-            // Not HTML content, forward decompressed body
-            log::debug!(
-                "Proxy: Path suggested HTML but content is not HTML: {}",
-                path
-            );
-            let final_response =
-                build_response(response.status_code, &response.headers, &decompressed_body);
-            client_stream.write_all(&final_response)?;
+/// Receive a batch of console entries from the injected script
+///
+/// Read as raw bytes rather than `Json` so the page doesn't have to set a content type.
+async fn collect_preview_console(State(target): State<ProxyTarget>, body: Bytes) -> StatusCode {
+    match serde_json::from_slice::<Vec<PreviewConsoleEntry>>(&body) {
+        Ok(entries) => {
+            record_preview_console(&target.project_path, entries);
+            StatusCode::NO_CONTENT
         }
-    } else {
-        // Forward response as-is (but handle potential decompression for consistency)
-        if response.content_encoding.is_some() {
-            // Decompress and forward without compression
-            let decompressed =
-                decompress_body(&response.body, response.content_encoding.as_deref());
-            let final_response =
-                build_response(response.status_code, &response.headers, &decompressed);
-            client_stream.write_all(&final_response)?;
-        } else if response.is_chunked {
-            // Was chunked, rebuild as content-length
-            let final_response =
-                build_response(response.status_code, &response.headers, &response.body);
-            client_stream.write_all(&final_response)?;
-        } else {
-            // Forward original response as-is (already have Content-Length)
-            let final_response =
-                build_response(response.status_code, &response.headers, &response.body);
-            client_stream.write_all(&final_response)?;
+        Err(e) => {
+            log::debug!("Proxy: Ignoring malformed console batch - {}", e);
+            StatusCode::BAD_REQUEST
         }
     }
-
-    Ok(())
 }
 
-fn run_proxy_server(
+/// Serve the injection proxy on `proxy_port` until `cancel` fires
+async fn run_proxy_server(
+    project_path: String,
     proxy_port: u16,
     target_host: String,
     target_port: u16,
-    stop_flag: Arc<Mutex<bool>>,
+    cancel: CancellationToken,
 ) {
-    let listener = match TcpListener::bind(format!("127.0.0.1:{}", proxy_port)) {
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", proxy_port)).await {
         Ok(l) => l,
         Err(e) => {
             log::error!("Failed to bind proxy server: {}", e);
@@ -892,7 +1125,6 @@ fn run_proxy_server(
         }
     };
 
-    listener.set_nonblocking(true).ok();
     log::info!(
         "Proxy server listening on port {}, forwarding to {}:{}",
         proxy_port,
@@ -900,34 +1132,63 @@ fn run_proxy_server(
         target_port
     );
 
-    loop {
-        // Check stop flag
-        if *stop_flag.lock().unwrap() {
-            log::info!("Proxy server stopping");
-            break;
-        }
+    let app = axum::Router::new()
+        .route(
+            CONSOLE_COLLECTOR_PATH,
+            axum::routing::post(collect_preview_console),
+        )
+        .fallback(proxy_request)
+        .with_state(ProxyTarget {
+            project_path,
+            host: target_host,
+            port: target_port,
+            cancel: cancel.clone(),
+            client: upstream_client(),
+        });
+    let shutdown = async move { cancel.cancelled().await };
+    if let Err(e) = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+    {
+        log::error!("Proxy server error: {}", e);
+    }
+    log::info!("Proxy server stopping");
+}
 
-        match listener.accept() {
-            Ok((stream, _addr)) => {
-                let host = target_host.clone();
-                thread::spawn(move || {
-                    if let Err(e) = handle_proxy_connection(stream, &host, target_port) {
-                        log::debug!("Proxy connection error: {}", e);
-                    }
-                });
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                thread::sleep(Duration::from_millis(100));
-            }
-            Err(e) => {
-                log::error!("Accept error: {}", e);
-            }
+/// Serve a plain HTML site straight from disk, for projects without a dev server of their own
+///
+/// With `spa_fallback`, paths with no file behind them get the root `index.html`, so
+/// client-side routes survive a reload.
+async fn run_static_server(root: String, port: u16, spa_fallback: bool, cancel: CancellationToken) {
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(l) => l,
+        Err(e) => {
+            log::error!("Failed to bind static server: {}", e);
+            return;
         }
+    };
+
+    log::info!("Static server listening on port {}, serving {}", port, root);
+
+    let serve_dir = tower_http::services::ServeDir::new(&root);
+    let app = if spa_fallback {
+        let index = tower_http::services::ServeFile::new(Path::new(&root).join("index.html"));
+        axum::Router::new().fallback_service(serve_dir.fallback(index))
+    } else {
+        axum::Router::new().fallback_service(serve_dir)
+    };
+    let shutdown = async move { cancel.cancelled().await };
+    if let Err(e) = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+    {
+        log::error!("Static server error: {}", e);
     }
+    log::info!("Static server stopping");
 }
 
 fn find_available_port(start: u16) -> Option<u16> {
-    for port in start..start + 100 {
+    for port in start..start.saturating_add(100) {
         if TcpListener::bind(format!("127.0.0.1:{}", port)).is_ok() {
             return Some(port);
         }
@@ -935,7 +1196,42 @@ fn find_available_port(start: u16) -> Option<u16> {
     None
 }
 
+/// Proxy port for a project, held in the port registry so it stays the same between runs
+fn allocate_proxy_port(app: &AppHandle, project_path: &str, target_port: u16) -> Option<u16> {
+    let preferred = target_port.saturating_add(10000);
+    let Some(db) = app.try_state::<AgentDb>() else {
+        return find_available_port(preferred);
+    };
+    allocate_port(&db, project_path, None, PortKind::Proxy, preferred)
+        .map_err(|e| log::warn!("Failed to allocate proxy port: {}", e))
+        .ok()
+}
+
+/// `allocate_proxy_port` on the blocking pool, for async callers
+async fn allocate_proxy_port_async(
+    app: &AppHandle,
+    project_path: &str,
+    target_port: u16,
+) -> Option<u16> {
+    let app = app.clone();
+    let project_path = project_path.to_string();
+    tokio::task::spawn_blocking(move || allocate_proxy_port(&app, &project_path, target_port))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Remove terminal color codes; Vite, for one, prints the port in bold
+pub(crate) fn strip_ansi(output: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref ANSI_RE: regex::Regex = regex::Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap();
+    }
+    ANSI_RE.replace_all(output, "").to_string()
+}
+
 fn detect_dev_server_port(output: &str) -> Option<u16> {
+    let output = strip_ansi(output);
+
     // Common patterns for dev server port detection
     let patterns = [
         r"localhost:(\d+)",
@@ -949,10 +1245,10 @@ fn detect_dev_server_port(output: &str) -> Option<u16> {
 
     for pattern in patterns {
         if let Ok(re) = regex::Regex::new(pattern) {
-            if let Some(caps) = re.captures(output) {
+            if let Some(caps) = re.captures(&output) {
                 if let Some(port_str) = caps.get(1) {
                     if let Ok(port) = port_str.as_str().parse::<u16>() {
-                        if port >= 3000 && port <= 65535 {
+                        if port != 0 {
                             return Some(port);
                         }
                     }
@@ -1095,24 +1391,49 @@ pub async fn detect_package_manager(project_path: String) -> Result<String, Stri
         return Ok("npm".to_string());
     }
 
-    Err("No package manager detected".to_string())
+    // Not a Node project: report the tool its runner would use (python, cargo, go, ...)
+    detect_runner(path)
+        .map(|(_, tool)| tool)
+        .ok_or_else(|| "No supported project type detected".to_string())
+}
+
+/// Where the search for a project's dev port starts: a hash of its ID
+fn preferred_dev_port(project_id: &str) -> u16 {
+    let hash = project_id
+        .chars()
+        .fold(0u32, |acc, c| acc.wrapping_mul(31).wrapping_add(c as u32));
+    32100 + (hash % 10000) as u16
 }
 
+/// Start a project's dev server, replacing any that's already running for it
+///
+/// With `auto_restart`, a server that crashes is started again with backoff.
 #[tauri::command]
 pub async fn start_dev_server(
     app: AppHandle,
+    db: tauri::State<'_, AgentDb>,
     project_path: String,
     project_id: Option<String>,
+    auto_restart: Option<bool>,
 ) -> Result<(), String> {
     log::info!("Starting dev server for: {}", project_path);
-
-    // Calculate fixed port if project_id is provided
-    let fixed_port = project_id.as_ref().map(|id| {
-        let hash = id
-            .chars()
-            .fold(0u32, |acc, c| acc.wrapping_mul(31).wrapping_add(c as u32));
-        32100 + (hash % 10000) as u16
-    });
+    stop_dev_server(db.clone(), project_path.clone()).await?;
+
+    // Projects with an ID keep a registered port between runs; the ID's hash is only where
+    // the search for a free one starts
+    let fixed_port = match &project_id {
+        Some(id) => Some(
+            allocate_port_async(
+                &app,
+                &project_path,
+                Some(id),
+                PortKind::Dev,
+                preferred_dev_port(id),
+            )
+            .await?,
+        ),
+        None => None,
+    };
 
     if let Some(port) = fixed_port {
         log::info!("Using fixed port: {}", port);
@@ -1120,46 +1441,56 @@ pub async fn start_dev_server(
         log::info!("No project_id provided, using dynamic port detection");
     }
 
-    // Detect package manager
-    let pm = detect_package_manager(project_path.clone()).await?;
-    log::info!("Using package manager: {}", pm);
-
-    // Build command with port option
-    let (cmd, base_args) = match pm.as_str() {
-        "bun" => ("bun", vec!["run", "dev"]),
-        "pnpm" => ("pnpm", vec!["run", "dev"]),
-        "yarn" => ("yarn", vec!["dev"]),
-        _ => ("npm", vec!["run", "dev"]),
+    // Work out the runner, the framework's command, port flags and readiness signal
+    let overrides = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_dev_server_overrides(&conn, &project_path).map_err(|e| e.to_string())?
+    };
+    let plan = plan_launch(Path::new(&project_path), overrides.as_ref(), fixed_port)?;
+    log::info!(
+        "Launching {} {:?} dev server with {}: {} {}",
+        plan.runner,
+        plan.framework,
+        plan.package_manager,
+        plan.program,
+        plan.args.join(" ")
+    );
+    // A fixed port only counts when the command actually pins it
+    let fixed_port = plan.port;
+    let target_host = plan.host.clone();
+    let ready_re = regex::Regex::new(&plan.ready_pattern).ok();
+    let proxy_cancel = CancellationToken::new();
+
+    // Static sites are served in-process; everything else gets its own process
+    let mut process = match &plan.static_root {
+        Some(root) => {
+            let port = fixed_port.ok_or("No free port for the static server")?;
+            tauri::async_runtime::spawn(run_static_server(
+                root.clone(),
+                port,
+                false,
+                proxy_cancel.clone(),
+            ));
+            None
+        }
+        None => Some(spawn_dev_process(
+            &plan.program,
+            &plan.args,
+            &plan.env,
+            &project_path,
+        )?),
     };
 
-    // Build args list with port if available
-    let port_str = fixed_port.map(|p| p.to_string());
-    let mut args: Vec<&str> = base_args;
-
-    // Add port argument if fixed port is available
-    // This works for most frameworks: Vite, Next.js, Create React App, etc.
-    if let Some(ref port_string) = port_str {
-        args.push("--");
-        args.push("--port");
-        args.push(port_string.as_str());
-    }
-
-    // Start process
-    let process = Command::new(cmd)
-        .args(&args)
-        .current_dir(&project_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start dev server: {}", e))?;
-
-    let pid = process.id();
-    let stop_flag = Arc::new(Mutex::new(false));
+    let pid = process.as_ref().map(|p| p.id()).unwrap_or(0);
+    let (stdout, stderr) = process
+        .as_mut()
+        .map(|p| (p.stdout.take(), p.stderr.take()))
+        .unwrap_or_default();
 
     // Create entry with fixed port if available
     let (initial_port, initial_proxy_port, initial_urls) = if let Some(port) = fixed_port {
-        let proxy_port = find_available_port(port + 10000);
-        let original_url = Some(format!("http://localhost:{}", port));
+        let proxy_port = allocate_proxy_port_async(&app, &project_path, port).await;
+        let original_url = Some(format!("http://{}:{}", target_host, port));
         let proxy_url = proxy_port.map(|p| format!("http://localhost:{}", p));
         (Some(port), proxy_port, (original_url, proxy_url))
     } else {
@@ -1173,13 +1504,20 @@ pub async fn start_dev_server(
         original_url: initial_urls.0,
         proxy_port: initial_proxy_port,
         proxy_url: initial_urls.1,
+        status: "running".to_string(),
+        mode: "dev".to_string(),
+        exit_code: None,
+        restart_count: 0,
+        log_tail: Vec::new(),
     };
 
+    let id = NEXT_DEV_SERVER_ID.fetch_add(1, Ordering::Relaxed);
     let entry = DevServerEntry {
-        process: Some(process),
+        id,
+        process,
         proxy_handle: None,
         info: info.clone(),
-        stop_flag: stop_flag.clone(),
+        proxy_cancel: proxy_cancel.clone(),
     };
 
     // Store in global state
@@ -1190,10 +1528,13 @@ pub async fn start_dev_server(
 
     // If we have a fixed port, start proxy immediately and notify
     if let (Some(port), Some(proxy_port)) = (initial_port, initial_proxy_port) {
-        let proxy_stop = stop_flag.clone();
-        thread::spawn(move || {
-            run_proxy_server(proxy_port, "localhost".to_string(), port, proxy_stop);
-        });
+        tauri::async_runtime::spawn(run_proxy_server(
+            project_path.clone(),
+            proxy_port,
+            target_host.clone(),
+            port,
+            proxy_cancel.clone(),
+        ));
 
         // Notify frontend immediately
         let _ = app.emit(
@@ -1204,169 +1545,954 @@ pub async fn start_dev_server(
                 message: format!("Dev server configured to use port {}", port),
                 port: Some(port),
                 proxy_url: Some(format!("http://localhost:{}", proxy_port)),
+                seq: None,
             },
         );
+
+        if plan.static_root.is_some() {
+            let _ = app.emit(
+                "dev-server-output",
+                DevServerOutput {
+                    project_path: project_path.clone(),
+                    output_type: "ready".to_string(),
+                    message: "Dev server is ready".to_string(),
+                    port: Some(port),
+                    proxy_url: Some(format!("http://localhost:{}", proxy_port)),
+                    seq: None,
+                },
+            );
+        }
+    }
+    if plan.static_root.is_some() {
+        return Ok(());
     }
 
-    // Spawn thread to read output
-    let app_clone = app.clone();
-    let project_clone = project_path.clone();
-    thread::spawn(move || {
-        // Get stdout/stderr from process
-        let mut servers = DEV_SERVERS.lock().unwrap();
-        if let Some(entry) = servers.servers.get_mut(&project_clone) {
-            if let Some(ref mut proc) = entry.process {
-                let stdout = proc.stdout.take();
-                let stderr = proc.stderr.take();
-                drop(servers);
-
-                // Read output in separate threads
-                if let Some(mut stdout) = stdout {
-                    let app = app_clone.clone();
-                    let project = project_clone.clone();
-                    thread::spawn(move || {
-                        let mut buffer = [0u8; 1024];
-                        loop {
-                            match stdout.read(&mut buffer) {
-                                Ok(0) => break,
-                                Ok(n) => {
-                                    let output = String::from_utf8_lossy(&buffer[..n]);
-                                    let _ = app.emit(
-                                        "dev-server-output",
-                                        DevServerOutput {
-                                            project_path: project.clone(),
-                                            output_type: "stdout".to_string(),
-                                            message: output.to_string(),
-                                            port: None,
-                                            proxy_url: None,
-                                        },
-                                    );
-
-                                    // Try to detect port (only if not already set with fixed port)
-                                    let port_already_set = {
-                                        let servers = DEV_SERVERS.lock().unwrap();
-                                        servers
-                                            .servers
-                                            .get(&project)
-                                            .and_then(|e| e.info.detected_port)
-                                            .is_some()
-                                    };
-
-                                    if !port_already_set {
-                                        if let Some(port) = detect_dev_server_port(&output) {
-                                            log::info!(
-                                                "Detected dev server port from output: {}",
-                                                port
-                                            );
-
-                                            // Start proxy server
-                                            if let Some(proxy_port) =
-                                                find_available_port(port + 10000)
-                                            {
-                                                let stop_flag = {
-                                                    let mut servers = DEV_SERVERS.lock().unwrap();
-                                                    if let Some(entry) =
-                                                        servers.servers.get_mut(&project)
-                                                    {
-                                                        entry.info.detected_port = Some(port);
-                                                        entry.info.original_url = Some(format!(
-                                                            "http://localhost:{}",
-                                                            port
-                                                        ));
-                                                        entry.info.proxy_port = Some(proxy_port);
-                                                        entry.info.proxy_url = Some(format!(
-                                                            "http://localhost:{}",
-                                                            proxy_port
-                                                        ));
-                                                        entry.stop_flag.clone()
-                                                    } else {
-                                                        return;
-                                                    }
-                                                };
-
-                                                // Start proxy in background
-                                                let proxy_stop = stop_flag.clone();
-                                                thread::spawn(move || {
-                                                    run_proxy_server(
-                                                        proxy_port,
-                                                        "localhost".to_string(),
-                                                        port,
-                                                        proxy_stop,
-                                                    );
-                                                });
-
-                                                let _ = app.emit("dev-server-output", DevServerOutput {
-                                                    project_path: project.clone(),
-                                                    output_type: "port-detected".to_string(),
-                                                    message: format!("Dev server ready at http://localhost:{}", port),
-                                                    port: Some(port),
-                                                    proxy_url: Some(format!("http://localhost:{}", proxy_port)),
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(_) => break,
-                            }
-                        }
-                    });
-                }
+    read_dev_server_output(
+        app.clone(),
+        project_path.clone(),
+        stdout,
+        stderr,
+        target_host,
+        ready_re,
+    );
 
-                if let Some(mut stderr) = stderr {
-                    let app = app_clone.clone();
-                    let project = project_clone.clone();
-                    thread::spawn(move || {
-                        let mut buffer = [0u8; 1024];
-                        loop {
-                            match stderr.read(&mut buffer) {
-                                Ok(0) => break,
-                                Ok(n) => {
-                                    let output = String::from_utf8_lossy(&buffer[..n]);
-                                    let _ = app.emit(
-                                        "dev-server-output",
-                                        DevServerOutput {
-                                            project_path: project.clone(),
-                                            output_type: "stderr".to_string(),
-                                            message: output.to_string(),
-                                            port: None,
-                                            proxy_url: None,
-                                        },
-                                    );
-                                }
-                                Err(_) => break,
-                            }
-                        }
-                    });
-                }
-            }
-        }
-    });
+    let auto_restart = auto_restart.unwrap_or(false);
+    thread::spawn(move || supervise_dev_server(app, project_path, id, plan, auto_restart));
 
     Ok(())
 }
 
-#[tauri::command]
-pub async fn stop_dev_server(project_path: String) -> Result<(), String> {
-    log::info!("Stopping dev server for: {}", project_path);
-
-    let mut servers = DEV_SERVERS.lock().unwrap();
-    if let Some(entry) = servers.servers.remove(&project_path) {
-        // Set stop flag for proxy
-        *entry.stop_flag.lock().unwrap() = true;
+/// Spawn a dev server in its own process group so the whole tree can be stopped at once
+///
+/// Package managers start the real server as a child; killing only the wrapper leaves that
+/// child running and holding the port.
+fn spawn_dev_process(
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+    project_path: &str,
+) -> Result<Child, String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .envs(env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .current_dir(project_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
 
-        // Kill process
-        if let Some(mut process) = entry.process {
-            let _ = process.kill();
-        }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
     }
 
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        cmd.creation_flags(CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    cmd.spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))
+}
+
+/// Stop a dev server along with everything it started
+///
+/// On Unix the process group gets SIGTERM, then SIGKILL if anything is still alive after a
+/// grace period; on Windows `taskkill /T` takes down the tree.
+fn kill_process_tree(child: &mut Child) {
+    let pid = child.id();
+
+    #[cfg(unix)]
+    {
+        let pgid = pid as libc::pid_t;
+        // SAFETY: killpg only sends a signal; the group is the one spawn_dev_process created
+        unsafe { libc::killpg(pgid, libc::SIGTERM) };
+
+        let deadline = std::time::Instant::now() + DEV_SERVER_KILL_GRACE;
+        loop {
+            // Reap the leader so it doesn't linger as a zombie member of the group
+            let _ = child.try_wait();
+            let group_alive = unsafe { libc::killpg(pgid, 0) } == 0;
+            if !group_alive {
+                break;
+            }
+            if std::time::Instant::now() >= deadline {
+                log::warn!("Dev server group {} ignored SIGTERM, sending SIGKILL", pgid);
+                unsafe { libc::killpg(pgid, libc::SIGKILL) };
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Forward a dev server's output to the frontend, watching stdout for its port and readiness
+fn read_dev_server_output(
+    app: AppHandle,
+    project_path: String,
+    stdout: Option<ChildStdout>,
+    stderr: Option<ChildStderr>,
+    target_host: String,
+    ready_re: Option<regex::Regex>,
+) {
+    if let Some(mut stdout) = stdout {
+        let app = app.clone();
+        let project = project_path.clone();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 1024];
+            let mut log = DevServerLogWriter::new(app.clone(), project.clone(), "stdout");
+            let mut ready = false;
+            loop {
+                match stdout.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let entries = log.push(&buffer[..n]);
+                        let lines: Vec<String> =
+                            entries.iter().map(|e| e.message.clone()).collect();
+                        emit_log_entries(&app, &project, entries);
+                        for line in &lines {
+                            if !watch_stdout_line(
+                                &app,
+                                &project,
+                                &target_host,
+                                ready_re.as_ref(),
+                                &mut ready,
+                                line,
+                            ) {
+                                return;
+                            }
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            emit_log_entries(&app, &project, log.finish());
+        });
+    }
+
+    if let Some(stderr) = stderr {
+        forward_output(app, project_path, "stderr", stderr);
+    }
+}
+
+/// Look for the port and the ready message in one complete line of stdout
+///
+/// Returns false once the server is gone and there's nothing left to watch.
+fn watch_stdout_line(
+    app: &AppHandle,
+    project: &str,
+    target_host: &str,
+    ready_re: Option<&regex::Regex>,
+    ready: &mut bool,
+    line: &str,
+) -> bool {
+    // Try to detect port (only if not already set with fixed port)
+    let port_already_set = {
+        let servers = DEV_SERVERS.lock().unwrap();
+        servers
+            .servers
+            .get(project)
+            .and_then(|e| e.info.detected_port)
+            .is_some()
+    };
+
+    if !port_already_set {
+        if let Some(port) = detect_dev_server_port(line) {
+            log::info!("Detected dev server port from output: {}", port);
+
+            // Start proxy server
+            if let Some(proxy_port) = allocate_proxy_port(app, project, port) {
+                let proxy_cancel = {
+                    let mut servers = DEV_SERVERS.lock().unwrap();
+                    if let Some(entry) = servers.servers.get_mut(project) {
+                        entry.info.detected_port = Some(port);
+                        entry.info.original_url = Some(format!("http://{}:{}", target_host, port));
+                        entry.info.proxy_port = Some(proxy_port);
+                        entry.info.proxy_url = Some(format!("http://localhost:{}", proxy_port));
+                        entry.proxy_cancel.clone()
+                    } else {
+                        return false;
+                    }
+                };
+
+                // Start proxy in background
+                tauri::async_runtime::spawn(run_proxy_server(
+                    project.to_string(),
+                    proxy_port,
+                    target_host.to_string(),
+                    port,
+                    proxy_cancel,
+                ));
+
+                let _ = app.emit(
+                    "dev-server-output",
+                    DevServerOutput {
+                        project_path: project.to_string(),
+                        output_type: "port-detected".to_string(),
+                        message: format!("Dev server ready at http://localhost:{}", port),
+                        port: Some(port),
+                        proxy_url: Some(format!("http://localhost:{}", proxy_port)),
+                        seq: None,
+                    },
+                );
+            }
+        }
+    }
+
+    // Tell the frontend once the framework reports it's serving
+    if !*ready && ready_re.is_some_and(|re| re.is_match(line)) {
+        *ready = true;
+        let (port, proxy_url) = {
+            let servers = DEV_SERVERS.lock().unwrap();
+            servers
+                .servers
+                .get(project)
+                .map(|e| (e.info.detected_port, e.info.proxy_url.clone()))
+                .unwrap_or_default()
+        };
+        let _ = app.emit(
+            "dev-server-output",
+            DevServerOutput {
+                project_path: project.to_string(),
+                output_type: "ready".to_string(),
+                message: "Dev server is ready".to_string(),
+                port,
+                proxy_url,
+                seq: None,
+            },
+        );
+    }
+    true
+}
+
+/// Forward one output stream to the frontend and the project's log, as is
+fn forward_output(
+    app: AppHandle,
+    project_path: String,
+    stream: &'static str,
+    mut reader: impl Read + Send + 'static,
+) {
+    thread::spawn(move || {
+        let mut buffer = [0u8; 1024];
+        let mut log = DevServerLogWriter::new(app.clone(), project_path.clone(), stream);
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => emit_log_entries(&app, &project_path, log.push(&buffer[..n])),
+                Err(_) => break,
+            }
+        }
+        emit_log_entries(&app, &project_path, log.finish());
+    });
+}
+
+/// Send recorded output lines to the frontend, one event per line
+fn emit_log_entries(app: &AppHandle, project_path: &str, entries: Vec<DevServerLogEntry>) {
+    for entry in entries {
+        let _ = app.emit(
+            "dev-server-output",
+            DevServerOutput {
+                project_path: project_path.to_string(),
+                output_type: entry.stream,
+                message: entry.message,
+                port: None,
+                proxy_url: None,
+                seq: Some(entry.seq),
+            },
+        );
+    }
+}
+
+/// Watch a dev server process, report when it exits, and restart it after a crash
+///
+/// Restarts back off exponentially and stop after `MAX_DEV_SERVER_RESTARTS` crashes in a
+/// row; a run that stays up for `DEV_SERVER_STABLE_RUN` resets the count. Returns once the
+/// server is stopped, replaced or given up on.
+fn supervise_dev_server(
+    app: AppHandle,
+    project_path: String,
+    id: u64,
+    plan: LaunchPlan,
+    auto_restart: bool,
+) {
+    let mut attempts = 0u32;
+    let mut started = std::time::Instant::now();
+
+    loop {
+        thread::sleep(Duration::from_millis(500));
+
+        let status = {
+            let mut servers = DEV_SERVERS.lock().unwrap();
+            let Some(entry) = servers
+                .servers
+                .get_mut(&project_path)
+                .filter(|e| e.id == id)
+            else {
+                return;
+            };
+            let Some(process) = entry.process.as_mut() else {
+                return;
+            };
+            match process.try_wait() {
+                Ok(Some(status)) => status,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::warn!("Failed to check dev server for {}: {}", project_path, e);
+                    continue;
+                }
+            }
+        };
+
+        // The wrapper is gone, but whatever it started may still be holding the port
+        if let Some(mut process) = take_dev_process(&project_path, id) {
+            kill_process_tree(&mut process);
+        }
+
+        if started.elapsed() >= DEV_SERVER_STABLE_RUN {
+            attempts = 0;
+        }
+        let crashed = !status.success();
+        let delay = restart_delay(crashed, auto_restart, attempts);
+        let restart = delay.is_some();
+        let message = match status.code() {
+            Some(code) => format!("Dev server exited with code {}", code),
+            None => "Dev server was terminated by a signal".to_string(),
+        };
+        log::info!("{} ({})", message, project_path);
+        record_dev_server_lines(
+            &app,
+            &project_path,
+            "system",
+            Some(if crashed { "error" } else { "info" }),
+            vec![message.clone()],
+        );
+
+        {
+            let mut servers = DEV_SERVERS.lock().unwrap();
+            let Some(entry) = servers
+                .servers
+                .get_mut(&project_path)
+                .filter(|e| e.id == id)
+            else {
+                return;
+            };
+            entry.info.status = match (restart, crashed) {
+                (true, _) => "restarting",
+                (false, true) => "crashed",
+                (false, false) => "exited",
+            }
+            .to_string();
+            entry.info.exit_code = status.code();
+        }
+        let _ = app.emit(
+            "dev-server-output",
+            DevServerOutput {
+                project_path: project_path.clone(),
+                output_type: if crashed { "crashed" } else { "exited" }.to_string(),
+                message,
+                port: None,
+                proxy_url: None,
+                seq: None,
+            },
+        );
+        let Some(delay) = delay else {
+            return;
+        };
+        attempts += 1;
+        let message = format!(
+            "Restarting dev server in {}s (attempt {} of {})",
+            delay.as_secs(),
+            attempts,
+            MAX_DEV_SERVER_RESTARTS
+        );
+        record_dev_server_lines(
+            &app,
+            &project_path,
+            "system",
+            Some("info"),
+            vec![message.clone()],
+        );
+        let _ = app.emit(
+            "dev-server-output",
+            DevServerOutput {
+                project_path: project_path.clone(),
+                output_type: "info".to_string(),
+                message,
+                port: None,
+                proxy_url: None,
+                seq: None,
+            },
+        );
+        thread::sleep(delay);
+
+        match restart_dev_process(&app, &project_path, id, &plan) {
+            Ok(true) => started = std::time::Instant::now(),
+            Ok(false) => return,
+            Err(e) => {
+                log::error!("Failed to restart dev server for {}: {}", project_path, e);
+                if let Some(entry) = DEV_SERVERS
+                    .lock()
+                    .unwrap()
+                    .servers
+                    .get_mut(&project_path)
+                    .filter(|e| e.id == id)
+                {
+                    entry.info.status = "crashed".to_string();
+                }
+                record_dev_server_lines(
+                    &app,
+                    &project_path,
+                    "system",
+                    Some("error"),
+                    vec![e.clone()],
+                );
+                let _ = app.emit(
+                    "dev-server-output",
+                    DevServerOutput {
+                        project_path: project_path.clone(),
+                        output_type: "error".to_string(),
+                        message: e,
+                        port: None,
+                        proxy_url: None,
+                        seq: None,
+                    },
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Take the process out of a project's entry, unless the entry was replaced since `id` started
+fn take_dev_process(project_path: &str, id: u64) -> Option<Child> {
+    DEV_SERVERS
+        .lock()
+        .unwrap()
+        .servers
+        .get_mut(project_path)
+        .filter(|e| e.id == id)
+        .and_then(|e| e.process.take())
+}
+
+/// How long to wait before restarting a server that exited, or `None` to leave it down
+///
+/// `attempts` counts the crashes in a row so far; each doubles the delay.
+fn restart_delay(crashed: bool, auto_restart: bool, attempts: u32) -> Option<Duration> {
+    (crashed && auto_restart && attempts < MAX_DEV_SERVER_RESTARTS)
+        .then(|| Duration::from_secs(1 << attempts))
+}
+
+/// Start a crashed server's process again under the same entry
+///
+/// Returns `Ok(false)` if the server was stopped or replaced in the meantime.
+fn restart_dev_process(
+    app: &AppHandle,
+    project_path: &str,
+    id: u64,
+    plan: &LaunchPlan,
+) -> Result<bool, String> {
+    let mut process = spawn_dev_process(&plan.program, &plan.args, &plan.env, project_path)?;
+    let stdout = process.stdout.take();
+    let stderr = process.stderr.take();
+
+    {
+        let mut servers = DEV_SERVERS.lock().unwrap();
+        let Some(entry) = servers
+            .servers
+            .get_mut(project_path)
+            .filter(|e| e.id == id && e.info.status == "restarting")
+        else {
+            drop(servers);
+            kill_process_tree(&mut process);
+            return Ok(false);
+        };
+
+        entry.info.pid = process.id();
+        entry.info.status = "running".to_string();
+        entry.info.exit_code = None;
+        entry.info.restart_count += 1;
+        entry.process = Some(process);
+
+        // Without a pinned port the server may come back on a different one, so the proxy
+        // is restarted once the new port shows up in its output
+        if plan.port.is_none() {
+            entry.proxy_cancel.cancel();
+            entry.proxy_cancel = CancellationToken::new();
+            entry.info.detected_port = None;
+            entry.info.original_url = None;
+            entry.info.proxy_port = None;
+            entry.info.proxy_url = None;
+        }
+    }
+
+    read_dev_server_output(
+        app.clone(),
+        project_path.to_string(),
+        stdout,
+        stderr,
+        plan.host.clone(),
+        regex::Regex::new(&plan.ready_pattern).ok(),
+    );
+    let _ = app.emit(
+        "dev-server-output",
+        DevServerOutput {
+            project_path: project_path.to_string(),
+            output_type: "info".to_string(),
+            message: "Dev server restarted".to_string(),
+            port: None,
+            proxy_url: None,
+            seq: None,
+        },
+    );
+    Ok(true)
+}
+
+/// Build a project for production and preview the result, replacing any dev server it runs
+///
+/// The build runs in the background and its output arrives as `dev-server-output` events,
+/// like a dev server's. Once it succeeds the output directory is served, falling back to
+/// `index.html` for client-side routes, behind the same injecting proxy; a Next.js
+/// standalone build runs its own server instead. A failed build is reported as an `error`
+/// event and leaves the server "failed".
+#[tauri::command]
+pub async fn start_build_preview(
+    app: AppHandle,
+    db: tauri::State<'_, AgentDb>,
+    project_path: String,
+    project_id: Option<String>,
+) -> Result<(), String> {
+    log::info!("Starting build preview for: {}", project_path);
+    stop_dev_server(db.clone(), project_path.clone()).await?;
+
+    let overrides = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_dev_server_overrides(&conn, &project_path).map_err(|e| e.to_string())?
+    };
+    let plan = plan_build(Path::new(&project_path), overrides.as_ref())?;
+
+    // The preview takes the project's dev port; the dev server is stopped while it runs
+    let preferred = preferred_dev_port(project_id.as_deref().unwrap_or(&project_path));
+    let port = allocate_port_async(
+        &app,
+        &project_path,
+        project_id.as_deref(),
+        PortKind::Dev,
+        preferred,
+    )
+    .await?;
+
+    let mut process = if plan.program.is_empty() {
+        None
+    } else {
+        let process = spawn_dev_process(&plan.program, &plan.args, &plan.env, &project_path)?;
+        report_build_step(
+            &app,
+            &project_path,
+            "info",
+            "info",
+            format!("Building: {} {}", plan.program, plan.args.join(" ")),
+        );
+        Some(process)
+    };
+    let pid = process.as_ref().map(|p| p.id()).unwrap_or(0);
+    if let Some(process) = process.as_mut() {
+        if let Some(stdout) = process.stdout.take() {
+            forward_output(app.clone(), project_path.clone(), "stdout", stdout);
+        }
+        if let Some(stderr) = process.stderr.take() {
+            forward_output(app.clone(), project_path.clone(), "stderr", stderr);
+        }
+    }
+
+    let id = NEXT_DEV_SERVER_ID.fetch_add(1, Ordering::Relaxed);
+    let entry = DevServerEntry {
+        id,
+        process,
+        proxy_handle: None,
+        info: DevServerInfo {
+            project_path: project_path.clone(),
+            pid,
+            detected_port: None,
+            original_url: None,
+            proxy_port: None,
+            proxy_url: None,
+            status: "building".to_string(),
+            mode: "build".to_string(),
+            exit_code: None,
+            restart_count: 0,
+            log_tail: Vec::new(),
+        },
+        proxy_cancel: CancellationToken::new(),
+    };
+    DEV_SERVERS
+        .lock()
+        .unwrap()
+        .servers
+        .insert(project_path.clone(), entry);
+
+    thread::spawn(move || run_build_preview(app, project_path, id, plan, port));
+    Ok(())
+}
+
+/// Wait for a preview build to finish, then serve what it produced on `port`
+///
+/// Returns early if the preview is stopped or replaced while building.
+fn run_build_preview(app: AppHandle, project_path: String, id: u64, plan: BuildPlan, port: u16) {
+    let finished = loop {
+        {
+            let mut servers = DEV_SERVERS.lock().unwrap();
+            let Some(entry) = servers
+                .servers
+                .get_mut(&project_path)
+                .filter(|e| e.id == id)
+            else {
+                return;
+            };
+            let Some(process) = entry.process.as_mut() else {
+                break None; // Nothing to build
+            };
+            match process.try_wait() {
+                Ok(Some(status)) => break entry.process.take().map(|p| (p, status)),
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to check build for {}: {}", project_path, e),
+            }
+        }
+        thread::sleep(Duration::from_millis(500));
+    };
+
+    if let Some((mut build, status)) = finished {
+        // Bundlers can leave workers behind in the group
+        kill_process_tree(&mut build);
+        if !status.success() {
+            let message = match status.code() {
+                Some(code) => format!("Build failed with code {}", code),
+                None => "Build was terminated by a signal".to_string(),
+            };
+            fail_build_preview(&app, &project_path, id, status.code(), message);
+            return;
+        }
+    }
+
+    let Some(output) = find_build_output(Path::new(&project_path), &plan) else {
+        let message = format!(
+            "Build finished, but no output was found in {}",
+            plan.output_dirs.join(", ")
+        );
+        fail_build_preview(&app, &project_path, id, None, message);
+        return;
+    };
+    if let BuildOutput::NextStandalone(server_js) = &output {
+        if let Err(e) = prepare_next_standalone(Path::new(&project_path), server_js) {
+            let message = format!("Failed to prepare the standalone build: {}", e);
+            fail_build_preview(&app, &project_path, id, None, message);
+            return;
+        }
+    }
+    let Some(proxy_port) = allocate_proxy_port(&app, &project_path, port) else {
+        let message = "Could not find available port for proxy".to_string();
+        fail_build_preview(&app, &project_path, id, None, message);
+        return;
+    };
+
+    let serve_plan = plan_build_server(&output, port);
+    let mut process = match &serve_plan.static_root {
+        Some(_) => None,
+        None => {
+            match spawn_dev_process(
+                &serve_plan.program,
+                &serve_plan.args,
+                &serve_plan.env,
+                &project_path,
+            ) {
+                Ok(process) => Some(process),
+                Err(e) => {
+                    fail_build_preview(&app, &project_path, id, None, e);
+                    return;
+                }
+            }
+        }
+    };
+    let (stdout, stderr) = process
+        .as_mut()
+        .map(|p| (p.stdout.take(), p.stderr.take()))
+        .unwrap_or_default();
+    let proxy_url = format!("http://localhost:{}", proxy_port);
+
+    let proxy_cancel = {
+        let mut servers = DEV_SERVERS.lock().unwrap();
+        let Some(entry) = servers
+            .servers
+            .get_mut(&project_path)
+            .filter(|e| e.id == id)
+        else {
+            drop(servers);
+            if let Some(mut process) = process {
+                kill_process_tree(&mut process);
+            }
+            return;
+        };
+        entry.info.pid = process.as_ref().map(|p| p.id()).unwrap_or(0);
+        entry.info.status = "running".to_string();
+        entry.info.detected_port = Some(port);
+        entry.info.original_url = Some(format!("http://{}:{}", serve_plan.host, port));
+        entry.info.proxy_port = Some(proxy_port);
+        entry.info.proxy_url = Some(proxy_url.clone());
+        entry.process = process;
+        entry.proxy_cancel.clone()
+    };
+
+    if let Some(root) = &serve_plan.static_root {
+        tauri::async_runtime::spawn(run_static_server(
+            root.clone(),
+            port,
+            true,
+            proxy_cancel.clone(),
+        ));
+    }
+    tauri::async_runtime::spawn(run_proxy_server(
+        project_path.clone(),
+        proxy_port,
+        serve_plan.host.clone(),
+        port,
+        proxy_cancel,
+    ));
+    let _ = app.emit(
+        "dev-server-output",
+        DevServerOutput {
+            project_path: project_path.clone(),
+            output_type: "port-detected".to_string(),
+            message: format!("Build preview serving on port {}", port),
+            port: Some(port),
+            proxy_url: Some(proxy_url.clone()),
+            seq: None,
+        },
+    );
+
+    if serve_plan.static_root.is_some() {
+        let _ = app.emit(
+            "dev-server-output",
+            DevServerOutput {
+                project_path,
+                output_type: "ready".to_string(),
+                message: "Build preview is ready".to_string(),
+                port: Some(port),
+                proxy_url: Some(proxy_url),
+                seq: None,
+            },
+        );
+        return;
+    }
+
+    read_dev_server_output(
+        app.clone(),
+        project_path.clone(),
+        stdout,
+        stderr,
+        serve_plan.host.clone(),
+        regex::Regex::new(&serve_plan.ready_pattern).ok(),
+    );
+    supervise_dev_server(app, project_path, id, serve_plan, false);
+}
+
+/// Note a build preview step in the project's log and tell the frontend
+fn report_build_step(
+    app: &AppHandle,
+    project_path: &str,
+    output_type: &str,
+    level: &str,
+    message: String,
+) {
+    log::info!("{} ({})", message, project_path);
+    record_dev_server_lines(
+        app,
+        project_path,
+        "system",
+        Some(level),
+        vec![message.clone()],
+    );
+    let _ = app.emit(
+        "dev-server-output",
+        DevServerOutput {
+            project_path: project_path.to_string(),
+            output_type: output_type.to_string(),
+            message,
+            port: None,
+            proxy_url: None,
+            seq: None,
+        },
+    );
+}
+
+fn fail_build_preview(
+    app: &AppHandle,
+    project_path: &str,
+    id: u64,
+    exit_code: Option<i32>,
+    message: String,
+) {
+    if let Some(entry) = DEV_SERVERS
+        .lock()
+        .unwrap()
+        .servers
+        .get_mut(project_path)
+        .filter(|e| e.id == id)
+    {
+        entry.info.status = "failed".to_string();
+        entry.info.exit_code = exit_code;
+    }
+    report_build_step(app, project_path, "error", "error", message);
+}
+
+/// Copy in the assets a Next.js standalone build leaves for the deployment to provide
+///
+/// Without `.next/static` and `public` next to `server.js`, pages load unstyled.
+fn prepare_next_standalone(project: &Path, server_js: &Path) -> std::io::Result<()> {
+    let standalone = server_js.parent().unwrap_or(project);
+    copy_dir_all(
+        &project.join(".next").join("static"),
+        &standalone.join(".next").join("static"),
+    )?;
+    copy_dir_all(&project.join("public"), &standalone.join("public"))
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return Ok(());
+    }
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
     Ok(())
 }
 
+#[tauri::command]
+pub async fn stop_dev_server(
+    db: tauri::State<'_, AgentDb>,
+    project_path: String,
+) -> Result<(), String> {
+    log::info!("Stopping dev server for: {}", project_path);
+
+    // The project keeps its ports for the next start
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        reserve_project_ports(&conn, &project_path).map_err(|e| e.to_string())?;
+    }
+
+    let entry = DEV_SERVERS.lock().unwrap().servers.remove(&project_path);
+    if let Some(entry) = entry {
+        // Shut down the proxy
+        entry.proxy_cancel.cancel();
+
+        // Kill the process and everything it spawned
+        if let Some(mut process) = entry.process {
+            tauri::async_runtime::spawn_blocking(move || kill_process_tree(&mut process))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get a dev server's state, including the last lines of its output
 #[tauri::command]
 pub async fn get_dev_server_info(project_path: String) -> Result<Option<DevServerInfo>, String> {
     let servers = DEV_SERVERS.lock().unwrap();
-    Ok(servers.servers.get(&project_path).map(|e| e.info.clone()))
+    Ok(servers.servers.get(&project_path).map(|e| DevServerInfo {
+        log_tail: dev_server_log_tail(&project_path, DEV_SERVER_LOG_TAIL_LINES),
+        ..e.info.clone()
+    }))
+}
+
+/// PID of a project's running dev server process, which also leads its process group
+pub(crate) fn dev_server_pid(project_path: &str) -> Option<u32> {
+    let servers = DEV_SERVERS.lock().unwrap();
+    servers
+        .servers
+        .get(project_path)
+        .filter(|e| e.process.is_some())
+        .map(|e| e.info.pid)
+}
+
+/// Get console output and uncaught errors reported by a project's preview, oldest first
+///
+/// Pass the last `seq` seen as `since_seq` to fetch only newer entries; `limit` keeps the
+/// most recent ones.
+#[tauri::command]
+pub async fn get_preview_console_logs(
+    project_path: String,
+    since_seq: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<PreviewConsoleEntry>, String> {
+    let logs = PREVIEW_CONSOLE_LOGS.lock().map_err(|e| e.to_string())?;
+    let Some(buffer) = logs.get(&project_path) else {
+        return Ok(Vec::new());
+    };
+
+    let since_seq = since_seq.unwrap_or(0);
+    let mut entries: Vec<PreviewConsoleEntry> = buffer
+        .entries
+        .iter()
+        .filter(|e| e.seq > since_seq)
+        .cloned()
+        .collect();
+    if let Some(limit) = limit {
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+    }
+    Ok(entries)
+}
+
+/// Forget a project's preview console entries; numbering carries on for `since_seq` callers
+#[tauri::command]
+pub async fn clear_preview_console_logs(project_path: String) -> Result<(), String> {
+    let mut logs = PREVIEW_CONSOLE_LOGS.lock().map_err(|e| e.to_string())?;
+    if let Some(buffer) = logs.get_mut(&project_path) {
+        buffer.entries.clear();
+    }
+    Ok(())
 }
 
 /// Connect to an already running external dev server
@@ -1406,7 +2532,7 @@ pub async fn connect_to_existing_server(
     }
 
     // Find available proxy port
-    let proxy_port = match find_available_port(port + 10000) {
+    let proxy_port = match allocate_proxy_port_async(&app, &project_path, port).await {
         Some(p) => {
             log::info!("connect_to_existing_server: Found proxy port {}", p);
             p
@@ -1417,7 +2543,7 @@ pub async fn connect_to_existing_server(
         }
     };
 
-    let stop_flag = Arc::new(Mutex::new(false));
+    let proxy_cancel = CancellationToken::new();
 
     let info = DevServerInfo {
         project_path: project_path.clone(),
@@ -1426,13 +2552,19 @@ pub async fn connect_to_existing_server(
         original_url: Some(format!("http://localhost:{}", port)),
         proxy_port: Some(proxy_port),
         proxy_url: Some(format!("http://localhost:{}", proxy_port)),
+        status: "running".to_string(),
+        mode: "dev".to_string(),
+        exit_code: None,
+        restart_count: 0,
+        log_tail: Vec::new(),
     };
 
     let entry = DevServerEntry {
+        id: NEXT_DEV_SERVER_ID.fetch_add(1, Ordering::Relaxed),
         process: None, // No process to manage
         proxy_handle: None,
         info: info.clone(),
-        stop_flag: stop_flag.clone(),
+        proxy_cancel: proxy_cancel.clone(),
     };
 
     // Store in global state
@@ -1447,10 +2579,13 @@ pub async fn connect_to_existing_server(
         proxy_port,
         port
     );
-    let proxy_stop = stop_flag.clone();
-    thread::spawn(move || {
-        run_proxy_server(proxy_port, "localhost".to_string(), port, proxy_stop);
-    });
+    tauri::async_runtime::spawn(run_proxy_server(
+        project_path.clone(),
+        proxy_port,
+        "localhost".to_string(),
+        port,
+        proxy_cancel,
+    ));
 
     let proxy_url = format!("http://localhost:{}", proxy_port);
     log::info!("connect_to_existing_server: Proxy URL = {}", proxy_url);
@@ -1464,6 +2599,7 @@ pub async fn connect_to_existing_server(
             message: format!("Connected to existing server at localhost:{}", port),
             port: Some(port),
             proxy_url: Some(proxy_url.clone()),
+            seq: None,
         },
     );
 
@@ -1471,3 +2607,251 @@ pub async fn connect_to_existing_server(
     log::info!("connect_to_existing_server: Success, returning proxy URL");
     Ok(proxy_url)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn test_entry(id: u64, process: Child) -> DevServerEntry {
+        DevServerEntry {
+            id,
+            info: DevServerInfo {
+                project_path: String::new(),
+                pid: process.id(),
+                detected_port: None,
+                original_url: None,
+                proxy_port: None,
+                proxy_url: None,
+                status: "running".to_string(),
+                mode: "dev".to_string(),
+                exit_code: None,
+                restart_count: 0,
+                log_tail: Vec::new(),
+            },
+            process: Some(process),
+            proxy_handle: None,
+            proxy_cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Exited and either reaped or left as a zombie
+    #[cfg(target_os = "linux")]
+    fn process_gone(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat
+                .rsplit_once(')')
+                .map(|(_, rest)| rest.trim_start().starts_with('Z'))
+                .unwrap_or(false),
+            Err(_) => true,
+        }
+    }
+
+    #[test]
+    fn restart_delay_backs_off_and_gives_up() {
+        assert_eq!(restart_delay(false, true, 0), None);
+        assert_eq!(restart_delay(true, false, 0), None);
+        assert_eq!(restart_delay(true, true, 0), Some(Duration::from_secs(1)));
+        assert_eq!(restart_delay(true, true, 3), Some(Duration::from_secs(8)));
+        assert_eq!(restart_delay(true, true, MAX_DEV_SERVER_RESTARTS), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn supervisor_leaves_a_replaced_entry_alone() {
+        let project = "/tmp/dev-server-supervisor-test";
+        let process = spawn_dev_process("sleep", &["30".to_string()], &[], "/tmp").unwrap();
+        DEV_SERVERS
+            .lock()
+            .unwrap()
+            .servers
+            .insert(project.to_string(), test_entry(2, process));
+
+        // A supervisor for the entry this one replaced must not take its process
+        assert!(take_dev_process(project, 1).is_none());
+        let mut process = take_dev_process(project, 2).expect("current entry's process");
+        kill_process_tree(&mut process);
+        DEV_SERVERS.lock().unwrap().servers.remove(project);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kill_process_tree_stops_grandchildren() {
+        let script = "sleep 30 & echo $!; wait".to_string();
+        let mut process =
+            spawn_dev_process("sh", &["-c".to_string(), script], &[], "/tmp").unwrap();
+        let mut line = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(process.stdout.take().unwrap()),
+            &mut line,
+        )
+        .unwrap();
+        let grandchild: u32 = line.trim().parse().unwrap();
+        let leader = process.id();
+
+        kill_process_tree(&mut process);
+        assert!(process_gone(leader));
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while !process_gone(grandchild) && std::time::Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(process_gone(grandchild));
+    }
+
+    fn run_injector(chunks: &[&str]) -> String {
+        let mut injector = ScriptInjector::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            out.extend(injector.push(chunk.as_bytes()));
+        }
+        out.extend(injector.finish());
+        String::from_utf8(out).unwrap()
+    }
+
+    fn script_tag() -> String {
+        format!("<script>{}</script>", ELEMENT_SELECTOR_SCRIPT)
+    }
+
+    #[test]
+    fn injects_before_close_body_split_across_chunks() {
+        let html = run_injector(&["<html><body><p>hi</p></bo", "dy></html>"]);
+        assert_eq!(
+            html,
+            format!("<html><body><p>hi</p>{}</body></html>", script_tag())
+        );
+
+        let html = run_injector(&["<body>x<", "/", "b", "ody>"]);
+        assert_eq!(html, format!("<body>x{}</body>", script_tag()));
+    }
+
+    #[test]
+    fn matches_uppercase_tags_and_appends_without_close_body() {
+        let html = run_injector(&["<HTML><BODY>x</BODY></HTML>"]);
+        assert_eq!(html, format!("<HTML><BODY>x{}</BODY></HTML>", script_tag()));
+
+        let html = run_injector(&["<div>partial", " document"]);
+        assert_eq!(html, format!("<div>partial document{}", script_tag()));
+        assert_eq!(html.matches("<script>").count(), 1);
+    }
+
+    #[test]
+    fn injects_into_compressed_html() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"<html><body>gz</body></html>").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let html = decompress_body(&compressed, Some("gzip"));
+        let injected = inject_html(&String::from_utf8_lossy(&html));
+        assert_eq!(
+            injected,
+            format!("<html><body>gz{}</body></html>", script_tag())
+        );
+    }
+
+    #[tokio::test]
+    async fn buffers_small_bodies_and_hands_back_large_ones_intact() {
+        let BufferedBody::Complete(bytes) = buffer_body(Body::from("small"), 16).await.unwrap()
+        else {
+            panic!("expected the body to fit");
+        };
+        assert_eq!(&bytes[..], b"small");
+
+        let chunks = ["0123456789", "abcdefghij", "tail"]
+            .map(|c| Ok::<_, std::io::Error>(Bytes::from_static(c.as_bytes())));
+        let body = Body::from_stream(futures_util::stream::iter(chunks));
+        let BufferedBody::TooLarge(body) = buffer_body(body, 16).await.unwrap() else {
+            panic!("expected the body to exceed the limit");
+        };
+        let rest = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(&rest[..], b"0123456789abcdefghijtail");
+    }
+
+    #[test]
+    fn detects_the_port_from_a_whole_line() {
+        let line =
+            "  \x1b[32m➜\x1b[39m  \x1b[1mLocal\x1b[22m:   http://localhost:\x1b[1m5173\x1b[22m/";
+        assert_eq!(detect_dev_server_port(line), Some(5173));
+        // What a read cut short would have seen
+        assert_eq!(
+            detect_dev_server_port("  Local:   http://localhost:51"),
+            Some(51)
+        );
+        assert_eq!(
+            detect_dev_server_port("  press h + enter to show help"),
+            None
+        );
+    }
+
+    fn console_entry(message: &str) -> PreviewConsoleEntry {
+        PreviewConsoleEntry {
+            seq: 0,
+            kind: "console".to_string(),
+            level: "log".to_string(),
+            message: message.to_string(),
+            stack: None,
+            source: None,
+            line: None,
+            column: None,
+            page_url: None,
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn numbers_pages_and_evicts_console_entries() {
+        let project = "/tmp/console-paging";
+        let entries = (0..PREVIEW_CONSOLE_CAPACITY + 5)
+            .map(|i| console_entry(&i.to_string()))
+            .collect();
+        record_preview_console(project, entries);
+
+        let all = get_preview_console_logs(project.to_string(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), PREVIEW_CONSOLE_CAPACITY);
+        // The five oldest were dropped; seq keeps counting from the first entry
+        assert_eq!((all[0].seq, all[0].message.as_str()), (6, "5"));
+        assert_eq!(all.last().unwrap().seq, PREVIEW_CONSOLE_CAPACITY as u64 + 5);
+
+        let newer = get_preview_console_logs(project.to_string(), Some(1000), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            newer.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            [1001, 1002, 1003, 1004, 1005]
+        );
+        let latest = get_preview_console_logs(project.to_string(), Some(1000), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            latest.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            [1004, 1005]
+        );
+
+        clear_preview_console_logs(project.to_string())
+            .await
+            .unwrap();
+        assert!(get_preview_console_logs(project.to_string(), None, None)
+            .await
+            .unwrap()
+            .is_empty());
+        record_preview_console(project, vec![console_entry("after clear")]);
+        let after = get_preview_console_logs(project.to_string(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(after[0].seq, PREVIEW_CONSOLE_CAPACITY as u64 + 6);
+    }
+
+    #[test]
+    fn truncates_long_console_text_on_a_char_boundary() {
+        // 'é' is two bytes, so the limit falls inside the last one
+        let mut text = format!("a{}", "é".repeat(PREVIEW_CONSOLE_MAX_TEXT / 2));
+        truncate_text(&mut text);
+        let kept = text.strip_suffix("…[truncated]").unwrap();
+        assert_eq!(kept.len(), PREVIEW_CONSOLE_MAX_TEXT - 1);
+
+        let mut short = "é".repeat(10);
+        truncate_text(&mut short);
+        assert_eq!(short, "é".repeat(10));
+    }
+}
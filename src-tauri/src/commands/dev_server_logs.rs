@@ -0,0 +1,379 @@
+//! Dev server log capture
+//! Keeps a project's dev server output as numbered lines, in memory and on disk
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+/// Lines kept in memory per project; older ones are dropped first
+const DEV_SERVER_LOG_CAPACITY: usize = 5000;
+
+/// Longest line kept; anything beyond it is split into another line
+const DEV_SERVER_LOG_LINE_LIMIT: usize = 8 * 1024;
+
+/// Size at which a project's log file is rotated to `<name>.1`
+const DEV_SERVER_LOG_FILE_LIMIT: u64 = 2 * 1024 * 1024;
+
+/// One line of dev server output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevServerLogEntry {
+    pub seq: u64,          // Increasing per project, across app restarts
+    pub timestamp: String, // RFC3339
+    pub stream: String,    // "stdout", "stderr", "system"
+    pub level: String,     // "info", "warn", "error"
+    pub message: String,
+}
+
+struct DevServerLogBuffer {
+    next_seq: u64,
+    entries: VecDeque<DevServerLogEntry>,
+    file: Option<(PathBuf, File, u64)>, // Path, handle and current size
+}
+
+lazy_static::lazy_static! {
+    static ref DEV_SERVER_LOGS: Mutex<HashMap<String, DevServerLogBuffer>> =
+        Mutex::new(HashMap::new());
+}
+
+fn level_rank(level: &str) -> Option<u8> {
+    match level {
+        "info" => Some(0),
+        "warn" => Some(1),
+        "error" => Some(2),
+        _ => None,
+    }
+}
+
+/// Guess a line's level from its text; plenty of tools log everything to stderr
+fn classify_level(line: &str) -> &'static str {
+    lazy_static::lazy_static! {
+        static ref ERROR_RE: regex::Regex = regex::Regex::new(
+            r"(?i)\b(error|err!|failed|failure|exception|panicked|traceback|fatal)\b"
+        )
+        .unwrap();
+        static ref WARN_RE: regex::Regex =
+            regex::Regex::new(r"(?i)\b(warn|warning|deprecated)\b").unwrap();
+    }
+    if ERROR_RE.is_match(line) {
+        "error"
+    } else if WARN_RE.is_match(line) {
+        "warn"
+    } else {
+        "info"
+    }
+}
+
+/// Take the complete lines out of `pending` after appending `chunk`
+///
+/// Output is raw bytes, so a character can straddle two reads; lines are only decoded once
+/// they're complete. A partial line stays in `pending` for the next chunk unless it
+/// outgrows the line limit, in which case it's split before a character boundary.
+fn split_lines(pending: &mut Vec<u8>, chunk: &[u8]) -> Vec<String> {
+    pending.extend_from_slice(chunk);
+    let mut lines = Vec::new();
+    while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+    while pending.len() > DEV_SERVER_LOG_LINE_LIMIT {
+        let mut split = DEV_SERVER_LOG_LINE_LIMIT;
+        // Don't start the next line with a UTF-8 continuation byte
+        while split > DEV_SERVER_LOG_LINE_LIMIT - 3 && pending[split] & 0xC0 == 0x80 {
+            split -= 1;
+        }
+        let line: Vec<u8> = pending.drain(..split).collect();
+        lines.push(String::from_utf8_lossy(&line).into_owned());
+    }
+    lines
+}
+
+fn log_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("dev-server-logs"))
+}
+
+/// `<log dir>/<hash of the project path>.jsonl`
+fn log_file_path(log_dir: &Path, project_path: &str) -> PathBuf {
+    let hash = Sha256::digest(project_path.as_bytes());
+    let name: String = hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    log_dir.join(format!("{}.jsonl", name))
+}
+
+/// Load a project's buffer from its log file, so history survives app restarts
+fn load_buffer(log_dir: Option<&Path>, project_path: &str) -> DevServerLogBuffer {
+    let mut buffer = DevServerLogBuffer {
+        next_seq: 1,
+        entries: VecDeque::new(),
+        file: None,
+    };
+    let Some(log_dir) = log_dir else {
+        return buffer;
+    };
+
+    let path = log_file_path(log_dir, project_path);
+    if let Ok(file) = File::open(&path) {
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if let Ok(entry) = serde_json::from_str::<DevServerLogEntry>(&line) {
+                if buffer.entries.len() == DEV_SERVER_LOG_CAPACITY {
+                    buffer.entries.pop_front();
+                }
+                buffer.next_seq = entry.seq + 1;
+                buffer.entries.push_back(entry);
+            }
+        }
+    }
+
+    let opened = std::fs::create_dir_all(log_dir).and_then(|_| {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok((file, size))
+    });
+    match opened {
+        Ok((file, size)) => buffer.file = Some((path, file, size)),
+        Err(e) => log::warn!("Failed to open dev server log {}: {}", path.display(), e),
+    }
+    buffer
+}
+
+/// Append an entry to the log file, rotating it once it gets too big
+fn persist(file: &mut Option<(PathBuf, File, u64)>, entry: &DevServerLogEntry) {
+    let Some((path, handle, size)) = file.as_mut() else {
+        return;
+    };
+    let Ok(mut line) = serde_json::to_string(entry) else {
+        return;
+    };
+    line.push('\n');
+
+    if *size + line.len() as u64 > DEV_SERVER_LOG_FILE_LIMIT {
+        let rotated = path.with_extension("jsonl.1");
+        let reopened = std::fs::rename(&path, &rotated)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+        match reopened {
+            Ok(new_handle) => {
+                *handle = new_handle;
+                *size = 0;
+            }
+            Err(e) => {
+                log::warn!("Failed to rotate dev server log {}: {}", path.display(), e);
+                *file = None;
+                return;
+            }
+        }
+    }
+
+    if handle.write_all(line.as_bytes()).is_ok() {
+        *size += line.len() as u64;
+    }
+}
+
+/// Record lines of output for a project and return their entries
+///
+/// `level` is guessed from each line's text when not given.
+pub(crate) fn record_dev_server_lines(
+    app: &AppHandle,
+    project_path: &str,
+    stream: &str,
+    level: Option<&str>,
+    lines: Vec<String>,
+) -> Vec<DevServerLogEntry> {
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    let mut logs = DEV_SERVER_LOGS.lock().unwrap();
+    let buffer = logs
+        .entry(project_path.to_string())
+        .or_insert_with(|| load_buffer(log_dir(app).as_deref(), project_path));
+
+    let mut recorded = Vec::with_capacity(lines.len());
+    for line in lines {
+        let message = super::dev_server::strip_ansi(&line);
+        let entry = DevServerLogEntry {
+            seq: buffer.next_seq,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            stream: stream.to_string(),
+            level: level
+                .unwrap_or_else(|| classify_level(&message))
+                .to_string(),
+            message,
+        };
+        buffer.next_seq += 1;
+        persist(&mut buffer.file, &entry);
+        if buffer.entries.len() == DEV_SERVER_LOG_CAPACITY {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(entry.clone());
+        recorded.push(entry);
+    }
+    recorded
+}
+
+/// The last `count` messages logged for a project, oldest first
+pub(crate) fn dev_server_log_tail(project_path: &str, count: usize) -> Vec<String> {
+    let logs = DEV_SERVER_LOGS.lock().unwrap();
+    let Some(buffer) = logs.get(project_path) else {
+        return Vec::new();
+    };
+    let skip = buffer.entries.len().saturating_sub(count);
+    buffer
+        .entries
+        .iter()
+        .skip(skip)
+        .map(|e| e.message.clone())
+        .collect()
+}
+
+/// Turns one stream of a dev server's raw output into log lines
+pub(crate) struct DevServerLogWriter {
+    app: AppHandle,
+    project_path: String,
+    stream: &'static str,
+    pending: Vec<u8>,
+}
+
+impl DevServerLogWriter {
+    pub fn new(app: AppHandle, project_path: String, stream: &'static str) -> Self {
+        Self {
+            app,
+            project_path,
+            stream,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Record the complete lines a read finished; returns their entries
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<DevServerLogEntry> {
+        let lines = split_lines(&mut self.pending, chunk);
+        record_dev_server_lines(&self.app, &self.project_path, self.stream, None, lines)
+    }
+
+    /// Record whatever partial line is left once the stream closes
+    pub fn finish(mut self) -> Vec<DevServerLogEntry> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+        record_dev_server_lines(&self.app, &self.project_path, self.stream, None, vec![line])
+    }
+}
+
+/// Get a project's dev server output as lines, oldest first
+///
+/// Pass the last `seq` seen as `since_seq` to fetch only newer lines. `level_filter` is the
+/// lowest level to include ("info", "warn" or "error"); `limit` keeps the most recent lines.
+/// History from earlier app sessions is loaded from disk.
+#[tauri::command]
+pub async fn get_dev_server_logs(
+    app: AppHandle,
+    project_path: String,
+    since_seq: Option<u64>,
+    level_filter: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<DevServerLogEntry>, String> {
+    let min_rank = match level_filter.as_deref() {
+        Some(level) => level_rank(level).ok_or_else(|| format!("Unknown log level: {}", level))?,
+        None => 0,
+    };
+
+    let mut logs = DEV_SERVER_LOGS.lock().map_err(|e| e.to_string())?;
+    let buffer = logs
+        .entry(project_path.clone())
+        .or_insert_with(|| load_buffer(log_dir(&app).as_deref(), &project_path));
+
+    let since_seq = since_seq.unwrap_or(0);
+    let mut entries: Vec<DevServerLogEntry> = buffer
+        .entries
+        .iter()
+        .filter(|e| e.seq > since_seq)
+        .filter(|e| level_rank(&e.level).unwrap_or(0) >= min_rank)
+        .cloned()
+        .collect();
+    if let Some(limit) = limit {
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_lines_across_chunks() {
+        let mut pending = Vec::new();
+        assert_eq!(
+            split_lines(&mut pending, b"VITE v5 ready\r\n  Local: "),
+            ["VITE v5 ready"]
+        );
+        assert_eq!(
+            split_lines(&mut pending, b"http://localhost:5173/\n\nerror"),
+            ["  Local: http://localhost:5173/", ""]
+        );
+        assert_eq!(pending, b"error");
+
+        let long = "x".repeat(DEV_SERVER_LOG_LINE_LIMIT + 10);
+        let mut pending = Vec::new();
+        let lines = split_lines(&mut pending, long.as_bytes());
+        assert_eq!(lines.len(), 1);
+        assert_eq!(pending.len(), 10);
+    }
+
+    #[test]
+    fn keeps_characters_split_between_reads() {
+        let output = "  ➜  Local:   http://localhost:5173/\n".as_bytes();
+        // Split inside the three bytes of the arrow
+        let (first, rest) = output.split_at(4);
+        let mut pending = Vec::new();
+        assert!(split_lines(&mut pending, first).is_empty());
+        assert_eq!(
+            split_lines(&mut pending, rest),
+            ["  ➜  Local:   http://localhost:5173/"]
+        );
+
+        // An over-long line is cut before the arrow rather than through it
+        let mut long = "x".repeat(DEV_SERVER_LOG_LINE_LIMIT - 1).into_bytes();
+        long.extend_from_slice("➜ more".as_bytes());
+        let mut pending = Vec::new();
+        let lines = split_lines(&mut pending, &long);
+        assert_eq!(lines, ["x".repeat(DEV_SERVER_LOG_LINE_LIMIT - 1)]);
+        assert_eq!(pending, "➜ more".as_bytes());
+    }
+
+    #[test]
+    fn classifies_levels_and_reloads_from_disk() {
+        assert_eq!(
+            classify_level("[vite] Internal server error: boom"),
+            "error"
+        );
+        assert_eq!(classify_level("npm WARN deprecated glob@7"), "warn");
+        assert_eq!(classify_level("compiled successfully"), "info");
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = load_buffer(Some(dir.path()), "/work/app");
+        for seq in 1..=3 {
+            let entry = DevServerLogEntry {
+                seq,
+                timestamp: String::new(),
+                stream: "stdout".to_string(),
+                level: "info".to_string(),
+                message: format!("line {}", seq),
+            };
+            persist(&mut buffer.file, &entry);
+        }
+        drop(buffer);
+
+        let reloaded = load_buffer(Some(dir.path()), "/work/app");
+        assert_eq!(reloaded.entries.len(), 3);
+        assert_eq!(reloaded.next_seq, 4);
+    }
+}
@@ -1,14 +1,19 @@
 //! Development workflow auto-routing
 //! Based on anyon-mvp's PM workflow logic
 //!
-//! This module handles automatic workflow progression:
+//! A workflow is a state machine: each state runs a slash command or prompt, and its
+//! outcome picks the next state. Projects can define their own workflow in
+//! `anyon-docs/dev-workflow.yaml` (or `.yml` / `.json`); otherwise the built-in PM flow is used:
 //! pm-orchestrator → pm-executor ↔ pm-reviewer (cycles until complete)
 
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use tauri::{AppHandle, Manager};
 
+use super::budget::{self, BudgetEvent, BudgetUsage, RunBudget};
+
 // ============================================================================
 // Constants
 // ============================================================================
@@ -26,8 +31,230 @@ pub const COMPLETION_MARKERS: &[&str] = &[
     "Project Implementation Complete",
 ];
 
+/// Project files that can override the built-in workflow, in lookup order
+pub const WORKFLOW_DEFINITION_FILES: &[&str] = &[
+    "anyon-docs/dev-workflow.yaml",
+    "anyon-docs/dev-workflow.yml",
+    "anyon-docs/dev-workflow.json",
+];
+
 const MAX_DEV_CYCLES: i32 = 100;
 
+// ============================================================================
+// Workflow Definition
+// ============================================================================
+
+/// A condition checked after a state succeeds; paths are relative to the project
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompletionPredicate {
+    /// The file exists and contains at least one of the markers
+    FileContains { path: String, any_of: Vec<String> },
+    /// The file exists
+    FileExists { path: String },
+    /// The shell command, run in the project directory, exits with `exit_code`
+    Command {
+        run: String,
+        #[serde(default)]
+        exit_code: i32,
+    },
+}
+
+impl CompletionPredicate {
+    pub fn is_satisfied(&self, project_path: &str) -> bool {
+        let project = Path::new(project_path);
+        match self {
+            CompletionPredicate::FileContains { path, any_of } => {
+                match std::fs::read_to_string(project.join(path)) {
+                    Ok(content) => any_of.iter().any(|marker| content.contains(marker)),
+                    Err(_) => false,
+                }
+            }
+            CompletionPredicate::FileExists { path } => project.join(path).exists(),
+            CompletionPredicate::Command { run, exit_code } => {
+                #[cfg(target_os = "windows")]
+                let mut cmd = {
+                    let mut cmd = std::process::Command::new("cmd");
+                    cmd.args(["/C", run]);
+                    cmd
+                };
+                #[cfg(not(target_os = "windows"))]
+                let mut cmd = {
+                    let mut cmd = std::process::Command::new("sh");
+                    cmd.args(["-c", run]);
+                    cmd
+                };
+
+                match cmd.current_dir(project).output() {
+                    Ok(output) => output.status.code() == Some(*exit_code),
+                    Err(e) => {
+                        log::warn!("Dev workflow: completion command '{}' failed: {}", run, e);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One step of a workflow
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowState {
+    /// Slash command or prompt sent to Claude
+    pub prompt: String,
+    /// Model for this state; defaults to the model the workflow was started with
+    #[serde(default)]
+    pub model: Option<String>,
+    /// The workflow completes when any of these holds after the state succeeds
+    #[serde(default)]
+    pub complete_when: Vec<CompletionPredicate>,
+    /// Next state after a successful run; the workflow completes if unset
+    #[serde(default)]
+    pub on_success: Option<String>,
+    /// Next state after a failed run; the workflow stops with an error if unset
+    #[serde(default)]
+    pub on_failure: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowDefinition {
+    pub name: String,
+    pub initial_state: String,
+    #[serde(default = "default_max_cycles")]
+    pub max_cycles: i32,
+    /// Limits on all the Claude runs of one workflow together; each run gets what is left.
+    /// The turn limit is the exception and applies to each run on its own.
+    #[serde(default)]
+    pub budget: Option<RunBudget>,
+    pub states: BTreeMap<String, WorkflowState>,
+}
+
+fn default_max_cycles() -> i32 {
+    MAX_DEV_CYCLES
+}
+
+/// Where the workflow goes after a state finishes
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkflowTransition {
+    Next(String),
+    Completed,
+    Failed,
+}
+
+impl WorkflowDefinition {
+    /// The built-in PM workflow: orchestrate once, then execute and review until done
+    pub fn builtin() -> Self {
+        let state = |prompt: &str, on_success: &str| WorkflowState {
+            prompt: prompt.to_string(),
+            model: None,
+            complete_when: Vec::new(),
+            on_success: Some(on_success.to_string()),
+            on_failure: None,
+        };
+
+        let mut reviewer = state(PM_REVIEWER_PROMPT, "pm-executor");
+        reviewer.complete_when = vec![CompletionPredicate::FileContains {
+            path: EXECUTION_PROGRESS_FILE.to_string(),
+            any_of: COMPLETION_MARKERS.iter().map(|m| m.to_string()).collect(),
+        }];
+
+        let mut states = BTreeMap::new();
+        states.insert(
+            "pm-orchestrator".to_string(),
+            state(PM_ORCHESTRATOR_PROMPT, "pm-executor"),
+        );
+        states.insert(
+            "pm-executor".to_string(),
+            state(PM_EXECUTOR_PROMPT, "pm-reviewer"),
+        );
+        states.insert("pm-reviewer".to_string(), reviewer);
+
+        Self {
+            name: "pm".to_string(),
+            initial_state: "pm-orchestrator".to_string(),
+            max_cycles: MAX_DEV_CYCLES,
+            budget: None,
+            states,
+        }
+    }
+
+    /// Parse a definition from YAML or JSON (JSON is valid YAML, so one parser covers both)
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let definition: Self = serde_yaml::from_str(content)
+            .map_err(|e| format!("Invalid workflow definition: {}", e))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Load the project's workflow, falling back to the built-in one
+    pub fn load_for_project(project_path: &str) -> Result<Self, String> {
+        for file in WORKFLOW_DEFINITION_FILES {
+            let path = Path::new(project_path).join(file);
+            if path.exists() {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                return Self::parse(&content).map_err(|e| format!("{}: {}", file, e));
+            }
+        }
+        Ok(Self::builtin())
+    }
+
+    /// Check that every referenced state exists
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.states.contains_key(&self.initial_state) {
+            return Err(format!("Unknown initial state '{}'", self.initial_state));
+        }
+        for (id, state) in &self.states {
+            for target in [&state.on_success, &state.on_failure].into_iter().flatten() {
+                if !self.states.contains_key(target) {
+                    return Err(format!(
+                        "State '{}' transitions to unknown state '{}'",
+                        id, target
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn state(&self, id: &str) -> Result<&WorkflowState, String> {
+        self.states
+            .get(id)
+            .ok_or_else(|| format!("Unknown workflow state '{}'", id))
+    }
+
+    /// Decide the transition out of `state_id` given the run's outcome
+    pub fn next_transition(
+        &self,
+        state_id: &str,
+        success: bool,
+        project_path: &str,
+    ) -> Result<WorkflowTransition, String> {
+        let state = self.state(state_id)?;
+
+        let next = if success {
+            if state
+                .complete_when
+                .iter()
+                .any(|p| p.is_satisfied(project_path))
+            {
+                return Ok(WorkflowTransition::Completed);
+            }
+            match &state.on_success {
+                Some(next) => next,
+                None => return Ok(WorkflowTransition::Completed),
+            }
+        } else {
+            match &state.on_failure {
+                Some(next) => next,
+                None => return Ok(WorkflowTransition::Failed),
+            }
+        };
+
+        Ok(WorkflowTransition::Next(next.clone()))
+    }
+}
+
 // ============================================================================
 // DB Schema
 // ============================================================================
@@ -38,11 +265,50 @@ pub struct DevSession {
     pub project_path: String,
     pub last_prompt: String,
     pub cycle_count: i32,
-    pub status: String, // "idle", "running", "completed", "error"
+    pub status: String, // "idle", "running", "paused", "completed", "error"
+    pub workflow_name: String,
+    pub current_state: String,
+    pub model: String,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// One executed workflow state
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DevSessionStep {
+    pub id: i64,
+    pub dev_session_id: i64,
+    pub project_path: String,
+    pub cycle: i32,
+    pub state: String,
+    pub prompt: String,
+    pub model: String,
+    pub claude_session_id: Option<String>,
+    pub status: String, // "running", "completed", "failed", "budget_exceeded", "cancelled", "interrupted"
+    pub exit_code: Option<i32>,
+    pub cost_usd: Option<f64>,
+    pub total_tokens: Option<i64>,
+    pub git_head_before: Option<String>,
+    pub git_head_after: Option<String>,
+    pub started_at: String,
+    pub completed_at: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// What a finished Claude run reports back to the workflow
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeRunSummary {
+    pub success: bool,
+    pub cancelled: bool,
+    pub session_id: Option<String>,
+    pub exit_code: Option<i32>,
+    pub cost_usd: Option<f64>,
+    /// Input plus output tokens, counted when the run has a budget
+    pub total_tokens: Option<u64>,
+    /// Why the run was stopped for going over its budget
+    pub budget_exceeded: Option<String>,
+}
+
 /// Initialize dev_sessions table
 pub fn init_dev_workflow_db(conn: &Connection) -> rusqlite::Result<()> {
     conn.execute(
@@ -52,11 +318,81 @@ pub fn init_dev_workflow_db(conn: &Connection) -> rusqlite::Result<()> {
             last_prompt TEXT NOT NULL DEFAULT '',
             cycle_count INTEGER NOT NULL DEFAULT 0,
             status TEXT NOT NULL DEFAULT 'idle',
+            workflow_name TEXT NOT NULL DEFAULT '',
+            current_state TEXT NOT NULL DEFAULT '',
+            model TEXT NOT NULL DEFAULT '',
+            workflow_definition TEXT,
+            workflow_started_at TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         )",
         [],
     )?;
+
+    // Add workflow columns to existing tables
+    let _ = conn.execute(
+        "ALTER TABLE dev_sessions ADD COLUMN workflow_name TEXT NOT NULL DEFAULT ''",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE dev_sessions ADD COLUMN current_state TEXT NOT NULL DEFAULT ''",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE dev_sessions ADD COLUMN model TEXT NOT NULL DEFAULT ''",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE dev_sessions ADD COLUMN workflow_definition TEXT",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE dev_sessions ADD COLUMN workflow_started_at TEXT",
+        [],
+    );
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dev_session_steps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            dev_session_id INTEGER NOT NULL,
+            project_path TEXT NOT NULL,
+            cycle INTEGER NOT NULL,
+            state TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            model TEXT NOT NULL,
+            claude_session_id TEXT,
+            status TEXT NOT NULL DEFAULT 'running',
+            exit_code INTEGER,
+            cost_usd REAL,
+            total_tokens INTEGER,
+            git_head_before TEXT,
+            git_head_after TEXT,
+            started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+            completed_at TEXT,
+            duration_ms INTEGER,
+            FOREIGN KEY (dev_session_id) REFERENCES dev_sessions(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    let _ = conn.execute(
+        "ALTER TABLE dev_session_steps ADD COLUMN total_tokens INTEGER",
+        [],
+    );
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_dev_session_steps_project ON dev_session_steps(project_path)",
+        [],
+    )?;
+
+    // Workflows running when the app quit lost their Claude process; pause them so they can
+    // be resumed from the interrupted step
+    conn.execute(
+        "UPDATE dev_session_steps SET status = 'interrupted' WHERE status = 'running'",
+        [],
+    )?;
+    conn.execute(
+        "UPDATE dev_sessions SET status = 'paused' WHERE status = 'running'",
+        [],
+    )?;
     Ok(())
 }
 
@@ -70,7 +406,7 @@ pub fn get_or_create_dev_session(
 ) -> rusqlite::Result<DevSession> {
     // Try to get existing session
     let result = conn.query_row(
-        "SELECT id, project_path, last_prompt, cycle_count, status, created_at, updated_at
+        "SELECT id, project_path, last_prompt, cycle_count, status, workflow_name, current_state, model, created_at, updated_at
          FROM dev_sessions WHERE project_path = ?1",
         params![project_path],
         |row| {
@@ -80,8 +416,11 @@ pub fn get_or_create_dev_session(
                 last_prompt: row.get(2)?,
                 cycle_count: row.get(3)?,
                 status: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
+                workflow_name: row.get(5)?,
+                current_state: row.get(6)?,
+                model: row.get(7)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
             })
         },
     );
@@ -101,6 +440,9 @@ pub fn get_or_create_dev_session(
                 last_prompt: String::new(),
                 cycle_count: 0,
                 status: "idle".to_string(),
+                workflow_name: String::new(),
+                current_state: String::new(),
+                model: String::new(),
                 created_at: String::new(),
                 updated_at: String::new(),
             })
@@ -112,23 +454,217 @@ pub fn get_or_create_dev_session(
 pub fn update_dev_session(
     conn: &Connection,
     project_path: &str,
+    current_state: &str,
     last_prompt: &str,
     cycle_count: i32,
     status: &str,
 ) -> rusqlite::Result<()> {
     conn.execute(
         "UPDATE dev_sessions
-         SET last_prompt = ?1, cycle_count = ?2, status = ?3, updated_at = datetime('now')
+         SET current_state = ?1, last_prompt = ?2, cycle_count = ?3, status = ?4, updated_at = datetime('now')
+         WHERE project_path = ?5",
+        params![current_state, last_prompt, cycle_count, status, project_path],
+    )?;
+    Ok(())
+}
+
+/// Pin the workflow a session runs, so edits to the project file don't affect a run in progress.
+/// Also marks where the workflow started, which its budget counts from.
+pub fn set_dev_session_workflow(
+    conn: &Connection,
+    project_path: &str,
+    definition: &WorkflowDefinition,
+    model: &str,
+) -> Result<(), String> {
+    let definition_json = serde_json::to_string(definition).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE dev_sessions
+         SET workflow_name = ?1, workflow_definition = ?2, model = ?3,
+             workflow_started_at = strftime('%Y-%m-%d %H:%M:%f', 'now'), updated_at = datetime('now')
          WHERE project_path = ?4",
-        params![last_prompt, cycle_count, status, project_path],
+        params![definition.name, definition_json, model, project_path],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Get the workflow pinned to a session
+pub fn get_dev_session_workflow(
+    conn: &Connection,
+    project_path: &str,
+) -> Result<WorkflowDefinition, String> {
+    let definition_json: Option<String> = conn
+        .query_row(
+            "SELECT workflow_definition FROM dev_sessions WHERE project_path = ?1",
+            params![project_path],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    match definition_json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        // Sessions started before workflows were configurable ran the built-in flow
+        None => Ok(WorkflowDefinition::builtin()),
+    }
+}
+
+/// Record the start of a workflow state
+pub fn begin_dev_step(
+    conn: &Connection,
+    session: &DevSession,
+    cycle: i32,
+    state: &str,
+    prompt: &str,
+    model: &str,
+    git_head_before: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO dev_session_steps (dev_session_id, project_path, cycle, state, prompt, model, git_head_before)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            session.id,
+            session.project_path,
+            cycle,
+            state,
+            prompt,
+            model,
+            git_head_before
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Find the step a finished run belongs to
+pub fn find_running_dev_step(
+    conn: &Connection,
+    project_path: &str,
+    prompt: &str,
+) -> rusqlite::Result<Option<i64>> {
+    match conn.query_row(
+        "SELECT id FROM dev_session_steps
+         WHERE project_path = ?1 AND prompt = ?2 AND status = 'running'
+         ORDER BY id DESC LIMIT 1",
+        params![project_path, prompt],
+        |row| row.get(0),
+    ) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Record how a step ended
+pub fn finish_dev_step(
+    conn: &Connection,
+    step_id: i64,
+    status: &str,
+    run: Option<&ClaudeRunSummary>,
+    git_head_after: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE dev_session_steps
+         SET status = ?1,
+             claude_session_id = COALESCE(?2, claude_session_id),
+             exit_code = ?3,
+             cost_usd = ?4,
+             total_tokens = ?5,
+             git_head_after = ?6,
+             completed_at = strftime('%Y-%m-%d %H:%M:%f', 'now'),
+             duration_ms = CAST((julianday('now') - julianday(started_at)) * 86400000 AS INTEGER)
+         WHERE id = ?7",
+        params![
+            status,
+            run.and_then(|r| r.session_id.as_deref()),
+            run.and_then(|r| r.exit_code),
+            run.and_then(|r| r.cost_usd),
+            run.and_then(|r| r.total_tokens).map(|tokens| tokens as i64),
+            git_head_after,
+            step_id
+        ],
     )?;
     Ok(())
 }
 
+/// Mark a project's in-flight steps as cancelled
+fn cancel_running_dev_steps(
+    conn: &Connection,
+    project_path: &str,
+    git_head_after: Option<&str>,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM dev_session_steps WHERE project_path = ?1 AND status = 'running'",
+    )?;
+    let ids = stmt
+        .query_map(params![project_path], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in ids {
+        finish_dev_step(conn, id, "cancelled", None, git_head_after)?;
+    }
+    Ok(())
+}
+
+pub fn list_dev_steps(
+    conn: &Connection,
+    project_path: &str,
+) -> rusqlite::Result<Vec<DevSessionStep>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, dev_session_id, project_path, cycle, state, prompt, model, claude_session_id, status,
+                exit_code, cost_usd, total_tokens, git_head_before, git_head_after, started_at, completed_at,
+                duration_ms
+         FROM dev_session_steps WHERE project_path = ?1 ORDER BY id ASC",
+    )?;
+    let steps = stmt
+        .query_map(params![project_path], |row| {
+            Ok(DevSessionStep {
+                id: row.get(0)?,
+                dev_session_id: row.get(1)?,
+                project_path: row.get(2)?,
+                cycle: row.get(3)?,
+                state: row.get(4)?,
+                prompt: row.get(5)?,
+                model: row.get(6)?,
+                claude_session_id: row.get(7)?,
+                status: row.get(8)?,
+                exit_code: row.get(9)?,
+                cost_usd: row.get(10)?,
+                total_tokens: row.get(11)?,
+                git_head_before: row.get(12)?,
+                git_head_after: row.get(13)?,
+                started_at: row.get(14)?,
+                completed_at: row.get(15)?,
+                duration_ms: row.get(16)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(steps)
+}
+
+/// What the finished steps of the project's current workflow have used together
+///
+/// Turns aren't counted: a workflow budget's turn limit applies to each step.
+pub fn workflow_usage(conn: &Connection, project_path: &str) -> rusqlite::Result<BudgetUsage> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0), COALESCE(SUM(total_tokens), 0), COALESCE(SUM(duration_ms), 0)
+         FROM dev_session_steps
+         WHERE project_path = ?1 AND status != 'running'
+           AND started_at >= COALESCE(
+               (SELECT workflow_started_at FROM dev_sessions WHERE project_path = ?1), '')",
+        params![project_path],
+        |row| {
+            Ok(BudgetUsage {
+                cost_usd: row.get(0)?,
+                total_tokens: row.get::<_, i64>(1)? as u64,
+                turns: 0,
+                duration_ms: row.get::<_, i64>(2)? as u64,
+            })
+        },
+    )
+}
+
 pub fn reset_dev_session(conn: &Connection, project_path: &str) -> rusqlite::Result<()> {
     conn.execute(
         "UPDATE dev_sessions
-         SET last_prompt = '', cycle_count = 0, status = 'idle', updated_at = datetime('now')
+         SET last_prompt = '', current_state = '', cycle_count = 0, status = 'idle', updated_at = datetime('now')
          WHERE project_path = ?1",
         params![project_path],
     )?;
@@ -136,61 +672,135 @@ pub fn reset_dev_session(conn: &Connection, project_path: &str) -> rusqlite::Res
 }
 
 // ============================================================================
-// Auto-routing Logic
+// Tauri Commands
 // ============================================================================
 
-/// Check if all epics are completed by reading execution-progress.md
-pub fn is_dev_complete(project_path: &str) -> bool {
-    let progress_file = Path::new(project_path).join(EXECUTION_PROGRESS_FILE);
-
-    match std::fs::read_to_string(&progress_file) {
-        Ok(content) => COMPLETION_MARKERS
-            .iter()
-            .any(|marker| content.contains(marker)),
-        Err(_) => false,
-    }
+/// Current HEAD of the project's repository, if it is one
+async fn git_head(project_path: &str) -> Option<String> {
+    super::git::get_git_head_sha(project_path.to_string())
+        .await
+        .ok()
 }
 
-/// Determine next workflow based on last prompt
-/// Returns: Some(next_prompt) or None if complete
-pub fn get_next_workflow(last_prompt: &str, is_complete: bool) -> Option<&'static str> {
-    if last_prompt.contains("pm-orchestrator") {
-        return Some(PM_EXECUTOR_PROMPT);
-    }
+/// Record a step for the state and start its Claude run
+async fn run_workflow_state(
+    app: &AppHandle,
+    project_path: &str,
+    state_id: &str,
+    state: &WorkflowState,
+    model: &str,
+) -> Result<(), String> {
+    let git_head_before = git_head(project_path).await;
 
-    if last_prompt.contains("pm-executor") {
-        return Some(PM_REVIEWER_PROMPT);
-    }
+    let (step_id, budget) = {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let session = get_or_create_dev_session(&conn, project_path).map_err(|e| e.to_string())?;
+        let used = workflow_usage(&conn, project_path).map_err(|e| e.to_string())?;
+        let budget = get_dev_session_workflow(&conn, project_path)?
+            .budget
+            .map(|budget| budget.remaining(&used));
+        let step_id = begin_dev_step(
+            &conn,
+            &session,
+            session.cycle_count,
+            state_id,
+            &state.prompt,
+            model,
+            git_head_before.as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
+        (step_id, budget)
+    };
 
-    if last_prompt.contains("pm-reviewer") {
-        if is_complete {
-            return None; // All done!
-        }
-        return Some(PM_EXECUTOR_PROMPT); // Next wave
+    let result = super::claude::execute_claude_code(
+        app.clone(),
+        project_path.to_string(),
+        state.prompt.clone(),
+        model.to_string(),
+        None, // execution_mode: default to execute
+        None, // input_streaming: the prompt is the whole turn
+        // options: nobody answers permission prompts, and the step runs within the budget
+        Some(
+            super::claude::ClaudeRunOptions::default()
+                .unattended(true)
+                .budget(budget),
+        ),
+    )
+    .await;
+
+    if result.is_err() {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        finish_dev_step(&conn, step_id, "failed", None, git_head_before.as_deref())
+            .map_err(|e| e.to_string())?;
     }
 
-    None
+    result
 }
 
-/// Check if prompt is a dev workflow prompt
-pub fn is_dev_workflow_prompt(prompt: &str) -> bool {
-    prompt.contains("pm-orchestrator")
-        || prompt.contains("pm-executor")
-        || prompt.contains("pm-reviewer")
+/// Run a workflow state in a background thread to avoid Send trait issues
+fn spawn_workflow_state(
+    app: AppHandle,
+    project_path: String,
+    state_id: String,
+    state: WorkflowState,
+    model: String,
+) {
+    std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            if let Err(e) = run_workflow_state(&app, &project_path, &state_id, &state, &model).await
+            {
+                log::error!("Failed to start dev workflow state '{}': {}", state_id, e);
+            }
+        });
+    });
 }
 
-// ============================================================================
-// Tauri Commands
-// ============================================================================
+/// Stop the project's Claude runs and record the in-flight step as cancelled
+async fn cancel_workflow_runs(app: &AppHandle, project_path: &str) -> Result<(), String> {
+    let git_head_after = git_head(project_path).await;
+    {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        cancel_running_dev_steps(&conn, project_path, git_head_after.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Cancel only this project's runs, in background thread to avoid Send trait issues
+    let app = app.clone();
+    let project_path = project_path.to_string();
+    std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            if let Err(e) =
+                super::claude::execution::cancel_claude_runs_for_project(&app, &project_path).await
+            {
+                log::error!("Failed to cancel Claude execution: {}", e);
+            }
+        });
+    });
 
+    Ok(())
+}
+
+/// Start the project's workflow from its initial state
+///
+/// `budget`, when given, replaces the definition's budget for this workflow.
 #[tauri::command]
 pub async fn start_dev_workflow(
     app: AppHandle,
     project_path: String,
     model: String,
+    budget: Option<RunBudget>,
 ) -> Result<(), String> {
     log::info!("Starting dev workflow for: {}", project_path);
 
+    let mut definition = WorkflowDefinition::load_for_project(&project_path)?;
+    if budget.is_some() {
+        definition.budget = budget;
+    }
+    let initial = definition.state(&definition.initial_state)?.clone();
+
     let db = app.state::<super::agents::AgentDb>();
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
@@ -198,35 +808,37 @@ pub async fn start_dev_workflow(
     let session = get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())?;
 
     // Reset if needed
-    if session.status == "completed" || session.status == "error" {
+    if matches!(session.status.as_str(), "completed" | "error" | "paused") {
         reset_dev_session(&conn, &project_path).map_err(|e| e.to_string())?;
     }
 
     // Update status
-    update_dev_session(&conn, &project_path, PM_ORCHESTRATOR_PROMPT, 0, "running")
-        .map_err(|e| e.to_string())?;
+    set_dev_session_workflow(&conn, &project_path, &definition, &model)?;
+    update_dev_session(
+        &conn,
+        &project_path,
+        &definition.initial_state,
+        &initial.prompt,
+        0,
+        "running",
+    )
+    .map_err(|e| e.to_string())?;
 
     drop(conn);
 
-    // Start first workflow in background thread to avoid Send trait issues
-    let app_clone = app.clone();
-    let project_clone = project_path.clone();
-    let model_clone = model.clone();
-    std::thread::spawn(move || {
-        tauri::async_runtime::block_on(async move {
-            if let Err(e) = super::claude::execute_claude_code(
-                app_clone,
-                project_clone,
-                PM_ORCHESTRATOR_PROMPT.to_string(),
-                model_clone,
-                None, // execution_mode: default to execute
-            )
-            .await
-            {
-                log::error!("Failed to start dev workflow: {}", e);
-            }
-        });
-    });
+    log::info!(
+        "Dev workflow '{}' starting at state '{}'",
+        definition.name,
+        definition.initial_state
+    );
+    let state_model = initial.model.clone().unwrap_or(model);
+    spawn_workflow_state(
+        app.clone(),
+        project_path,
+        definition.initial_state.clone(),
+        initial,
+        state_model,
+    );
 
     Ok(())
 }
@@ -235,23 +847,112 @@ pub async fn start_dev_workflow(
 pub async fn stop_dev_workflow(app: AppHandle, project_path: String) -> Result<(), String> {
     log::info!("Stopping dev workflow for: {}", project_path);
 
-    let db = app.state::<super::agents::AgentDb>();
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        update_dev_session(&conn, &project_path, "", "", 0, "idle").map_err(|e| e.to_string())?;
+    }
 
-    update_dev_session(&conn, &project_path, "", 0, "idle").map_err(|e| e.to_string())?;
+    cancel_workflow_runs(&app, &project_path).await
+}
 
-    drop(conn);
+/// Pause a running workflow, keeping its position so it can be resumed
+#[tauri::command]
+pub async fn pause_dev_workflow(
+    app: AppHandle,
+    project_path: String,
+) -> Result<DevSession, String> {
+    log::info!("Pausing dev workflow for: {}", project_path);
 
-    // Cancel in background thread to avoid Send trait issues
-    std::thread::spawn(move || {
-        tauri::async_runtime::block_on(async move {
-            if let Err(e) = super::claude::cancel_claude_execution(app, None).await {
-                log::error!("Failed to cancel Claude execution: {}", e);
-            }
-        });
-    });
+    let session = {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let session = get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())?;
+        if session.status != "running" {
+            return Err(format!(
+                "Dev workflow is not running (status: {})",
+                session.status
+            ));
+        }
+        update_dev_session(
+            &conn,
+            &project_path,
+            &session.current_state,
+            &session.last_prompt,
+            session.cycle_count,
+            "paused",
+        )
+        .map_err(|e| e.to_string())?;
+        get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())?
+    };
 
-    Ok(())
+    cancel_workflow_runs(&app, &project_path).await?;
+    Ok(session)
+}
+
+/// Resume a paused workflow by re-running the state it stopped in
+#[tauri::command]
+pub async fn resume_dev_workflow(
+    app: AppHandle,
+    project_path: String,
+    model: Option<String>,
+) -> Result<DevSession, String> {
+    log::info!("Resuming dev workflow for: {}", project_path);
+
+    // A cancelled run still reports back shortly after it dies; don't let it race the resume
+    let claude_state = app.state::<super::claude::ClaudeProcessState>();
+    if claude_state
+        .processes
+        .lock()
+        .await
+        .values()
+        .any(|p| p.project_path == project_path)
+    {
+        return Err("The paused run is still stopping; try again in a moment".to_string());
+    }
+
+    let (session, state) = {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let session = get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())?;
+        if session.status != "paused" {
+            return Err(format!(
+                "Dev workflow is not paused (status: {})",
+                session.status
+            ));
+        }
+
+        let definition = get_dev_session_workflow(&conn, &project_path)?;
+        let state = definition.state(&session.current_state)?.clone();
+        update_dev_session(
+            &conn,
+            &project_path,
+            &session.current_state,
+            &state.prompt,
+            session.cycle_count,
+            "running",
+        )
+        .map_err(|e| e.to_string())?;
+        let session = get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())?;
+        (session, state)
+    };
+
+    let base_model = model.unwrap_or_else(|| session.model.clone());
+    let state_model = state.model.clone().unwrap_or(base_model);
+    log::info!(
+        "Dev workflow resuming at state '{}' (cycle {})",
+        session.current_state,
+        session.cycle_count
+    );
+    spawn_workflow_state(
+        app.clone(),
+        project_path,
+        session.current_state.clone(),
+        state,
+        state_model,
+    );
+
+    Ok(session)
 }
 
 #[tauri::command]
@@ -265,104 +966,223 @@ pub async fn get_dev_workflow_status(
     get_or_create_dev_session(&conn, &project_path).map_err(|e| e.to_string())
 }
 
+/// Get every step the project's workflows have run, oldest first
+#[tauri::command]
+pub async fn get_dev_workflow_steps(
+    app: AppHandle,
+    project_path: String,
+) -> Result<Vec<DevSessionStep>, String> {
+    let db = app.state::<super::agents::AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    list_dev_steps(&conn, &project_path).map_err(|e| e.to_string())
+}
+
+/// Get the workflow a project would run: its own definition file or the built-in one
+#[tauri::command]
+pub async fn get_dev_workflow_definition(
+    project_path: String,
+) -> Result<WorkflowDefinition, String> {
+    WorkflowDefinition::load_for_project(&project_path)
+}
+
 /// Called by claude.rs when a Claude process completes
 /// This is the auto-routing hook
 pub async fn on_claude_complete(
     app: &AppHandle,
     project_path: &str,
     prompt: &str,
-    success: bool,
+    run: &ClaudeRunSummary,
     model: &str,
 ) -> Result<bool, String> {
     log::info!(
         "🔥 on_claude_complete CALLED - prompt: {}, success: {}",
         prompt,
-        success
+        run.success
     );
 
-    // Only handle dev workflow prompts
-    if !is_dev_workflow_prompt(prompt) {
-        log::info!("🔥 Not a dev workflow prompt, skipping auto-route");
-        return Ok(false);
-    }
+    let (session, definition, step_id) = {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+        let session = get_or_create_dev_session(&conn, project_path).map_err(|e| e.to_string())?;
+        let definition = get_dev_session_workflow(&conn, project_path)?;
+        let step_id =
+            find_running_dev_step(&conn, project_path, prompt).map_err(|e| e.to_string())?;
+        (session, definition, step_id)
+    };
+
+    // Only handle the run the workflow itself started; pause and stop record their own steps
+    let step_id = match step_id {
+        Some(step_id) if session.status == "running" && session.last_prompt == prompt => step_id,
+        _ => {
+            log::info!("🔥 Not the running dev workflow state, skipping auto-route");
+            return Ok(false);
+        }
+    };
 
     log::info!(
-        "🔥 Dev workflow: Claude completed for prompt: {}",
-        &prompt[..prompt.len().min(50)]
+        "🔥 Dev workflow '{}': state '{}' completed",
+        definition.name,
+        session.current_state
     );
 
-    // Determine next action in a separate scope to ensure conn is dropped
-    let next_action: Option<String> = {
+    let git_head_after = git_head(project_path).await;
+    let step_status = if run.cancelled {
+        "cancelled"
+    } else if run.budget_exceeded.is_some() {
+        "budget_exceeded"
+    } else if run.success {
+        "completed"
+    } else {
+        "failed"
+    };
+
+    // Record the step first so the workflow's total includes it
+    let over_budget = {
         let db = app.state::<super::agents::AgentDb>();
         let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-        let session = get_or_create_dev_session(&conn, project_path).map_err(|e| e.to_string())?;
+        finish_dev_step(
+            &conn,
+            step_id,
+            step_status,
+            Some(run),
+            git_head_after.as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
 
-        // Check if we're in running state
-        if session.status != "running" {
-            log::debug!("Dev workflow: Not in running state, skipping auto-route");
-            None
-        } else if session.cycle_count >= MAX_DEV_CYCLES {
-            // Check cycle count
-            log::warn!("Dev workflow: Max cycles reached ({})", MAX_DEV_CYCLES);
-            update_dev_session(&conn, project_path, prompt, session.cycle_count, "error")
-                .map_err(|e| e.to_string())?;
-            None
-        } else if !success {
-            // Check if process failed
-            log::warn!("Dev workflow: Process failed, stopping auto-route");
-            update_dev_session(&conn, project_path, prompt, session.cycle_count, "error")
-                .map_err(|e| e.to_string())?;
-            None
-        } else {
-            // Check completion
-            let is_complete = is_dev_complete(project_path);
+        match &definition.budget {
+            Some(budget) => {
+                budget.exceeded_by(&workflow_usage(&conn, project_path).map_err(|e| e.to_string())?)
+            }
+            None => None,
+        }
+    };
+
+    // Completion predicates may run commands, so evaluate them without holding the DB lock
+    let transition = if run.cancelled {
+        None
+    } else if let Some(reason) = &run.budget_exceeded {
+        log::warn!("Dev workflow: Run went over budget ({})", reason);
+        Some(WorkflowTransition::Failed)
+    } else if session.cycle_count >= definition.max_cycles {
+        log::warn!(
+            "Dev workflow: Max cycles reached ({})",
+            definition.max_cycles
+        );
+        Some(WorkflowTransition::Failed)
+    } else {
+        let transition =
+            definition.next_transition(&session.current_state, run.success, project_path)?;
+        match (transition, &over_budget) {
+            // A workflow over its budget may still finish, but doesn't start another run
+            (WorkflowTransition::Next(_), Some(exceeded)) => {
+                log::warn!(
+                    "Dev workflow: Workflow went over budget ({})",
+                    exceeded.reason
+                );
+                budget::report_budget_exceeded(
+                    app,
+                    &BudgetEvent::new(project_path, run.session_id.clone(), None, exceeded),
+                );
+                Some(WorkflowTransition::Failed)
+            }
+            (transition, _) => Some(transition),
+        }
+    };
 
-            // Get next workflow
-            let next_prompt = get_next_workflow(prompt, is_complete);
+    // Determine next action in a separate scope to ensure conn is dropped
+    let next_action: Option<(String, WorkflowState)> = {
+        let db = app.state::<super::agents::AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-            match next_prompt {
-                Some(next) => {
-                    log::info!("🚀 Dev workflow: Auto-routing to {}", next);
+        match transition {
+            // Cancelled from outside the workflow: keep the position so it can be resumed
+            None => {
+                log::info!("⏸️  Dev workflow: Run cancelled, pausing");
+                update_dev_session(
+                    &conn,
+                    project_path,
+                    &session.current_state,
+                    prompt,
+                    session.cycle_count,
+                    "paused",
+                )
+                .map_err(|e| e.to_string())?;
+                None
+            }
+            Some(WorkflowTransition::Next(next_id)) => {
+                let next = definition.state(&next_id)?.clone();
+                log::info!(
+                    "🚀 Dev workflow: Auto-routing to {} ({})",
+                    next_id,
+                    next.prompt
+                );
 
-                    // Update session
-                    let new_cycle = session.cycle_count + 1;
-                    update_dev_session(&conn, project_path, next, new_cycle, "running")
-                        .map_err(|e| e.to_string())?;
+                // Update session
+                let new_cycle = session.cycle_count + 1;
+                update_dev_session(
+                    &conn,
+                    project_path,
+                    &next_id,
+                    &next.prompt,
+                    new_cycle,
+                    "running",
+                )
+                .map_err(|e| e.to_string())?;
 
-                    log::info!("🚀 Updated session - cycle: {}, next: {}", new_cycle, next);
-                    Some(next.to_string())
-                }
-                None => {
-                    log::info!("✅ Dev workflow: All complete!");
-                    update_dev_session(
-                        &conn,
-                        project_path,
-                        prompt,
-                        session.cycle_count,
-                        "completed",
-                    )
-                    .map_err(|e| e.to_string())?;
-                    None
-                }
+                log::info!(
+                    "🚀 Updated session - cycle: {}, next: {}",
+                    new_cycle,
+                    next_id
+                );
+                Some((next_id, next))
+            }
+            Some(WorkflowTransition::Completed) => {
+                log::info!("✅ Dev workflow: All complete!");
+                update_dev_session(
+                    &conn,
+                    project_path,
+                    &session.current_state,
+                    prompt,
+                    session.cycle_count,
+                    "completed",
+                )
+                .map_err(|e| e.to_string())?;
+                None
+            }
+            Some(WorkflowTransition::Failed) => {
+                log::warn!(
+                    "Dev workflow: Stopping auto-route after state '{}'",
+                    session.current_state
+                );
+                update_dev_session(
+                    &conn,
+                    project_path,
+                    &session.current_state,
+                    prompt,
+                    session.cycle_count,
+                    "error",
+                )
+                .map_err(|e| e.to_string())?;
+                None
             }
         }
     }; // conn is dropped here
 
     // Execute next workflow if needed (outside of conn scope)
-    if let Some(next) = next_action {
-        log::info!("🎯 Executing next workflow: {}", next);
-        match super::claude::execute_claude_code(
-            app.clone(),
-            project_path.to_string(),
-            next.clone(),
-            model.to_string(),
-            None, // execution_mode: default to execute
-        )
-        .await
-        {
+    if let Some((next_id, next)) = next_action {
+        let base_model = if session.model.is_empty() {
+            model.to_string()
+        } else {
+            session.model.clone()
+        };
+        let state_model = next.model.clone().unwrap_or(base_model);
+        log::info!("🎯 Executing next workflow: {}", next.prompt);
+        match run_workflow_state(app, project_path, &next_id, &next, &state_model).await {
             Ok(_) => {
-                log::info!("✅ Successfully started next workflow: {}", next);
+                log::info!("✅ Successfully started next workflow: {}", next.prompt);
                 Ok(true)
             }
             Err(e) => {
@@ -375,3 +1195,194 @@ pub async fn on_claude_complete(
         Ok(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_workflow_cycles_until_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().to_str().unwrap();
+        let workflow = WorkflowDefinition::builtin();
+        workflow.validate().unwrap();
+
+        assert_eq!(
+            workflow
+                .next_transition("pm-orchestrator", true, project)
+                .unwrap(),
+            WorkflowTransition::Next("pm-executor".to_string())
+        );
+        assert_eq!(
+            workflow
+                .next_transition("pm-reviewer", true, project)
+                .unwrap(),
+            WorkflowTransition::Next("pm-executor".to_string())
+        );
+        assert_eq!(
+            workflow
+                .next_transition("pm-executor", false, project)
+                .unwrap(),
+            WorkflowTransition::Failed
+        );
+
+        std::fs::create_dir_all(dir.path().join("anyon-docs")).unwrap();
+        std::fs::write(
+            dir.path().join(EXECUTION_PROGRESS_FILE),
+            "## All Epics Completed",
+        )
+        .unwrap();
+        assert_eq!(
+            workflow
+                .next_transition("pm-reviewer", true, project)
+                .unwrap(),
+            WorkflowTransition::Completed
+        );
+    }
+
+    #[test]
+    fn test_step_history_records_run_outcome() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_dev_workflow_db(&conn).unwrap();
+        let session = get_or_create_dev_session(&conn, "/tmp/project").unwrap();
+
+        let step_id = begin_dev_step(
+            &conn,
+            &session,
+            0,
+            "pm-executor",
+            PM_EXECUTOR_PROMPT,
+            "sonnet",
+            Some("abc123"),
+        )
+        .unwrap();
+        assert_eq!(
+            find_running_dev_step(&conn, "/tmp/project", PM_EXECUTOR_PROMPT).unwrap(),
+            Some(step_id)
+        );
+
+        let run = ClaudeRunSummary {
+            success: true,
+            cancelled: false,
+            session_id: Some("session-1".to_string()),
+            exit_code: Some(0),
+            cost_usd: Some(0.42),
+            total_tokens: Some(1200),
+            budget_exceeded: None,
+        };
+        finish_dev_step(&conn, step_id, "completed", Some(&run), Some("def456")).unwrap();
+
+        assert_eq!(
+            find_running_dev_step(&conn, "/tmp/project", PM_EXECUTOR_PROMPT).unwrap(),
+            None
+        );
+        let steps = list_dev_steps(&conn, "/tmp/project").unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].status, "completed");
+        assert_eq!(steps[0].claude_session_id.as_deref(), Some("session-1"));
+        assert_eq!(steps[0].total_tokens, Some(1200));
+        assert_eq!(steps[0].git_head_before.as_deref(), Some("abc123"));
+        assert_eq!(steps[0].git_head_after.as_deref(), Some("def456"));
+        assert!(steps[0].duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_budget_counts_the_current_workflow() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_dev_workflow_db(&conn).unwrap();
+        let session = get_or_create_dev_session(&conn, "/tmp/project").unwrap();
+        let run_step = |cost_usd: f64, total_tokens: u64| {
+            let step_id =
+                begin_dev_step(&conn, &session, 0, "pm-executor", "/x", "sonnet", None).unwrap();
+            let run = ClaudeRunSummary {
+                success: true,
+                cost_usd: Some(cost_usd),
+                total_tokens: Some(total_tokens),
+                ..Default::default()
+            };
+            finish_dev_step(&conn, step_id, "completed", Some(&run), None).unwrap();
+            step_id
+        };
+
+        // A step of an earlier workflow
+        let earlier = run_step(5.0, 50_000);
+        conn.execute(
+            "UPDATE dev_session_steps SET started_at = '2000-01-01 00:00:00.000' WHERE id = ?1",
+            params![earlier],
+        )
+        .unwrap();
+
+        let mut definition = WorkflowDefinition::builtin();
+        definition.budget = Some(RunBudget {
+            max_cost_usd: Some(1.0),
+            max_tokens: Some(10_000),
+            ..Default::default()
+        });
+        set_dev_session_workflow(&conn, "/tmp/project", &definition, "sonnet").unwrap();
+        let budget = definition.budget.as_ref().unwrap();
+
+        run_step(0.25, 3_000);
+        run_step(0.5, 3_000);
+        let used = workflow_usage(&conn, "/tmp/project").unwrap();
+        assert_eq!(used.cost_usd, 0.75);
+        assert_eq!(used.total_tokens, 6_000);
+        assert!(budget.exceeded_by(&used).is_none());
+        let remaining = budget.remaining(&used);
+        assert_eq!(remaining.max_cost_usd, Some(0.25));
+        assert_eq!(remaining.max_tokens, Some(4_000));
+
+        // A running step isn't counted until it finishes
+        begin_dev_step(&conn, &session, 1, "pm-reviewer", "/y", "sonnet", None).unwrap();
+        assert_eq!(
+            workflow_usage(&conn, "/tmp/project").unwrap().cost_usd,
+            0.75
+        );
+
+        run_step(0.5, 1_000);
+        let exceeded = budget
+            .exceeded_by(&workflow_usage(&conn, "/tmp/project").unwrap())
+            .unwrap();
+        assert_eq!(exceeded.limit, "cost");
+    }
+
+    #[test]
+    fn test_parse_yaml_workflow() {
+        let workflow = WorkflowDefinition::parse(
+            r#"
+name: tdd
+initial_state: test
+states:
+  test:
+    prompt: /write-tests
+    model: opus
+    on_success: implement
+  implement:
+    prompt: /implement
+    complete_when:
+      - type: command
+        run: "exit 0"
+    on_failure: test
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(workflow.max_cycles, MAX_DEV_CYCLES);
+        assert_eq!(workflow.states["test"].model.as_deref(), Some("opus"));
+        assert_eq!(
+            workflow.states["implement"].complete_when,
+            vec![CompletionPredicate::Command {
+                run: "exit 0".to_string(),
+                exit_code: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unknown_transition_target_is_rejected() {
+        let err = WorkflowDefinition::parse(
+            r#"{"name": "x", "initial_state": "a", "states": {"a": {"prompt": "/a", "on_success": "b"}}}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown state 'b'"));
+    }
+}
@@ -0,0 +1,449 @@
+//! Element source resolution
+//! Maps an element selected in the preview back to the source lines that likely render it
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Source files searched when the page carries no usable metadata
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "html", "htm", "jsx", "tsx", "js", "ts", "mjs", "vue", "svelte", "astro", "mdx", "jinja", "j2",
+    "hbs", "ejs",
+];
+
+/// Directories never searched: dependencies, build output and caches
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    ".git",
+    "dist",
+    "build",
+    "out",
+    ".next",
+    ".nuxt",
+    ".output",
+    ".svelte-kit",
+    ".astro",
+    ".turbo",
+    ".vercel",
+    ".cache",
+    "coverage",
+    "target",
+    "vendor",
+    "__pycache__",
+    ".venv",
+    "venv",
+];
+
+const MAX_SEARCHED_FILES: usize = 5000;
+const MAX_SEARCHED_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Lowest score a text/class match needs to be reported
+const MIN_SEARCH_SCORE: f32 = 0.3;
+
+/// Where the preview's dev-mode metadata says an element comes from
+#[derive(Debug, Clone, Deserialize)]
+pub struct ElementSourceHint {
+    pub file: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub origin: Option<String>, // "react", "vue", "svelte", "data-attribute"
+}
+
+/// What the preview reported about a selected element
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ElementSourceQuery {
+    pub tag: String,
+    pub element_id: Option<String>, // The DOM id, if any
+    pub classes: Vec<String>,
+    pub text: Option<String>,
+    pub source: Option<ElementSourceHint>,
+    pub components: Vec<String>, // Innermost first
+}
+
+/// A place in the project that may render the element
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceCandidate {
+    pub file: String, // Relative to the project
+    pub line: u32,
+    pub column: Option<u32>,
+    pub location: String, // "file:line", ready to attach to a prompt
+    pub score: f32,       // 0 to 1; metadata beats definitions beats text search
+    pub reason: String,   // Hint origin, "component" or "search"
+}
+
+impl SourceCandidate {
+    fn new(file: String, line: u32, column: Option<u32>, score: f32, reason: &str) -> Self {
+        Self {
+            location: format!("{}:{}", file, line),
+            file,
+            line,
+            column,
+            score,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Turn a file name from page metadata into a path relative to the project
+///
+/// Bundlers report absolute paths, dev server URLs (`/src/App.tsx?t=1`, `/@fs/...`) or
+/// `webpack://` URLs; the project may also have been built somewhere else, so trailing
+/// components are tried until one exists. The metadata comes from the page, so names with
+/// `..` are refused and whatever is found must resolve to a file inside the project.
+fn resolve_hint_path(project: &Path, file: &str) -> Option<String> {
+    let file = file
+        .trim_start_matches("webpack-internal:///")
+        .trim_start_matches("webpack://")
+        .trim_start_matches("file://")
+        .trim_start_matches("/@fs");
+    let file = file.split(['?', '#']).next().unwrap_or(file);
+
+    let parts: Vec<&str> = file
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    if parts.contains(&"..") {
+        return None;
+    }
+
+    let project = project.canonicalize().ok()?;
+    let inside_project = |path: &Path| {
+        let resolved = path.canonicalize().ok().filter(|p| p.is_file())?;
+        let relative = resolved.strip_prefix(&project).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    };
+
+    let path = Path::new(file);
+    if path.is_absolute() {
+        if let Some(relative) = inside_project(path) {
+            return Some(relative);
+        }
+    }
+    (0..parts.len())
+        // A drive prefix such as `C:` would replace the project when joined
+        .filter(|&start| !parts[start].contains(':'))
+        .find_map(|start| inside_project(&project.join(parts[start..].join("/"))))
+}
+
+/// Source files in the project, skipping dependencies and build output
+fn source_files(project: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(project)
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !e.file_type().is_dir()
+                || !e
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+        })
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+        })
+        .filter(|e| {
+            e.metadata()
+                .is_ok_and(|m| m.len() <= MAX_SEARCHED_FILE_SIZE)
+        })
+        .take(MAX_SEARCHED_FILES)
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// The element's visible text, collapsed to single spaces and cut to a searchable length
+fn search_text(text: Option<&str>) -> Option<String> {
+    let text = text?.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text: String = text.chars().take(40).collect();
+    (text.chars().count() >= 3).then_some(text)
+}
+
+/// How well one line of source matches the element, from 0 to 0.9
+fn score_line(line: &str, query: &ElementSourceQuery, text: Option<&str>) -> f32 {
+    let mut score = 0.0;
+
+    if let Some(id) = query.element_id.as_deref().filter(|id| !id.is_empty()) {
+        if line.contains(&format!("id=\"{}\"", id))
+            || line.contains(&format!("id='{}'", id))
+            || line.contains(&format!("id={{\"{}\"}}", id))
+        {
+            score += 0.5;
+        }
+    }
+
+    if !query.classes.is_empty() && (line.contains("class") || line.contains("className")) {
+        let tokens: HashSet<&str> = line
+            .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '{' | '}'))
+            .collect();
+        let matched = query
+            .classes
+            .iter()
+            .filter(|c| tokens.contains(c.as_str()))
+            .count();
+        // A lone common class like "flex" matches half the project
+        if matched >= query.classes.len().min(2) {
+            score += 0.35 * matched as f32 / query.classes.len() as f32;
+        }
+    }
+
+    if let Some(text) = text {
+        if line.contains(text) {
+            score += 0.35;
+        }
+    }
+
+    if score > 0.0 && !query.tag.is_empty() && line.contains(&format!("<{}", query.tag)) {
+        score += 0.1;
+    }
+    score.min(0.9)
+}
+
+/// Best matching line in one file, if any line matches at all
+fn best_line(content: &str, query: &ElementSourceQuery, text: Option<&str>) -> Option<(u32, f32)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i as u32 + 1, score_line(line, query, text)))
+        .filter(|(_, score)| *score > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Line that defines a component, for frameworks where components are functions or classes
+fn component_definition_line(content: &str, name: &str) -> Option<u32> {
+    let re = regex::Regex::new(&format!(
+        r"\b(function|class|const|let|var)\s+{}\b",
+        regex::escape(name)
+    ))
+    .ok()?;
+    content
+        .lines()
+        .position(|line| re.is_match(line))
+        .map(|i| i as u32 + 1)
+}
+
+/// Work out where an element comes from
+///
+/// Dev-mode metadata wins when it points at a real file; otherwise the components the page
+/// reported are looked up by definition, and finally the source is searched for the
+/// element's id, classes and text.
+pub fn resolve_element_source_in(
+    project: &Path,
+    query: &ElementSourceQuery,
+    limit: usize,
+) -> Vec<SourceCandidate> {
+    let text = search_text(query.text.as_deref());
+    let mut candidates = Vec::new();
+
+    if let Some(hint) = &query.source {
+        if let Some(file) = resolve_hint_path(project, &hint.file) {
+            let reason = hint.origin.as_deref().unwrap_or("metadata");
+            match hint.line {
+                Some(line) => {
+                    candidates.push(SourceCandidate::new(file, line, hint.column, 1.0, reason));
+                }
+                // Vue only knows the component's file; find the element inside it
+                None => {
+                    let content = std::fs::read_to_string(project.join(&file)).unwrap_or_default();
+                    let line = best_line(&content, query, text.as_deref())
+                        .map(|(line, _)| line)
+                        .unwrap_or(1);
+                    candidates.push(SourceCandidate::new(file, line, None, 0.8, reason));
+                }
+            }
+        }
+    }
+
+    let files = source_files(project);
+    let mut contents = Vec::with_capacity(files.len());
+    for path in &files {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let relative = path
+                .strip_prefix(project)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            contents.push((relative, content));
+        }
+    }
+
+    for (depth, name) in query.components.iter().take(3).enumerate() {
+        let score = 0.7 - 0.1 * depth as f32;
+        for (file, content) in &contents {
+            let stem = Path::new(file).file_stem().and_then(|s| s.to_str());
+            let line = if stem == Some(name.as_str())
+                && (file.ends_with(".vue") || file.ends_with(".svelte"))
+            {
+                // Single-file components are named after their file
+                best_line(content, query, text.as_deref())
+                    .map(|(line, _)| line)
+                    .or(Some(1))
+            } else {
+                component_definition_line(content, name)
+            };
+            if let Some(line) = line {
+                candidates.push(SourceCandidate::new(
+                    file.clone(),
+                    line,
+                    None,
+                    score,
+                    "component",
+                ));
+            }
+        }
+    }
+
+    for (file, content) in &contents {
+        if let Some((line, score)) = best_line(content, query, text.as_deref()) {
+            if score >= MIN_SEARCH_SCORE {
+                candidates.push(SourceCandidate::new(
+                    file.clone(),
+                    line,
+                    None,
+                    score,
+                    "search",
+                ));
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut seen = HashSet::new();
+    candidates.retain(|c| seen.insert(c.location.clone()));
+    candidates.truncate(limit);
+    candidates
+}
+
+/// Find the source locations that likely render an element selected in the preview
+///
+/// Returns candidates best first; `limit` defaults to 5.
+#[tauri::command]
+pub async fn resolve_element_source(
+    project_path: String,
+    element: ElementSourceQuery,
+    limit: Option<usize>,
+) -> Result<Vec<SourceCandidate>, String> {
+    let project = PathBuf::from(&project_path);
+    if !project.is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+    let limit = limit.unwrap_or(5);
+    tokio::task::spawn_blocking(move || resolve_element_source_in(&project, &element, limit))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn deserializes_the_selector_script_payload() {
+        let payload = r#"{
+            "id": "main > h1.hero-title",
+            "name": "h1.hero-title",
+            "tag": "h1",
+            "selector": "main > h1.hero-title",
+            "text": "Ship faster",
+            "html": "<h1 id=\"hero\" class=\"hero-title\">Ship faster</h1>",
+            "rect": { "x": 0, "y": 0, "width": 100, "height": 40 },
+            "elementId": "hero",
+            "classes": ["hero-title"],
+            "source": { "file": "/src/Hero.tsx?t=1", "line": 3, "column": 5, "origin": "react" },
+            "components": ["Hero", "App"],
+            "lineNumber": 3,
+            "columnNumber": 5
+        }"#;
+        let query: ElementSourceQuery = serde_json::from_str(payload).unwrap();
+        assert_eq!(query.element_id.as_deref(), Some("hero"));
+        assert_eq!(query.classes, ["hero-title"]);
+        assert_eq!(query.components, ["Hero", "App"]);
+        let source = query.source.unwrap();
+        assert_eq!(source.file, "/src/Hero.tsx?t=1");
+        assert_eq!(source.line, Some(3));
+    }
+
+    #[test]
+    fn hint_paths_stay_inside_the_project() {
+        let root = tempfile::tempdir().unwrap();
+        let project = root.path().join("app");
+        write(
+            &project,
+            "src/App.tsx",
+            "export default function App() {}\n",
+        );
+        write(root.path(), "secret.tsx", "secret\n");
+
+        assert_eq!(
+            resolve_hint_path(&project, &format!("{}/src/App.tsx", project.display())).as_deref(),
+            Some("src/App.tsx")
+        );
+        assert_eq!(
+            resolve_hint_path(&project, "webpack:///./src/App.tsx?abc").as_deref(),
+            Some("src/App.tsx")
+        );
+        assert_eq!(resolve_hint_path(&project, "../secret.tsx"), None);
+        assert_eq!(resolve_hint_path(&project, "src/../../secret.tsx"), None);
+        assert_eq!(
+            resolve_hint_path(&project, &root.path().join("secret.tsx").to_string_lossy()),
+            None
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.path().join("secret.tsx"), project.join("link.tsx"))
+                .unwrap();
+            assert_eq!(resolve_hint_path(&project, "/link.tsx"), None);
+        }
+    }
+
+    #[test]
+    fn prefers_metadata_then_falls_back_to_search() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "src/Hero.tsx",
+            "export function Hero() {\n  return (\n    <h1 className=\"hero-title text-xl\">Ship faster</h1>\n  );\n}\n",
+        );
+        write(
+            dir.path(),
+            "node_modules/lib/index.js",
+            "<h1 className=\"hero-title text-xl\">",
+        );
+
+        let mut query = ElementSourceQuery {
+            tag: "h1".to_string(),
+            classes: vec!["hero-title".to_string(), "text-xl".to_string()],
+            text: Some("Ship  faster".to_string()),
+            source: Some(ElementSourceHint {
+                file: "/home/ci/app/src/Hero.tsx?t=123".to_string(),
+                line: Some(3),
+                column: Some(5),
+                origin: Some("react".to_string()),
+            }),
+            components: vec!["Hero".to_string()],
+            ..Default::default()
+        };
+        let candidates = resolve_element_source_in(dir.path(), &query, 5);
+        assert_eq!(candidates[0].location, "src/Hero.tsx:3");
+        assert_eq!(candidates[0].reason, "react");
+        assert!(candidates.iter().any(|c| c.location == "src/Hero.tsx:1"));
+
+        query.source = None;
+        query.components.clear();
+        let candidates = resolve_element_source_in(dir.path(), &query, 5);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].location, "src/Hero.tsx:3");
+        assert_eq!(candidates[0].reason, "search");
+    }
+}
@@ -1,11 +1,12 @@
-use std::process::Command;
+use std::path::Path;
+use std::process::{Command, Output};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
 /// Creates a Command that runs hidden on Windows (no terminal window popup)
 #[cfg(target_os = "windows")]
-fn create_hidden_command(program: &str) -> Command {
+pub(crate) fn create_hidden_command(program: &str) -> Command {
     const CREATE_NO_WINDOW: u32 = 0x08000000;
     let mut cmd = Command::new(program);
     cmd.creation_flags(CREATE_NO_WINDOW);
@@ -14,10 +15,39 @@ fn create_hidden_command(program: &str) -> Command {
 
 /// Creates a Command (non-Windows - no special flags needed)
 #[cfg(not(target_os = "windows"))]
-fn create_hidden_command(program: &str) -> Command {
+pub(crate) fn create_hidden_command(program: &str) -> Command {
     Command::new(program)
 }
 
+/// Run git in the project, optionally against another index file
+pub(crate) fn run_git(
+    project_path: &str,
+    args: &[&str],
+    index_file: Option<&Path>,
+) -> Result<Output, String> {
+    let mut cmd = create_hidden_command("git");
+    cmd.args(args).current_dir(project_path);
+    if let Some(index_file) = index_file {
+        cmd.env("GIT_INDEX_FILE", index_file);
+    }
+    cmd.output()
+        .map_err(|e| format!("Failed to execute git {}: {}", args.join(" "), e))
+}
+
+/// Run git and return its trimmed stdout, failing on a non-zero exit
+pub(crate) fn git_stdout(
+    project_path: &str,
+    args: &[&str],
+    index_file: Option<&Path>,
+) -> Result<String, String> {
+    let output = run_git(project_path, args, index_file)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed: {}", args.join(" "), stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Get the current HEAD commit SHA of a git repository
 #[tauri::command]
 pub async fn get_git_head_sha(project_path: String) -> Result<String, String> {
@@ -1,12 +1,20 @@
 pub mod agents;
+pub mod budget;
+pub mod checkpoint;
 pub mod claude;
 pub mod claude_auth;
+pub mod dev_launcher;
 pub mod dev_server;
+pub mod dev_server_logs;
 pub mod dev_workflow;
+pub mod element_source;
 pub mod environment;
 pub mod git;
 pub mod mcp;
+pub mod permission_prompt;
+pub mod port_registry;
 pub mod preview;
+pub mod preview_network;
 pub mod slash_commands;
 pub mod storage;
 pub mod usage;
@@ -0,0 +1,724 @@
+//! Interactive tool permissions
+//! Runs a local MCP server whose single tool the CLI calls (`--permission-prompt-tool`)
+//! before using any tool it isn't already allowed to. Each call becomes a
+//! `permission-request` event and waits until the user answers it or it times out.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::{Path as AxumPath, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+
+use super::agents::AgentDb;
+
+/// `app_settings` key that turns interactive permissions on
+const INTERACTIVE_PERMISSIONS_KEY: &str = "interactive_permissions";
+
+const MCP_SERVER_NAME: &str = "anyon_permissions";
+const MCP_TOOL_NAME: &str = "approve";
+/// What the CLI is told to call, `mcp__<server>__<tool>`
+const PERMISSION_PROMPT_TOOL: &str = "mcp__anyon_permissions__approve";
+/// Used when the client doesn't say which protocol version it speaks
+const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+/// How long a request waits for an answer before it's denied
+const PERMISSION_REQUEST_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A tool call waiting for the user's decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionRequest {
+    pub request_id: String,
+    pub project_path: String,
+    pub run_id: Option<i64>, // The Claude run asking, known as soon as it starts
+    pub session_id: Option<String>, // Unknown until the run reports it
+    pub tool_name: String,
+    pub input: JsonValue,
+    pub tool_use_id: Option<String>,
+    pub requested_at: String, // RFC3339
+}
+
+/// A tool a project always allows without asking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionRule {
+    pub id: i64,
+    pub project_path: String,
+    pub tool_name: String,
+    pub created_at: String,
+}
+
+struct PermissionScope {
+    project_path: String,
+    run_id: Option<i64>,
+    session_id: Option<String>,
+}
+
+struct PendingRequest {
+    request: PermissionRequest,
+    reply: oneshot::Sender<PermissionDecision>,
+}
+
+enum PermissionDecision {
+    Allow,
+    Deny(String),
+}
+
+lazy_static::lazy_static! {
+    /// Port of the MCP server, once started
+    static ref PERMISSION_SERVER_PORT: Mutex<Option<u16>> = Mutex::new(None);
+    /// Runs that may ask for permission, by the token in their server URL
+    static ref PERMISSION_SCOPES: Mutex<HashMap<String, PermissionScope>> =
+        Mutex::new(HashMap::new());
+    static ref PENDING_REQUESTS: Mutex<HashMap<String, PendingRequest>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Initialize permission_rules table
+pub fn init_permission_rules_db(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS permission_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_path TEXT NOT NULL,
+            tool_name TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(project_path, tool_name)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn is_always_allowed(conn: &Connection, project_path: &str, tool_name: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM permission_rules WHERE project_path = ?1 AND tool_name = ?2",
+        params![project_path, tool_name],
+        |_| Ok(()),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .is_some()
+}
+
+fn interactive_permissions_enabled(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        params![INTERACTIVE_PERMISSIONS_KEY],
+        |row| row.get::<_, String>(0),
+    )
+    .is_ok_and(|value| value == "true")
+}
+
+/// A run's registration with the permission server
+///
+/// Dropping it unregisters the run and deletes its MCP config; any request still waiting
+/// is denied by the CLI going away.
+pub(crate) struct PermissionPrompt {
+    token: String,
+    config_path: PathBuf,
+}
+
+impl PermissionPrompt {
+    /// The MCP config and prompt tool that route the run's permission checks to the app
+    pub fn mcp_tool(&self) -> (String, String) {
+        (
+            self.config_path.to_string_lossy().to_string(),
+            PERMISSION_PROMPT_TOOL.to_string(),
+        )
+    }
+
+    /// Attach the run's ID so its requests can be routed before its session is known
+    pub fn set_run_id(&self, run_id: i64) {
+        if let Some(scope) = PERMISSION_SCOPES.lock().unwrap().get_mut(&self.token) {
+            scope.run_id = Some(run_id);
+        }
+    }
+
+    /// Attach the run's session ID so its requests can be routed to the right tab
+    pub fn set_session_id(&self, session_id: &str) {
+        if let Some(scope) = PERMISSION_SCOPES.lock().unwrap().get_mut(&self.token) {
+            scope.session_id = Some(session_id.to_string());
+        }
+    }
+}
+
+impl Drop for PermissionPrompt {
+    fn drop(&mut self) {
+        PERMISSION_SCOPES.lock().unwrap().remove(&self.token);
+        let _ = std::fs::remove_file(&self.config_path);
+    }
+}
+
+/// Register a run with the permission server if interactive permissions are turned on
+///
+/// Returns `None` when they're off, in which case the run keeps its usual permission mode.
+/// Only runs someone is watching should ask; unattended ones would sit out the timeout on
+/// every check. The MCP config goes in a file since JSON on the command line doesn't
+/// survive Windows batch wrappers.
+pub(crate) fn permission_prompt_for(
+    app: &AppHandle,
+    project_path: &str,
+) -> Result<Option<PermissionPrompt>, String> {
+    {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        if !interactive_permissions_enabled(&conn) {
+            return Ok(None);
+        }
+    }
+
+    let port = ensure_permission_server(app)?;
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let config = json!({
+        "mcpServers": {
+            MCP_SERVER_NAME: {
+                "type": "http",
+                "url": format!("http://127.0.0.1:{}/mcp/{}", port, token),
+            }
+        }
+    });
+    let config_path = std::env::temp_dir().join(format!("anyon-permissions-{}.json", token));
+    std::fs::write(&config_path, config.to_string())
+        .map_err(|e| format!("Failed to write permission MCP config: {}", e))?;
+
+    PERMISSION_SCOPES.lock().unwrap().insert(
+        token.clone(),
+        PermissionScope {
+            project_path: project_path.to_string(),
+            run_id: None,
+            session_id: None,
+        },
+    );
+    Ok(Some(PermissionPrompt { token, config_path }))
+}
+
+/// Start the MCP server on first use and return its port
+fn ensure_permission_server(app: &AppHandle) -> Result<u16, String> {
+    let mut port = PERMISSION_SERVER_PORT.lock().unwrap();
+    if let Some(port) = *port {
+        return Ok(port);
+    }
+
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|l| l.set_nonblocking(true).map(|_| l))
+        .map_err(|e| format!("Failed to start permission server: {}", e))?;
+    let bound = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let router = axum::Router::new()
+        .route(
+            "/mcp/{token}",
+            axum::routing::post(handle_mcp_message)
+                .get(|| async { StatusCode::METHOD_NOT_ALLOWED }),
+        )
+        .with_state(app.clone());
+    tauri::async_runtime::spawn(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("Failed to start permission server: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = axum::serve(listener, router).await {
+            log::error!("Permission server error: {}", e);
+        }
+    });
+
+    log::info!("Permission server listening on port {}", bound);
+    *port = Some(bound);
+    Ok(bound)
+}
+
+fn tool_definition() -> JsonValue {
+    json!({
+        "name": MCP_TOOL_NAME,
+        "description": "Ask the user whether a tool call may run",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "tool_name": { "type": "string" },
+                "input": { "type": "object" },
+                "tool_use_id": { "type": "string" },
+            },
+            "required": ["tool_name", "input"],
+        },
+    })
+}
+
+/// Handle one JSON-RPC message from the CLI's MCP client
+async fn handle_mcp_message(
+    AxumState(app): AxumState<AppHandle>,
+    AxumPath(token): AxumPath<String>,
+    Json(message): Json<JsonValue>,
+) -> Response {
+    if !PERMISSION_SCOPES.lock().unwrap().contains_key(&token) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let reply = mcp_reply(&message, |arguments| async move {
+        decide(&app, &token, &arguments).await
+    })
+    .await;
+    match reply {
+        Some(response) => Json(response).into_response(),
+        // Notifications and responses need no reply
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+/// The JSON-RPC reply to a message, with `approve` answering calls to the prompt tool
+///
+/// Returns `None` for notifications and responses.
+async fn mcp_reply<F, Fut>(message: &JsonValue, approve: F) -> Option<JsonValue>
+where
+    F: FnOnce(JsonValue) -> Fut,
+    Fut: Future<Output = JsonValue>,
+{
+    let id = message.get("id").cloned()?;
+    let params = &message["params"];
+
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(MCP_PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": MCP_SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": [tool_definition()] })),
+        "tools/call" if params["name"] == MCP_TOOL_NAME => {
+            let decision = approve(params["arguments"].clone()).await;
+            Ok(json!({ "content": [{ "type": "text", "text": decision.to_string() }] }))
+        }
+        "tools/call" => Err((-32602, format!("Unknown tool: {}", params["name"]))),
+        method => Err((-32601, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    })
+}
+
+/// Removes a request from the pending list when its call finishes or the CLI hangs up
+struct PendingGuard(String);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        PENDING_REQUESTS.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Answer a permission check, asking the user unless the project always allows the tool
+///
+/// Returns the JSON the CLI expects back from a permission prompt tool.
+async fn decide(app: &AppHandle, token: &str, arguments: &JsonValue) -> JsonValue {
+    decide_with(
+        token,
+        arguments,
+        PERMISSION_REQUEST_TIMEOUT,
+        |project_path, tool_name| {
+            app.try_state::<AgentDb>().is_some_and(|db| {
+                db.0.lock()
+                    .map(|conn| is_always_allowed(&conn, project_path, tool_name))
+                    .unwrap_or(false)
+            })
+        },
+        |request| {
+            if let Some(session_id) = &request.session_id {
+                let _ = app.emit(&format!("permission-request:{}", session_id), request);
+            }
+            let _ = app.emit("permission-request", request);
+        },
+    )
+    .await
+}
+
+/// `decide` with the rule lookup and the announcement passed in
+///
+/// A request nobody answers within `timeout` is denied.
+async fn decide_with(
+    token: &str,
+    arguments: &JsonValue,
+    timeout: Duration,
+    always_allowed: impl FnOnce(&str, &str) -> bool,
+    announce: impl FnOnce(&PermissionRequest),
+) -> JsonValue {
+    let tool_name = arguments["tool_name"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let input = arguments["input"].clone();
+    let Some((project_path, run_id, session_id)) =
+        PERMISSION_SCOPES.lock().unwrap().get(token).map(|scope| {
+            (
+                scope.project_path.clone(),
+                scope.run_id,
+                scope.session_id.clone(),
+            )
+        })
+    else {
+        return json!({ "behavior": "deny", "message": "The run is no longer active" });
+    };
+
+    let decision = if always_allowed(&project_path, &tool_name) {
+        log::info!("{} is always allowed in {}", tool_name, project_path);
+        PermissionDecision::Allow
+    } else {
+        let request = PermissionRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            project_path,
+            run_id,
+            session_id,
+            tool_name,
+            input: input.clone(),
+            tool_use_id: arguments["tool_use_id"].as_str().map(String::from),
+            requested_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let (reply, decision) = oneshot::channel();
+        let _guard = PendingGuard(request.request_id.clone());
+        PENDING_REQUESTS.lock().unwrap().insert(
+            request.request_id.clone(),
+            PendingRequest {
+                request: request.clone(),
+                reply,
+            },
+        );
+
+        log::info!(
+            "Asking for permission to use {} in {}",
+            request.tool_name,
+            request.project_path
+        );
+        announce(&request);
+
+        match tokio::time::timeout(timeout, decision).await {
+            Ok(Ok(decision)) => decision,
+            Ok(Err(_)) => PermissionDecision::Deny("The request was dismissed".to_string()),
+            Err(_) => {
+                log::warn!(
+                    "Permission request for {} got no answer within {}s, denying it",
+                    request.tool_name,
+                    timeout.as_secs()
+                );
+                PermissionDecision::Deny(
+                    "Nobody answered the permission request in time".to_string(),
+                )
+            }
+        }
+    };
+
+    match decision {
+        PermissionDecision::Allow => json!({ "behavior": "allow", "updatedInput": input }),
+        PermissionDecision::Deny(message) => json!({ "behavior": "deny", "message": message }),
+    }
+}
+
+/// Answer a pending permission request
+///
+/// With `always_allow`, the tool is allowed in the request's project from now on.
+/// `message` tells Claude why a request was denied.
+#[tauri::command]
+pub async fn respond_permission_request(
+    db: State<'_, AgentDb>,
+    request_id: String,
+    allow: bool,
+    always_allow: Option<bool>,
+    message: Option<String>,
+) -> Result<(), String> {
+    let pending = PENDING_REQUESTS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&request_id)
+        .ok_or_else(|| format!("Permission request {} is no longer pending", request_id))?;
+
+    if allow && always_allow.unwrap_or(false) {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO permission_rules (project_path, tool_name) VALUES (?1, ?2)",
+            params![pending.request.project_path, pending.request.tool_name],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let decision = if allow {
+        PermissionDecision::Allow
+    } else {
+        PermissionDecision::Deny(message.unwrap_or_else(|| "The user denied this".to_string()))
+    };
+    // The run may have ended while the user was deciding
+    let _ = pending.reply.send(decision);
+    Ok(())
+}
+
+/// List permission requests still waiting for an answer, optionally for one session or run
+#[tauri::command]
+pub async fn list_permission_requests(
+    session_id: Option<String>,
+    run_id: Option<i64>,
+) -> Result<Vec<PermissionRequest>, String> {
+    let pending = PENDING_REQUESTS.lock().map_err(|e| e.to_string())?;
+    let mut requests: Vec<PermissionRequest> = pending
+        .values()
+        .map(|p| p.request.clone())
+        .filter(|r| session_id.is_none() || r.session_id == session_id)
+        .filter(|r| run_id.is_none() || r.run_id == run_id)
+        .collect();
+    requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+    Ok(requests)
+}
+
+/// List "always allow" rules, optionally for one project
+#[tauri::command]
+pub async fn list_permission_rules(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<Vec<PermissionRule>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_path, tool_name, created_at FROM permission_rules
+             WHERE ?1 IS NULL OR project_path = ?1
+             ORDER BY project_path, tool_name",
+        )
+        .map_err(|e| e.to_string())?;
+    let rules = stmt
+        .query_map(params![project_path], |row| {
+            Ok(PermissionRule {
+                id: row.get(0)?,
+                project_path: row.get(1)?,
+                tool_name: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rules)
+}
+
+#[tauri::command]
+pub async fn delete_permission_rule(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM permission_rules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whether runs ask before using tools instead of skipping permission checks
+#[tauri::command]
+pub async fn get_interactive_permissions(db: State<'_, AgentDb>) -> Result<bool, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(interactive_permissions_enabled(&conn))
+}
+
+/// Turn interactive permissions on or off; applies to runs started afterwards
+#[tauri::command]
+pub async fn set_interactive_permissions(
+    db: State<'_, AgentDb>,
+    enabled: bool,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2",
+        params![INTERACTIVE_PERMISSIONS_KEY, enabled.to_string()],
+    )
+    .map_err(|e| format!("Failed to save permission setting: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register_scope(project_path: &str) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        PERMISSION_SCOPES.lock().unwrap().insert(
+            token.clone(),
+            PermissionScope {
+                project_path: project_path.to_string(),
+                run_id: Some(7),
+                session_id: None,
+            },
+        );
+        token
+    }
+
+    fn bash_call() -> JsonValue {
+        json!({ "tool_name": "Bash", "input": { "command": "ls" }, "tool_use_id": "toolu_1" })
+    }
+
+    #[test]
+    fn rules_match_per_project_and_tool() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_permission_rules_db(&conn).unwrap();
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO permission_rules (project_path, tool_name) VALUES ('/work/a', 'Bash')",
+            [],
+        )
+        .unwrap();
+
+        assert!(is_always_allowed(&conn, "/work/a", "Bash"));
+        assert!(!is_always_allowed(&conn, "/work/a", "Edit"));
+        assert!(!is_always_allowed(&conn, "/work/b", "Bash"));
+
+        assert!(!interactive_permissions_enabled(&conn));
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES (?1, 'true')",
+            params![INTERACTIVE_PERMISSIONS_KEY],
+        )
+        .unwrap();
+        assert!(interactive_permissions_enabled(&conn));
+    }
+
+    #[tokio::test]
+    async fn decide_allows_by_rule_and_waits_for_an_answer() {
+        let token = register_scope("/work/decide");
+
+        let decision = decide_with(
+            &token,
+            &bash_call(),
+            PERMISSION_REQUEST_TIMEOUT,
+            |project, tool| project == "/work/decide" && tool == "Bash",
+            |_| panic!("an always-allowed tool must not ask"),
+        )
+        .await;
+        assert_eq!(decision["behavior"], "allow");
+        assert_eq!(decision["updatedInput"]["command"], "ls");
+
+        let (announced, request) = oneshot::channel();
+        let mut announced = Some(announced);
+        let call = bash_call();
+        let pending = decide_with(
+            &token,
+            &call,
+            PERMISSION_REQUEST_TIMEOUT,
+            |_, _| false,
+            |request| {
+                let _ = announced.take().unwrap().send(request.clone());
+            },
+        );
+        let answer = async {
+            let request: PermissionRequest = request.await.unwrap();
+            assert_eq!(request.run_id, Some(7));
+            assert_eq!(request.tool_use_id.as_deref(), Some("toolu_1"));
+            let pending = PENDING_REQUESTS
+                .lock()
+                .unwrap()
+                .remove(&request.request_id)
+                .unwrap();
+            let _ = pending
+                .reply
+                .send(PermissionDecision::Deny("not now".to_string()));
+        };
+        let (decision, ()) = tokio::join!(pending, answer);
+        assert_eq!(decision["behavior"], "deny");
+        assert_eq!(decision["message"], "not now");
+    }
+
+    #[tokio::test]
+    async fn decide_denies_unanswered_and_unknown_runs() {
+        let token = register_scope("/work/timeout");
+        let decision = decide_with(
+            &token,
+            &bash_call(),
+            Duration::from_millis(20),
+            |_, _| false,
+            |_| {},
+        )
+        .await;
+        assert_eq!(decision["behavior"], "deny");
+        assert!(PENDING_REQUESTS
+            .lock()
+            .unwrap()
+            .values()
+            .all(|p| p.request.project_path != "/work/timeout"));
+
+        let decision = decide_with(
+            "no-such-run",
+            &bash_call(),
+            PERMISSION_REQUEST_TIMEOUT,
+            |_, _| true,
+            |_| {},
+        )
+        .await;
+        assert_eq!(decision["behavior"], "deny");
+    }
+
+    #[tokio::test]
+    async fn answers_the_mcp_handshake() {
+        let approve = |arguments: JsonValue| async move {
+            json!({ "behavior": "allow", "updatedInput": arguments["input"] })
+        };
+
+        let reply = mcp_reply(
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "protocolVersion": "2024-11-05", "capabilities": {} },
+            }),
+            approve,
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(reply["result"]["serverInfo"]["name"], MCP_SERVER_NAME);
+
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(mcp_reply(&initialized, approve).await.is_none());
+
+        let reply = mcp_reply(
+            &json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+            approve,
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["result"]["tools"][0]["name"], MCP_TOOL_NAME);
+        assert_eq!(
+            PERMISSION_PROMPT_TOOL,
+            format!("mcp__{}__{}", MCP_SERVER_NAME, MCP_TOOL_NAME)
+        );
+
+        let reply = mcp_reply(
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": { "name": MCP_TOOL_NAME, "arguments": bash_call() },
+            }),
+            approve,
+        )
+        .await
+        .unwrap();
+        let text = reply["result"]["content"][0]["text"].as_str().unwrap();
+        let decision: JsonValue = serde_json::from_str(text).unwrap();
+        assert_eq!(decision["behavior"], "allow");
+        assert_eq!(decision["updatedInput"]["command"], "ls");
+
+        let reply = mcp_reply(
+            &json!({ "jsonrpc": "2.0", "id": 4, "method": "resources/list" }),
+            approve,
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["error"]["code"], -32601);
+    }
+}
@@ -0,0 +1,304 @@
+//! Port registry
+//! Records which dev server and proxy ports each project holds, so they stay stable across
+//! restarts and never collide
+
+use std::io::ErrorKind;
+use std::net::{Ipv6Addr, TcpListener};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use super::agents::AgentDb;
+
+/// Dev server ports; the same range project IDs used to be hashed into
+const DEV_PORT_RANGE: RangeInclusive<u16> = 32100..=42099;
+
+/// Proxy ports, kept clear of the dev range
+const PROXY_PORT_RANGE: RangeInclusive<u16> = 42100..=52099;
+
+/// How long a project's own port may take to be released (e.g. by a proxy still shutting
+/// down) before it counts as taken by someone else
+const PORT_RELEASE_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortKind {
+    Dev,
+    Proxy,
+}
+
+impl PortKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PortKind::Dev => "dev",
+            PortKind::Proxy => "proxy",
+        }
+    }
+
+    fn range(&self) -> RangeInclusive<u16> {
+        match self {
+            PortKind::Dev => DEV_PORT_RANGE,
+            PortKind::Proxy => PROXY_PORT_RANGE,
+        }
+    }
+}
+
+/// A port held by a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortAllocation {
+    pub project_path: String,
+    pub kind: String, // "dev" or "proxy"
+    pub port: u16,
+    pub project_id: Option<String>,
+    pub status: String, // "in_use" while its server runs, "reserved" otherwise
+    pub allocated_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// Initialize port_allocations table
+pub fn init_port_registry_db(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS port_allocations (
+            project_path TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            port INTEGER NOT NULL UNIQUE,
+            project_id TEXT,
+            status TEXT NOT NULL DEFAULT 'reserved',
+            allocated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_used_at TEXT,
+            PRIMARY KEY (project_path, kind)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Whether nothing is listening on a port, on either loopback address
+fn port_is_free(port: u16) -> bool {
+    let v6_free = match TcpListener::bind((Ipv6Addr::LOCALHOST, port)) {
+        Ok(_) => true,
+        // No IPv6 on this machine, so nothing can be listening there
+        Err(e) => e.kind() == ErrorKind::AddrNotAvailable,
+    };
+    v6_free && TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+fn wait_until_free(port: u16) -> bool {
+    let deadline = std::time::Instant::now() + PORT_RELEASE_WAIT;
+    loop {
+        if port_is_free(port) {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Pick a free port in `kind`'s range, starting from `preferred` and skipping ports other
+/// projects hold
+fn pick_port(conn: &Connection, project_path: &str, kind: PortKind, preferred: u16) -> Option<u16> {
+    let taken: Vec<u16> = conn
+        .prepare("SELECT port FROM port_allocations WHERE NOT (project_path = ?1 AND kind = ?2)")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![project_path, kind.as_str()], |row| row.get(0))?
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let range = kind.range();
+    let start = if range.contains(&preferred) {
+        preferred
+    } else {
+        *range.start()
+    };
+    let candidates = (start..=*range.end()).chain(*range.start()..start);
+    candidates
+        .filter(|port| !taken.contains(port))
+        .find(|&port| port_is_free(port))
+}
+
+/// Get the port a project holds for `kind`, allocating one if it has none
+///
+/// A held port that another process has since taken is given up and a new one is
+/// allocated. `preferred` is where the search for a new port starts.
+pub fn allocate_port(
+    db: &AgentDb,
+    project_path: &str,
+    project_id: Option<&str>,
+    kind: PortKind,
+    preferred: u16,
+) -> Result<u16, String> {
+    let held: Option<u16> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT port FROM port_allocations WHERE project_path = ?1 AND kind = ?2",
+            params![project_path, kind.as_str()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+    };
+
+    // Checked without the DB lock since it may wait for the port to be released
+    let reusable = held.filter(|&port| wait_until_free(port));
+    if let Some(port) = held.filter(|_| reusable.is_none()) {
+        log::warn!(
+            "{} port {} of {} is taken by another process; reassigning",
+            kind.as_str(),
+            port,
+            project_path
+        );
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let port = match reusable {
+        Some(port) => port,
+        None => pick_port(&conn, project_path, kind, preferred)
+            .ok_or_else(|| format!("No free {} port left for {}", kind.as_str(), project_path))?,
+    };
+    conn.execute(
+        "INSERT INTO port_allocations (project_path, kind, port, project_id, status, last_used_at)
+         VALUES (?1, ?2, ?3, ?4, 'in_use', datetime('now'))
+         ON CONFLICT(project_path, kind) DO UPDATE SET
+            port = excluded.port,
+            project_id = COALESCE(excluded.project_id, port_allocations.project_id),
+            status = 'in_use',
+            allocated_at = CASE WHEN port_allocations.port = excluded.port
+                THEN port_allocations.allocated_at ELSE datetime('now') END,
+            last_used_at = excluded.last_used_at",
+        params![project_path, kind.as_str(), port, project_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Allocated {} port {} to {}",
+        kind.as_str(),
+        port,
+        project_path
+    );
+    Ok(port)
+}
+
+/// `allocate_port` on the blocking pool, for async callers
+///
+/// Reusing a held port may wait for it to be released, and finding a new one binds
+/// candidate ports one at a time.
+pub async fn allocate_port_async(
+    app: &AppHandle,
+    project_path: &str,
+    project_id: Option<&str>,
+    kind: PortKind,
+    preferred: u16,
+) -> Result<u16, String> {
+    let app = app.clone();
+    let project_path = project_path.to_string();
+    let project_id = project_id.map(str::to_string);
+    tokio::task::spawn_blocking(move || {
+        allocate_port(
+            &app.state::<AgentDb>(),
+            &project_path,
+            project_id.as_deref(),
+            kind,
+            preferred,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Keep a project's ports for its next run, but mark them as not in use
+pub fn reserve_project_ports(conn: &Connection, project_path: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE port_allocations SET status = 'reserved' WHERE project_path = ?1",
+        params![project_path],
+    )?;
+    Ok(())
+}
+
+/// List port allocations, optionally for a single project
+#[tauri::command]
+pub async fn list_port_allocations(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<Vec<PortAllocation>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT project_path, kind, port, project_id, status, allocated_at, last_used_at
+             FROM port_allocations
+             WHERE ?1 IS NULL OR project_path = ?1
+             ORDER BY port",
+        )
+        .map_err(|e| e.to_string())?;
+    let allocations = stmt
+        .query_map(params![project_path], |row| {
+            Ok(PortAllocation {
+                project_path: row.get(0)?,
+                kind: row.get(1)?,
+                port: row.get(2)?,
+                project_id: row.get(3)?,
+                status: row.get(4)?,
+                allocated_at: row.get(5)?,
+                last_used_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(allocations)
+}
+
+/// Give up a project's ports so other projects can use them
+#[tauri::command]
+pub async fn release_project_ports(
+    db: State<'_, AgentDb>,
+    project_path: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM port_allocations WHERE project_path = ?1",
+        params![project_path],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn test_db() -> AgentDb {
+        let conn = Connection::open_in_memory().unwrap();
+        init_port_registry_db(&conn).unwrap();
+        AgentDb(Mutex::new(conn))
+    }
+
+    #[test]
+    fn keeps_ports_stable_and_avoids_other_projects_and_busy_ports() {
+        let db = test_db();
+        let port = allocate_port(&db, "/work/a", Some("a"), PortKind::Dev, 33000).unwrap();
+        assert!(DEV_PORT_RANGE.contains(&port));
+        assert_eq!(
+            allocate_port(&db, "/work/a", None, PortKind::Dev, 35000).unwrap(),
+            port
+        );
+
+        // Another project asking for the same port gets a different one
+        let other = allocate_port(&db, "/work/b", Some("b"), PortKind::Dev, port).unwrap();
+        assert_ne!(other, port);
+
+        // A foreign process takes project a's port, so a is moved
+        let _squatter = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        let moved = allocate_port(&db, "/work/a", None, PortKind::Dev, port).unwrap();
+        assert_ne!(moved, port);
+        assert_ne!(moved, other);
+
+        let proxy = allocate_port(&db, "/work/a", None, PortKind::Proxy, moved + 10000).unwrap();
+        assert!(PROXY_PORT_RANGE.contains(&proxy));
+    }
+}
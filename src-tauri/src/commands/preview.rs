@@ -1,38 +1,289 @@
 use serde::{Deserialize, Serialize};
 use std::net::TcpStream;
 use std::time::Duration;
+use tauri::State;
+
+use crate::process::ProcessRegistryState;
+
+/// Ports probed where listening sockets can't be listed
+const COMMON_DEV_PORTS: [u16; 10] = [3000, 3001, 3002, 5173, 5174, 5175, 8080, 8000, 4200, 4321];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PortInfo {
     pub port: u16,
     pub url: String,
     pub alive: bool,
+    pub pid: Option<u32>, // Process listening on the port, when it could be found
+    pub command: Option<String>, // Its command line
+    pub owner: Option<String>, // "dev-server", "claude-run" or "project" (cwd inside it)
 }
 
-#[tauri::command]
-pub async fn scan_ports() -> Result<Vec<PortInfo>, String> {
-    log::info!("scan_ports: Starting port scan...");
-    let common_ports = vec![3000, 3001, 3002, 5173, 5174, 5175, 8080, 8000, 4200, 4321];
-
-    let mut results = Vec::new();
-    let mut alive_count = 0;
-
-    for port in common_ports {
-        let alive = check_port(port);
-        if alive {
-            alive_count += 1;
-        }
-        results.push(PortInfo {
+impl PortInfo {
+    fn probed(port: u16, alive: bool) -> Self {
+        Self {
             port,
             url: format!("http://localhost:{}", port),
             alive,
-        });
+            pid: None,
+            command: None,
+            owner: None,
+        }
     }
+}
+
+/// Find dev server ports
+///
+/// With `project_path`, returns the ports listened on by that project's processes: its dev
+/// server's process tree, its Claude runs, or anything running from inside the project.
+/// Without it, reports which of the common dev ports are in use. On Linux both come from
+/// `/proc` without connecting anywhere; elsewhere the common ports are probed.
+#[tauri::command]
+pub async fn scan_ports(
+    registry: State<'_, ProcessRegistryState>,
+    project_path: Option<String>,
+) -> Result<Vec<PortInfo>, String> {
+    log::info!(
+        "scan_ports: Starting port scan (project: {:?})...",
+        project_path
+    );
+    let start = std::time::Instant::now();
+
+    let roots = match &project_path {
+        Some(project_path) => {
+            let mut roots = Vec::new();
+            if let Some(pid) = super::dev_server::dev_server_pid(project_path) {
+                roots.push((pid, "dev-server"));
+            }
+            for process in registry.0.get_running_processes()? {
+                if same_path(&process.project_path, project_path) {
+                    roots.push((process.pid, "claude-run"));
+                }
+            }
+            roots
+        }
+        None => Vec::new(),
+    };
 
-    log::info!("scan_ports: Completed. Found {} alive ports", alive_count);
+    let results = tokio::task::spawn_blocking(move || {
+        #[cfg(target_os = "linux")]
+        {
+            let listeners = linux::listening_ports();
+            match &project_path {
+                Some(project_path) => linux::project_ports(&listeners, project_path, &roots),
+                None => COMMON_DEV_PORTS
+                    .iter()
+                    .map(|&port| match listeners.iter().find(|l| l.port == port) {
+                        Some(listener) => linux::port_info(listener, None),
+                        None => PortInfo::probed(port, false),
+                    })
+                    .collect(),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = roots;
+            let probed = probe_common_ports();
+            match project_path {
+                // Ownership isn't known here, so only live ports are worth reporting
+                Some(_) => probed.into_iter().filter(|p| p.alive).collect(),
+                None => probed,
+            }
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "scan_ports: Completed in {}ms. Found {} alive ports",
+        start.elapsed().as_millis(),
+        results.iter().filter(|p| p.alive).count()
+    );
     Ok(results)
 }
 
+fn same_path(a: &str, b: &str) -> bool {
+    a.trim_end_matches(['/', '\\']) == b.trim_end_matches(['/', '\\'])
+}
+
+/// Probe the common dev ports concurrently
+#[cfg(not(target_os = "linux"))]
+fn probe_common_ports() -> Vec<PortInfo> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = COMMON_DEV_PORTS
+            .iter()
+            .map(|&port| scope.spawn(move || PortInfo::probed(port, check_port(port))))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+/// Listening sockets and their owners, read from `/proc`
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::PortInfo;
+
+    /// `st` value of a listening socket in `/proc/net/tcp`
+    const TCP_LISTEN: &str = "0A";
+
+    pub struct Listener {
+        pub port: u16,
+        pub pid: Option<u32>,
+    }
+
+    /// Ports and socket inodes of listening sockets in one `/proc/net/tcp*` table
+    pub(super) fn parse_tcp_table(table: &str) -> Vec<(u16, u64)> {
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 10 || fields[3] != TCP_LISTEN {
+                    return None;
+                }
+                let port = fields[1].rsplit(':').next()?;
+                let port = u16::from_str_radix(port, 16).ok()?;
+                let inode = fields[9].parse::<u64>().ok()?;
+                Some((port, inode))
+            })
+            .collect()
+    }
+
+    /// Map socket inodes to the processes holding them, as far as we're allowed to look
+    fn socket_owners(inodes: &HashSet<u64>) -> HashMap<u64, u32> {
+        let mut owners = HashMap::new();
+        let Ok(procs) = fs::read_dir("/proc") else {
+            return owners;
+        };
+        for proc_entry in procs.flatten() {
+            let Some(pid) = proc_entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+                continue;
+            };
+            let Ok(fds) = fs::read_dir(proc_entry.path().join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                let Ok(target) = fs::read_link(fd.path()) else {
+                    continue;
+                };
+                let inode = target
+                    .to_str()
+                    .and_then(|t| t.strip_prefix("socket:["))
+                    .and_then(|t| t.strip_suffix(']'))
+                    .and_then(|t| t.parse::<u64>().ok());
+                if let Some(inode) = inode.filter(|i| inodes.contains(i)) {
+                    owners.entry(inode).or_insert(pid);
+                }
+            }
+            if owners.len() == inodes.len() {
+                break;
+            }
+        }
+        owners
+    }
+
+    /// Every listening TCP port with the process that owns it, lowest port first
+    pub fn listening_ports() -> Vec<Listener> {
+        let sockets: Vec<(u16, u64)> = ["/proc/net/tcp", "/proc/net/tcp6"]
+            .iter()
+            .filter_map(|table| fs::read_to_string(table).ok())
+            .flat_map(|table| parse_tcp_table(&table))
+            .collect();
+        let inodes: HashSet<u64> = sockets.iter().map(|(_, inode)| *inode).collect();
+        let owners = socket_owners(&inodes);
+
+        let mut listeners: Vec<Listener> = Vec::new();
+        for (port, inode) in sockets {
+            let pid = owners.get(&inode).copied();
+            match listeners.iter_mut().find(|l| l.port == port) {
+                // IPv4 and IPv6 sockets on the same port
+                Some(existing) => existing.pid = existing.pid.or(pid),
+                None => listeners.push(Listener { port, pid }),
+            }
+        }
+        listeners.sort_by_key(|l| l.port);
+        listeners
+    }
+
+    /// Parent PID and process group from `/proc/<pid>/stat`
+    fn parent_and_group(pid: u32) -> Option<(u32, u32)> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name may contain spaces and parentheses; fields resume after the last ')'
+        let rest = &stat[stat.rfind(')')? + 1..];
+        let mut fields = rest.split_whitespace().skip(1);
+        let ppid = fields.next()?.parse().ok()?;
+        let pgid = fields.next()?.parse().ok()?;
+        Some((ppid, pgid))
+    }
+
+    fn command_line(pid: u32) -> Option<String> {
+        let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        let parts: Vec<String> = raw
+            .split(|b| *b == 0)
+            .filter(|p| !p.is_empty())
+            .map(|p| String::from_utf8_lossy(p).to_string())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Which of `roots` a process descends from, or belongs to the process group of
+    fn owning_root(pid: u32, roots: &[(u32, &'static str)]) -> Option<&'static str> {
+        let mut current = pid;
+        // Bounded in case of a PID reused mid-walk
+        for _ in 0..64 {
+            if let Some((_, kind)) = roots.iter().find(|(root, _)| *root == current) {
+                return Some(kind);
+            }
+            let (ppid, pgid) = parent_and_group(current)?;
+            // Orphaned dev server children are reparented but stay in its group
+            if let Some((_, kind)) = roots.iter().find(|(root, _)| *root == pgid) {
+                return Some(kind);
+            }
+            if ppid <= 1 {
+                return None;
+            }
+            current = ppid;
+        }
+        None
+    }
+
+    pub fn port_info(listener: &Listener, owner: Option<&str>) -> PortInfo {
+        PortInfo {
+            port: listener.port,
+            url: format!("http://localhost:{}", listener.port),
+            alive: true,
+            pid: listener.pid,
+            command: listener.pid.and_then(command_line),
+            owner: owner.map(str::to_string),
+        }
+    }
+
+    /// Listeners that belong to a project
+    pub fn project_ports(
+        listeners: &[Listener],
+        project_path: &str,
+        roots: &[(u32, &'static str)],
+    ) -> Vec<PortInfo> {
+        let project = Path::new(project_path);
+        listeners
+            .iter()
+            .filter_map(|listener| {
+                let pid = listener.pid?;
+                let owner = owning_root(pid, roots).or_else(|| {
+                    fs::read_link(format!("/proc/{}/cwd", pid))
+                        .ok()
+                        .filter(|cwd: &PathBuf| cwd.starts_with(project))
+                        .map(|_| "project")
+                })?;
+                Some(port_info(listener, Some(owner)))
+            })
+            .collect()
+    }
+}
+
 fn check_port(port: u16) -> bool {
     let alive = TcpStream::connect_timeout(
         &format!("127.0.0.1:{}", port).parse().unwrap(),
@@ -115,3 +366,37 @@ pub async fn check_port_alive(
         elapsed_ms: elapsed,
     })
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_listening_sockets_from_proc_tables() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:14EB 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41523 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:14EB 0100007F:C6A2 01 00000000:00000000 00:00000000 00000000  1000        0 41999 1 0000000000000000 20 4 30 10 -1
+   2: 00000000000000000000000001000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 52011 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(
+            linux::parse_tcp_table(table),
+            [(5355, 41523), (3000, 52011)]
+        );
+    }
+
+    #[test]
+    fn attributes_own_listener_to_project_cwd() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let cwd = std::env::current_dir().unwrap();
+
+        let listeners = linux::listening_ports();
+        let ours = listeners.iter().find(|l| l.port == port).unwrap();
+        assert_eq!(ours.pid, Some(std::process::id()));
+
+        let ports = linux::project_ports(&listeners, cwd.to_str().unwrap(), &[]);
+        let info = ports.iter().find(|p| p.port == port).unwrap();
+        assert_eq!(info.owner.as_deref(), Some("project"));
+        assert!(info.command.is_some());
+    }
+}
@@ -0,0 +1,572 @@
+//! Network capture for the preview proxy
+//! Records the requests a project's preview makes and exports them as HAR 1.2
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, StatusCode};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use super::dev_server::decompress_body;
+
+/// Entries kept per project; older ones are dropped first
+const NETWORK_LOG_CAPACITY: usize = 500;
+
+/// Bytes of each request/response body kept for display
+const NETWORK_BODY_CAPTURE_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// One request the preview made through the proxy, with its response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkEntry {
+    pub id: u64,            // Increasing per project
+    pub started_at: String, // RFC3339
+    pub method: String,
+    pub url: String,  // Full URL on the dev server
+    pub path: String, // Path and query
+    pub http_version: String,
+    pub request_headers: Vec<NetworkHeader>,
+    pub request_body: Option<String>, // Text bodies only, truncated
+    pub request_body_size: u64,
+    pub status: Option<u16>, // None when the dev server couldn't be reached
+    pub status_text: Option<String>,
+    pub response_headers: Vec<NetworkHeader>,
+    pub response_body: Option<String>, // Text bodies only, truncated
+    pub response_body_size: u64,       // Bytes on the wire, before decompression
+    pub response_body_truncated: bool,
+    pub mime_type: Option<String>,
+    pub wait_ms: f64,     // Until the response headers arrived
+    pub duration_ms: f64, // Until the response body finished
+    pub error: Option<String>,
+}
+
+/// Which entries to return; unset fields match everything
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkLogFilter {
+    pub since_id: Option<u64>,
+    pub method: Option<String>,
+    pub url_contains: Option<String>,
+    pub status_min: Option<u16>,
+    pub status_max: Option<u16>,
+    pub failed_only: bool, // Connection errors and 4xx/5xx responses
+}
+
+impl NetworkLogFilter {
+    fn matches(&self, entry: &NetworkEntry) -> bool {
+        if self.since_id.is_some_and(|id| entry.id <= id) {
+            return false;
+        }
+        if let Some(method) = &self.method {
+            if !entry.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(needle) = &self.url_contains {
+            if !entry.url.to_lowercase().contains(&needle.to_lowercase()) {
+                return false;
+            }
+        }
+        let status = entry.status.unwrap_or(0);
+        if self.status_min.is_some_and(|min| status < min)
+            || self.status_max.is_some_and(|max| status > max)
+        {
+            return false;
+        }
+        if self.failed_only && entry.error.is_none() && status < 400 {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Default)]
+struct NetworkLog {
+    next_id: u64,
+    entries: VecDeque<NetworkEntry>,
+}
+
+lazy_static::lazy_static! {
+    static ref NETWORK_LOGS: Mutex<HashMap<String, NetworkLog>> = Mutex::new(HashMap::new());
+}
+
+fn record_network_entry(project_path: &str, mut entry: NetworkEntry) {
+    let mut logs = NETWORK_LOGS.lock().unwrap();
+    let log = logs.entry(project_path.to_string()).or_default();
+    log.next_id += 1;
+    entry.id = log.next_id;
+    if log.entries.len() == NETWORK_LOG_CAPACITY {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(entry);
+}
+
+fn collect_headers(headers: &HeaderMap) -> Vec<NetworkHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| NetworkHeader {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).to_string(),
+        })
+        .collect()
+}
+
+fn find_header<'a>(headers: &'a [NetworkHeader], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    let mime = mime.to_lowercase();
+    mime.starts_with("text/")
+        || mime.contains("json")
+        || mime.contains("javascript")
+        || mime.contains("xml")
+        || mime.contains("x-www-form-urlencoded")
+        || mime.contains("graphql")
+}
+
+/// Captured body as text, if it's a text type and can be decoded
+///
+/// Compressed bodies are only decoded when they were captured whole.
+fn body_text(
+    body: &[u8],
+    mime_type: Option<&str>,
+    content_encoding: Option<&str>,
+    truncated: bool,
+) -> Option<String> {
+    if body.is_empty() || !mime_type.map(is_text_mime).unwrap_or(false) {
+        return None;
+    }
+    match content_encoding.map(|e| e.trim().to_lowercase()) {
+        Some(encoding) if encoding != "identity" => {
+            if truncated {
+                return None;
+            }
+            Some(String::from_utf8_lossy(&decompress_body(body, Some(&encoding))).to_string())
+        }
+        _ => Some(String::from_utf8_lossy(body).to_string()),
+    }
+}
+
+/// The first `NETWORK_BODY_CAPTURE_LIMIT` bytes of a body, plus its full size
+#[derive(Default)]
+struct CapturedBody {
+    bytes: Vec<u8>,
+    size: u64,
+    truncated: bool,
+}
+
+impl CapturedBody {
+    fn push(&mut self, chunk: &[u8]) {
+        self.size += chunk.len() as u64;
+        let room = NETWORK_BODY_CAPTURE_LIMIT.saturating_sub(self.bytes.len());
+        if chunk.len() > room {
+            self.truncated = true;
+        }
+        self.bytes
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+/// An exchange in flight through the proxy
+///
+/// The entry is written to the project's log when the capture is dropped, which is when the
+/// response body finished streaming, the client went away, or the request failed.
+pub(crate) struct NetworkCapture {
+    project_path: String,
+    entry: NetworkEntry,
+    started: Instant,
+    request_body: Arc<Mutex<CapturedBody>>, // Filled as the request streams upstream
+    response_body: CapturedBody,
+}
+
+impl NetworkCapture {
+    /// Start capturing a request, returning the body to forward in place of `request_body`
+    pub(crate) fn start(
+        project_path: &str,
+        method: &str,
+        url: String,
+        path: &str,
+        http_version: &str,
+        request_headers: &HeaderMap,
+        request_body: Body,
+    ) -> (Self, Body) {
+        let captured = Arc::new(Mutex::new(CapturedBody::default()));
+        let sink = captured.clone();
+        let request_body = Body::from_stream(request_body.into_data_stream().map(move |item| {
+            if let Ok(chunk) = &item {
+                sink.lock().unwrap().push(chunk);
+            }
+            item
+        }));
+
+        let capture = Self {
+            project_path: project_path.to_string(),
+            entry: NetworkEntry {
+                started_at: chrono::Utc::now().to_rfc3339(),
+                method: method.to_string(),
+                url,
+                path: path.to_string(),
+                http_version: http_version.to_string(),
+                request_headers: collect_headers(request_headers),
+                ..Default::default()
+            },
+            started: Instant::now(),
+            request_body: captured,
+            response_body: CapturedBody::default(),
+        };
+        (capture, request_body)
+    }
+
+    pub(crate) fn response_head(&mut self, status: StatusCode, headers: &HeaderMap) {
+        self.entry.wait_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+        self.entry.status = Some(status.as_u16());
+        self.entry.status_text = status.canonical_reason().map(str::to_string);
+        self.entry.response_headers = collect_headers(headers);
+        self.entry.mime_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(';').next().unwrap_or(v).trim().to_string());
+    }
+
+    fn response_chunk(&mut self, chunk: &[u8]) {
+        self.response_body.push(chunk);
+    }
+
+    pub(crate) fn fail(mut self, error: String) {
+        self.entry.error = Some(error);
+    }
+}
+
+impl Drop for NetworkCapture {
+    fn drop(&mut self) {
+        let mut entry = std::mem::take(&mut self.entry);
+        entry.duration_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+
+        let request = std::mem::take(&mut *self.request_body.lock().unwrap());
+        entry.request_body_size = request.size;
+        entry.request_body = body_text(
+            &request.bytes,
+            find_header(&entry.request_headers, "content-type"),
+            find_header(&entry.request_headers, "content-encoding"),
+            request.truncated,
+        );
+
+        entry.response_body_size = self.response_body.size;
+        entry.response_body_truncated = self.response_body.truncated;
+        entry.response_body = body_text(
+            &self.response_body.bytes,
+            entry.mime_type.as_deref(),
+            find_header(&entry.response_headers, "content-encoding"),
+            self.response_body.truncated,
+        );
+        record_network_entry(&self.project_path, entry);
+    }
+}
+
+/// Pass a response body through unchanged while recording it into `capture`
+pub(crate) fn capture_body(body: Body, capture: NetworkCapture) -> Body {
+    Body::from_stream(futures_util::stream::unfold(
+        (body.into_data_stream(), Some(capture)),
+        |(mut stream, mut capture)| async move {
+            let item = stream.next().await?;
+            match &item {
+                Ok(chunk) => {
+                    if let Some(capture) = capture.as_mut() {
+                        capture.response_chunk(chunk);
+                    }
+                }
+                Err(e) => {
+                    if let Some(capture) = capture.take() {
+                        capture.fail(e.to_string());
+                    }
+                }
+            }
+            Some((item, (stream, capture)))
+        },
+    ))
+}
+
+fn har_headers(headers: &[NetworkHeader]) -> JsonValue {
+    JsonValue::Array(
+        headers
+            .iter()
+            .map(|h| json!({ "name": h.name, "value": h.value }))
+            .collect(),
+    )
+}
+
+fn har_query_string(path: &str) -> JsonValue {
+    let query = path.split_once('?').map(|(_, q)| q).unwrap_or("");
+    JsonValue::Array(
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let decode = |s: &str| {
+                    urlencoding::decode(&s.replace('+', " "))
+                        .map(|d| d.into_owned())
+                        .unwrap_or_else(|_| s.to_string())
+                };
+                json!({ "name": decode(name), "value": decode(value) })
+            })
+            .collect(),
+    )
+}
+
+fn har_entry(entry: &NetworkEntry) -> JsonValue {
+    let mut request = json!({
+        "method": entry.method,
+        "url": entry.url,
+        "httpVersion": entry.http_version,
+        "cookies": [],
+        "headers": har_headers(&entry.request_headers),
+        "queryString": har_query_string(&entry.path),
+        "headersSize": -1,
+        "bodySize": entry.request_body_size,
+    });
+    if entry.request_body_size > 0 {
+        request["postData"] = json!({
+            "mimeType": find_header(&entry.request_headers, "content-type").unwrap_or(""),
+            "text": entry.request_body.clone().unwrap_or_default(),
+        });
+    }
+
+    let mut content = json!({
+        "size": entry.response_body_size,
+        "mimeType": entry.mime_type.clone().unwrap_or_default(),
+    });
+    if let Some(text) = &entry.response_body {
+        content["text"] = json!(text);
+    }
+    if entry.response_body_truncated {
+        content["comment"] = json!(format!(
+            "Body truncated to {} bytes",
+            NETWORK_BODY_CAPTURE_LIMIT
+        ));
+    }
+
+    let mut har = json!({
+        "startedDateTime": entry.started_at,
+        "time": entry.duration_ms,
+        "request": request,
+        "response": {
+            // HAR uses status 0 for requests that never got a response
+            "status": entry.status.unwrap_or(0),
+            "statusText": entry.status_text.clone().unwrap_or_default(),
+            "httpVersion": entry.http_version,
+            "cookies": [],
+            "headers": har_headers(&entry.response_headers),
+            "content": content,
+            "redirectURL": find_header(&entry.response_headers, "location").unwrap_or(""),
+            "headersSize": -1,
+            "bodySize": entry.response_body_size,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": entry.wait_ms,
+            "receive": (entry.duration_ms - entry.wait_ms).max(0.0),
+        },
+    });
+    if let Some(error) = &entry.error {
+        har["comment"] = json!(error);
+    }
+    har
+}
+
+fn filtered_entries(project_path: &str, filter: &NetworkLogFilter) -> Vec<NetworkEntry> {
+    let logs = NETWORK_LOGS.lock().unwrap();
+    logs.get(project_path)
+        .map(|log| {
+            log.entries
+                .iter()
+                .filter(|e| filter.matches(e))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get the requests a project's preview made, oldest first
+#[tauri::command]
+pub async fn get_preview_network_log(
+    project_path: String,
+    filter: Option<NetworkLogFilter>,
+) -> Result<Vec<NetworkEntry>, String> {
+    Ok(filtered_entries(&project_path, &filter.unwrap_or_default()))
+}
+
+/// Forget all captured requests for a project
+#[tauri::command]
+pub async fn clear_preview_network_log(project_path: String) -> Result<(), String> {
+    let mut logs = NETWORK_LOGS.lock().map_err(|e| e.to_string())?;
+    if let Some(log) = logs.get_mut(&project_path) {
+        log.entries.clear();
+    }
+    Ok(())
+}
+
+/// Export captured requests as a HAR 1.2 document
+#[tauri::command]
+pub async fn export_preview_network_har(
+    project_path: String,
+    filter: Option<NetworkLogFilter>,
+) -> Result<JsonValue, String> {
+    let entries = filtered_entries(&project_path, &filter.unwrap_or_default());
+    Ok(json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "Anyon",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "pages": [],
+            "entries": entries.iter().map(har_entry).collect::<Vec<_>>(),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    async fn drain(body: Body) -> Vec<u8> {
+        axum::body::to_bytes(body, usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    fn json_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn captures_streamed_bodies_and_exports_har() {
+        let project = "/tmp/preview-network-capture";
+        let (mut capture, request_body) = NetworkCapture::start(
+            project,
+            "POST",
+            "http://localhost:5173/api/items?q=a+b&page=2".to_string(),
+            "/api/items?q=a+b&page=2",
+            "HTTP/1.1",
+            &json_headers(),
+            Body::from(r#"{"name":"x"}"#),
+        );
+        // The request body passes through untouched
+        assert_eq!(drain(request_body).await, br#"{"name":"x"}"#);
+
+        capture.response_head(StatusCode::CREATED, &json_headers());
+        let response = capture_body(Body::from(r#"{"id":1}"#), capture);
+        assert_eq!(drain(response).await, br#"{"id":1}"#);
+
+        let entries = filtered_entries(project, &NetworkLogFilter::default());
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.request_body.as_deref(), Some(r#"{"name":"x"}"#));
+        assert_eq!(entry.request_body_size, 12);
+        assert_eq!(entry.status, Some(201));
+        assert_eq!(entry.response_body.as_deref(), Some(r#"{"id":1}"#));
+        assert_eq!(entry.mime_type.as_deref(), Some("application/json"));
+
+        let har = export_preview_network_har(project.to_string(), None)
+            .await
+            .unwrap();
+        let har_entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har_entry["request"]["postData"]["text"], r#"{"name":"x"}"#);
+        assert_eq!(
+            har_entry["request"]["queryString"],
+            json!([{ "name": "q", "value": "a b" }, { "name": "page", "value": "2" }])
+        );
+        assert_eq!(har_entry["response"]["status"], 201);
+        assert_eq!(har_entry["response"]["content"]["text"], r#"{"id":1}"#);
+    }
+
+    #[tokio::test]
+    async fn truncates_large_bodies_and_records_failures() {
+        let project = "/tmp/preview-network-truncate";
+        let large = "a".repeat(NETWORK_BODY_CAPTURE_LIMIT + 100);
+        let (capture, request_body) = NetworkCapture::start(
+            project,
+            "PUT",
+            "http://localhost:5173/upload".to_string(),
+            "/upload",
+            "HTTP/1.1",
+            &json_headers(),
+            Body::from(large.clone()),
+        );
+        assert_eq!(drain(request_body).await.len(), large.len());
+        capture.fail("connect failed".to_string());
+
+        let entries = filtered_entries(
+            project,
+            &NetworkLogFilter {
+                failed_only: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].request_body_size, large.len() as u64);
+        assert_eq!(
+            entries[0].request_body.as_ref().map(String::len),
+            Some(NETWORK_BODY_CAPTURE_LIMIT)
+        );
+        assert_eq!(entries[0].status, None);
+
+        let har = export_preview_network_har(project.to_string(), None)
+            .await
+            .unwrap();
+        let har_entry = &har["log"]["entries"][0];
+        assert_eq!(har_entry["response"]["status"], 0);
+        assert_eq!(har_entry["comment"], "connect failed");
+    }
+
+    #[test]
+    fn filters_by_method_url_and_status() {
+        let entry = NetworkEntry {
+            id: 3,
+            method: "GET".to_string(),
+            url: "http://localhost:5173/API/users".to_string(),
+            status: Some(404),
+            ..Default::default()
+        };
+        let matches = |filter: NetworkLogFilter| filter.matches(&entry);
+
+        assert!(matches(NetworkLogFilter::default()));
+        assert!(matches(NetworkLogFilter {
+            method: Some("get".to_string()),
+            url_contains: Some("api/".to_string()),
+            failed_only: true,
+            ..Default::default()
+        }));
+        assert!(!matches(NetworkLogFilter {
+            since_id: Some(3),
+            ..Default::default()
+        }));
+        assert!(!matches(NetworkLogFilter {
+            status_max: Some(399),
+            ..Default::default()
+        }));
+    }
+}
@@ -1,11 +1,12 @@
 use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
-use serde_json;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 use tauri::command;
 
+use crate::stream_json::{parse_line, StreamMessage, Usage};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UsageEntry {
     timestamp: String,
@@ -74,34 +75,7 @@ const SONNET_4_OUTPUT_PRICE: f64 = 15.0;
 const SONNET_4_CACHE_WRITE_PRICE: f64 = 3.75;
 const SONNET_4_CACHE_READ_PRICE: f64 = 0.30;
 
-#[derive(Debug, Deserialize)]
-struct JsonlEntry {
-    timestamp: String,
-    message: Option<MessageData>,
-    #[serde(rename = "sessionId")]
-    session_id: Option<String>,
-    #[serde(rename = "requestId")]
-    request_id: Option<String>,
-    #[serde(rename = "costUSD")]
-    cost_usd: Option<f64>,
-}
-
-#[derive(Debug, Deserialize)]
-struct MessageData {
-    id: Option<String>,
-    model: Option<String>,
-    usage: Option<UsageData>,
-}
-
-#[derive(Debug, Deserialize)]
-struct UsageData {
-    input_tokens: Option<u64>,
-    output_tokens: Option<u64>,
-    cache_creation_input_tokens: Option<u64>,
-    cache_read_input_tokens: Option<u64>,
-}
-
-fn calculate_cost(model: &str, usage: &UsageData) -> f64 {
+pub(crate) fn calculate_cost(model: &str, usage: &Usage) -> f64 {
     let input_tokens = usage.input_tokens.unwrap_or(0) as f64;
     let output_tokens = usage.output_tokens.unwrap_or(0) as f64;
     let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
@@ -159,17 +133,18 @@ fn parse_jsonl_file(
                 continue;
             }
 
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
+            if let Some(parsed) = parse_line(line) {
                 // Extract the actual project path from cwd if we haven't already
                 if actual_project_path.is_none() {
-                    if let Some(cwd) = json_value.get("cwd").and_then(|v| v.as_str()) {
+                    if let Some(cwd) = parsed.cwd() {
                         actual_project_path = Some(cwd.to_string());
                     }
                 }
 
-                // Try to parse as JsonlEntry for usage data
-                if let Ok(entry) = serde_json::from_value::<JsonlEntry>(json_value) {
-                    if let Some(message) = &entry.message {
+                // Only conversation turns carry per-request usage
+                if let StreamMessage::Assistant(entry) | StreamMessage::User(entry) = parsed {
+                    let message = &entry.message;
+                    if let Some(timestamp) = entry.timestamp.clone() {
                         // Deduplication based on message ID and request ID
                         if let (Some(msg_id), Some(req_id)) = (&message.id, &entry.request_id) {
                             let unique_hash = format!("{}:{}", msg_id, req_id);
@@ -181,11 +156,7 @@ fn parse_jsonl_file(
 
                         if let Some(usage) = &message.usage {
                             // Skip entries without meaningful token usage
-                            if usage.input_tokens.unwrap_or(0) == 0
-                                && usage.output_tokens.unwrap_or(0) == 0
-                                && usage.cache_creation_input_tokens.unwrap_or(0) == 0
-                                && usage.cache_read_input_tokens.unwrap_or(0) == 0
-                            {
+                            if usage.is_empty() {
                                 continue;
                             }
 
@@ -203,7 +174,7 @@ fn parse_jsonl_file(
                                 .unwrap_or_else(|| encoded_project_name.to_string());
 
                             entries.push(UsageEntry {
-                                timestamp: entry.timestamp,
+                                timestamp,
                                 model: message
                                     .model
                                     .clone()
@@ -712,3 +683,34 @@ pub fn get_session_stats(
 
     Ok(by_session)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_files_yield_one_entry_per_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session").join("log.jsonl");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let turn = r#"{"type":"assistant","timestamp":"2025-01-01T00:00:00Z","cwd":"/work/app","requestId":"r1","costUSD":0.5,"message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"tool_use","input":{}}],"usage":{"input_tokens":10,"output_tokens":5}}}"#;
+        let content = [
+            r#"{"type":"summary","summary":"x"}"#,
+            turn,
+            turn,
+            r#"{"type":"assistant","timestamp":"2025-01-01T00:00:01Z","message":{"content":[],"usage":{"input_tokens":0}}}"#,
+            r#"{"type":"result","subtype":"success","total_cost_usd":9.0,"usage":{"input_tokens":100}}"#,
+        ]
+        .join("\n");
+        fs::write(&path, content).unwrap();
+
+        let entries = parse_jsonl_file(&path, "-work-app", &mut HashSet::new());
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.input_tokens, 10);
+        assert_eq!(entry.output_tokens, 5);
+        assert_eq!(entry.cost, 0.5);
+        assert_eq!(entry.session_id, "session");
+        assert_eq!(entry.project_path, "/work/app");
+    }
+}
@@ -4,6 +4,7 @@
 pub mod claude_binary;
 pub mod commands;
 pub mod process;
+pub mod stream_json;
 pub mod web_server;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
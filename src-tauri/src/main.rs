@@ -7,15 +7,21 @@ mod claude_binary;
 mod commands;
 mod portable_deps;
 mod process;
+mod stream_json;
 use commands::agents::{
-    cleanup_finished_processes, create_agent, delete_agent, execute_agent, export_agent,
-    export_agent_to_file, fetch_github_agent_content, fetch_github_agents, get_agent,
-    get_agent_run, get_agent_run_with_real_time_metrics, get_claude_binary_path,
-    get_live_session_output, get_session_output, get_session_status, import_agent,
-    import_agent_from_file, import_agent_from_github, init_database, kill_agent_session,
-    list_agent_runs, list_agent_runs_with_metrics, list_agents, list_claude_installations,
-    list_running_sessions, load_agent_session_history, set_claude_binary_path,
-    stream_session_output, update_agent, AgentDb,
+    cancel_agent_pipeline_run, cancel_queued_agent_run, cherry_pick_agent_worktree,
+    cleanup_finished_processes, create_agent, create_agent_schedule, delete_agent,
+    delete_agent_pipeline, delete_agent_schedule, discard_agent_worktree, execute_agent,
+    execute_agent_pipeline, export_agent, export_agent_to_file, fetch_github_agent_content,
+    fetch_github_agents, get_agent, get_agent_pipeline, get_agent_pipeline_run_with_metrics,
+    get_agent_queue_config, get_agent_run, get_agent_run_with_real_time_metrics,
+    get_agent_worktree_diff, get_claude_binary_path, get_live_session_output, get_session_output,
+    get_session_status, import_agent, import_agent_from_file, import_agent_from_github,
+    init_database, kill_agent_session, list_agent_pipeline_runs, list_agent_pipelines,
+    list_agent_queue, list_agent_runs, list_agent_runs_with_metrics, list_agent_schedules,
+    list_agents, list_claude_installations, list_running_sessions, load_agent_session_history,
+    merge_agent_worktree, save_agent_pipeline, set_agent_queue_config, set_agent_schedule_enabled,
+    set_claude_binary_path, stream_session_output, update_agent, AgentDb, AgentQueueState,
 };
 use commands::claude::{
     cancel_claude_execution, check_anyon_installed, check_claude_version, check_file_exists,
@@ -23,10 +29,10 @@ use commands::claude::{
     find_claude_md_files, get_claude_session_output, get_claude_settings, get_file_metadata,
     get_home_directory, get_project_sessions, get_system_prompt, git_add_all, git_commit,
     git_current_branch, git_push, git_set_remote, git_status, init_git_repo, install_anyon_templates,
-    list_anyon_docs, list_directory_contents, list_projects, list_running_claude_sessions,
-    load_session_history, open_new_session, read_claude_md_file, read_file_content,
-    resume_claude_code, save_claude_md_file, save_claude_settings, save_system_prompt,
-    search_files, ClaudeProcessState,
+    interrupt_session, list_anyon_docs, list_directory_contents, list_projects,
+    list_running_claude_sessions, load_session_history, open_new_session, read_claude_md_file,
+    read_file_content, resume_claude_code, save_claude_md_file, save_claude_settings,
+    save_system_prompt, search_files, send_session_message, ClaudeProcessState,
 };
 use commands::claude_auth::{
     claude_auth_check, claude_auth_delete_api_key, claude_auth_disable_anyon_api,
@@ -140,6 +146,18 @@ fn setup_database(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
     // Initialize dev_workflow table
     commands::dev_workflow::init_dev_workflow_db(&conn)?;
 
+    // Initialize dev server launch settings table
+    commands::dev_launcher::init_dev_launcher_db(&conn)?;
+
+    // Initialize dev server port registry table
+    commands::port_registry::init_port_registry_db(&conn)?;
+
+    // Initialize "always allow" tool permission rules table
+    commands::permission_prompt::init_permission_rules_db(&conn)?;
+
+    // Initialize the record of runs stopped for going over budget
+    commands::budget::init_budget_db(&conn)?;
+
     app.manage(AgentDb(Mutex::new(conn)));
     Ok(())
 }
@@ -151,6 +169,13 @@ fn setup_process_registries(app: &mut tauri::App) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
+/// Setup the agent run queue and start its dispatcher
+fn setup_agent_queue(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    app.manage(AgentQueueState::default());
+    commands::agents::queue::start_dispatcher(app.handle().clone());
+    Ok(())
+}
+
 /// Setup auth server
 fn setup_auth_server() -> Result<(), Box<dyn std::error::Error>> {
     let node_env = std::env::var("NODE_ENV").unwrap_or_else(|_| "development".to_string());
@@ -238,6 +263,7 @@ fn setup_window_effects(app: &mut tauri::App) -> Result<(), Box<dyn std::error::
 fn setup_application(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     setup_database(app)?;
     setup_process_registries(app)?;
+    setup_agent_queue(app)?;
     setup_auth_server()?;
     setup_window_effects(app)?;
     Ok(())
@@ -260,8 +286,21 @@ macro_rules! create_handlers {
             commands::claude::execution::continue_claude_code,
             commands::claude::execution::resume_claude_code,
             commands::claude::execution::cancel_claude_execution,
+            commands::claude::execution::send_session_message,
+            commands::claude::execution::interrupt_session,
+            commands::claude::execution::end_session_input,
             commands::claude::execution::list_running_claude_sessions,
             commands::claude::execution::get_claude_session_output,
+            // Interactive tool permissions
+            commands::permission_prompt::respond_permission_request,
+            commands::permission_prompt::list_permission_requests,
+            commands::permission_prompt::list_permission_rules,
+            commands::permission_prompt::delete_permission_rule,
+            commands::permission_prompt::get_interactive_permissions,
+            commands::permission_prompt::set_interactive_permissions,
+            commands::budget::get_agent_budget,
+            commands::budget::set_agent_budget,
+            commands::budget::list_budget_events,
             // Claude & Project Management - Filesystem
             commands::claude::filesystem::list_directory_contents,
             commands::claude::filesystem::search_files,
@@ -318,6 +357,29 @@ macro_rules! create_handlers {
             fetch_github_agents,
             fetch_github_agent_content,
             import_agent_from_github,
+            // Agent Queue & Schedules
+            list_agent_queue,
+            cancel_queued_agent_run,
+            get_agent_queue_config,
+            set_agent_queue_config,
+            list_agent_schedules,
+            create_agent_schedule,
+            set_agent_schedule_enabled,
+            delete_agent_schedule,
+            // Agent Worktrees
+            get_agent_worktree_diff,
+            merge_agent_worktree,
+            cherry_pick_agent_worktree,
+            discard_agent_worktree,
+            // Agent Pipelines
+            list_agent_pipelines,
+            get_agent_pipeline,
+            save_agent_pipeline,
+            delete_agent_pipeline,
+            execute_agent_pipeline,
+            cancel_agent_pipeline_run,
+            list_agent_pipeline_runs,
+            get_agent_pipeline_run_with_metrics,
             // Usage & Analytics
             get_usage_stats,
             get_usage_by_date_range,
@@ -352,15 +414,33 @@ macro_rules! create_handlers {
             // Dev Workflow
             commands::dev_workflow::start_dev_workflow,
             commands::dev_workflow::stop_dev_workflow,
+            commands::dev_workflow::pause_dev_workflow,
+            commands::dev_workflow::resume_dev_workflow,
             commands::dev_workflow::get_dev_workflow_status,
+            commands::dev_workflow::get_dev_workflow_definition,
+            commands::dev_workflow::get_dev_workflow_steps,
             // Preview (Port Scanning)
             scan_ports,
             check_port_alive,
             // Dev Server with HTML injection proxy
             commands::dev_server::start_dev_server,
             commands::dev_server::stop_dev_server,
+            commands::dev_server::start_build_preview,
             commands::dev_server::get_dev_server_info,
+            commands::dev_server::get_preview_console_logs,
+            commands::dev_server::clear_preview_console_logs,
+            commands::dev_server_logs::get_dev_server_logs,
+            commands::element_source::resolve_element_source,
+            commands::port_registry::list_port_allocations,
+            commands::port_registry::release_project_ports,
+            commands::preview_network::get_preview_network_log,
+            commands::preview_network::clear_preview_network_log,
+            commands::preview_network::export_preview_network_har,
             commands::dev_server::detect_package_manager,
+            commands::dev_launcher::detect_dev_framework,
+            commands::dev_launcher::get_dev_server_settings,
+            commands::dev_launcher::save_dev_server_settings,
+            commands::dev_launcher::delete_dev_server_settings,
             commands::dev_server::connect_to_existing_server,
             commands::dev_server::verify_server_connection,
             commands::dev_server::wait_for_server_ready,
@@ -386,6 +466,10 @@ macro_rules! create_handlers {
             commands::git::get_git_diff_summary,
             commands::git::get_git_log,
             commands::git::get_git_changes_count,
+            // Checkpoints
+            commands::checkpoint::list_checkpoints,
+            commands::checkpoint::restore_checkpoint,
+            commands::checkpoint::delete_checkpoints,
             // Environment Check
             commands::environment::check_environment_status,
             commands::environment::open_terminal,
@@ -137,16 +137,17 @@ impl ProcessRegistry {
     }
 
     /// Register a new Claude session (without child process - handled separately)
+    ///
+    /// The `run_id` must come from `generate_id` so it matches the key used by `ClaudeProcessState`.
     pub fn register_claude_session(
         &self,
+        run_id: i64,
         session_id: String,
         pid: u32,
         project_path: String,
         task: String,
         model: String,
     ) -> Result<i64, String> {
-        let run_id = self.generate_id()?;
-
         let process_info = ProcessInfo {
             run_id,
             process_type: ProcessType::ClaudeSession { session_id },
@@ -377,6 +378,29 @@ impl ProcessRegistry {
         Ok(true)
     }
 
+    /// Kill a run through its own `Child` if it's still running; returns whether it was
+    ///
+    /// Unlike `kill_process` this never falls back to the bare PID, which may belong to
+    /// another process once the run has been reaped.
+    pub fn kill_if_running(&self, run_id: i64) -> Result<bool, String> {
+        let child_arc = {
+            let processes = self.processes.lock().map_err(|e| e.to_string())?;
+            match processes.get(&run_id) {
+                Some(handle) => handle.child.clone(),
+                None => return Ok(false),
+            }
+        };
+        let mut child_guard = child_arc.lock().map_err(|e| e.to_string())?;
+        let Some(child) = child_guard.as_mut() else {
+            return Ok(false);
+        };
+        if !matches!(child.try_wait(), Ok(None)) {
+            return Ok(false);
+        }
+        child.start_kill().map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
     /// Kill a process by PID using system commands (fallback method)
     pub fn kill_process_by_pid(&self, run_id: i64, pid: u32) -> Result<bool, String> {
         use log::{error, info, warn};
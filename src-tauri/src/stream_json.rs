@@ -0,0 +1,332 @@
+//! Typed model of Claude Code's stream-json output
+//!
+//! The same message shapes appear in live `--output-format stream-json` output and in the
+//! session JSONL files under `~/.claude/projects`. Message types and content blocks this
+//! module does not know about parse as `Unknown` instead of failing the whole line.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// One line of stream-json output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    System(SystemMessage),
+    Assistant(ConversationMessage),
+    User(ConversationMessage),
+    StreamEvent(StreamEvent),
+    Result(ResultMessage),
+    #[serde(other)]
+    Unknown,
+}
+
+/// `system` messages; `init` carries the session ID of a new run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMessage {
+    #[serde(default)]
+    pub subtype: String,
+    #[serde(default, alias = "sessionId")]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+}
+
+/// `assistant` and `user` turns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub message: ApiMessage,
+    #[serde(default, alias = "sessionId")]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub parent_tool_use_id: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Only present in session files
+    #[serde(default, rename = "requestId")]
+    pub request_id: Option<String>,
+    /// Only present in session files written by older CLI versions
+    #[serde(default, rename = "costUSD")]
+    pub cost_usd: Option<f64>,
+}
+
+/// The API message wrapped by a conversation turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiMessage {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub content: MessageContent,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// Message content is plain text for typed prompts and a list of blocks otherwise
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Blocks(Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+    },
+    ToolUse {
+        #[serde(default)]
+        id: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        input: JsonValue,
+    },
+    ToolResult {
+        tool_use_id: String,
+        /// A string or a list of content blocks, depending on the tool
+        #[serde(default)]
+        content: JsonValue,
+        #[serde(default)]
+        is_error: bool,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// Partial output, only sent with `--include-partial-messages`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEvent {
+    pub event: JsonValue,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub parent_tool_use_id: Option<String>,
+}
+
+/// The final message of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultMessage {
+    #[serde(default)]
+    pub subtype: String,
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub is_error: bool,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub total_cost_usd: Option<f64>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub num_turns: Option<u32>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: Option<u64>,
+    #[serde(default)]
+    pub output_tokens: Option<u64>,
+    #[serde(default)]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default)]
+    pub cache_read_input_tokens: Option<u64>,
+}
+
+impl Usage {
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens.unwrap_or(0) == 0
+            && self.output_tokens.unwrap_or(0) == 0
+            && self.cache_creation_input_tokens.unwrap_or(0) == 0
+            && self.cache_read_input_tokens.unwrap_or(0) == 0
+    }
+}
+
+/// Parses one line of output. Returns `None` for blank lines and lines that are not
+/// JSON objects; JSON whose shape does not match a known message becomes `Unknown`.
+pub fn parse_line(line: &str) -> Option<StreamMessage> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let value: JsonValue = serde_json::from_str(line).ok()?;
+    if !value.is_object() {
+        return None;
+    }
+    Some(serde_json::from_value(value).unwrap_or(StreamMessage::Unknown))
+}
+
+impl StreamMessage {
+    /// The session ID of a `system`/`init` message
+    pub fn init_session_id(&self) -> Option<&str> {
+        match self {
+            StreamMessage::System(system) if system.subtype == "init" => {
+                system.session_id.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn timestamp(&self) -> Option<&str> {
+        match self {
+            StreamMessage::System(m) => m.timestamp.as_deref(),
+            StreamMessage::Assistant(m) | StreamMessage::User(m) => m.timestamp.as_deref(),
+            StreamMessage::Result(m) => m.timestamp.as_deref(),
+            StreamMessage::StreamEvent(_) | StreamMessage::Unknown => None,
+        }
+    }
+
+    pub fn cwd(&self) -> Option<&str> {
+        match self {
+            StreamMessage::System(m) => m.cwd.as_deref(),
+            StreamMessage::Assistant(m) | StreamMessage::User(m) => m.cwd.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Token usage of an assistant turn, or the run total on a result
+    pub fn usage(&self) -> Option<&Usage> {
+        match self {
+            StreamMessage::Assistant(m) | StreamMessage::User(m) => m.message.usage.as_ref(),
+            StreamMessage::Result(m) => m.usage.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The run total on a result, or the per-turn cost recorded by older session files
+    pub fn cost_usd(&self) -> Option<f64> {
+        match self {
+            StreamMessage::Assistant(m) | StreamMessage::User(m) => m.cost_usd,
+            StreamMessage::Result(m) => m.total_cost_usd,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(message: &StreamMessage) -> &[ContentBlock] {
+        match message {
+            StreamMessage::Assistant(m) | StreamMessage::User(m) => match &m.message.content {
+                MessageContent::Blocks(blocks) => blocks,
+                other => panic!("unexpected content {:?}", other),
+            },
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_the_session_id_of_init() {
+        let init = parse_line(
+            r#"{"type":"system","subtype":"init","session_id":"abc","tools":["Bash"],"mcp_servers":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(init.init_session_id(), Some("abc"));
+
+        let other = parse_line(r#"{"type":"system","subtype":"compact","session_id":"abc"}"#);
+        assert_eq!(other.unwrap().init_session_id(), None);
+    }
+
+    #[test]
+    fn parses_assistant_blocks_and_keeps_unknown_ones() {
+        let assistant = parse_line(
+            r#"{"type":"assistant","session_id":"abc","message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"text","text":"hi"},{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"ls"}},{"type":"server_tool_use"}],"usage":{"input_tokens":3,"output_tokens":5}}}"#,
+        )
+        .unwrap();
+        let blocks = blocks(&assistant);
+        assert!(matches!(&blocks[0], ContentBlock::Text { text } if text == "hi"));
+        assert!(matches!(&blocks[1], ContentBlock::ToolUse { name, .. } if name == "Bash"));
+        assert!(matches!(blocks[2], ContentBlock::Unknown));
+        assert_eq!(assistant.usage().and_then(|u| u.output_tokens), Some(5));
+    }
+
+    #[test]
+    fn blocks_missing_fields_still_parse() {
+        // One odd block must not turn the whole turn, and its usage, into `Unknown`
+        let assistant = parse_line(
+            r#"{"type":"assistant","message":{"content":[{"type":"text"},{"type":"tool_use","input":{}}],"usage":{"output_tokens":7}}}"#,
+        )
+        .unwrap();
+        let blocks = blocks(&assistant);
+        assert!(matches!(&blocks[0], ContentBlock::Text { text } if text.is_empty()));
+        assert!(
+            matches!(&blocks[1], ContentBlock::ToolUse { id, name, .. } if id.is_empty() && name.is_empty())
+        );
+        assert_eq!(assistant.usage().and_then(|u| u.output_tokens), Some(7));
+    }
+
+    #[test]
+    fn reads_session_file_fields() {
+        let user = parse_line(
+            r#"{"type":"user","sessionId":"abc","message":{"role":"user","content":"hello"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &user,
+            StreamMessage::User(ConversationMessage { session_id: Some(id), message: ApiMessage { content: MessageContent::Text(_), .. }, .. }) if id == "abc"
+        ));
+
+        let turn = parse_line(
+            r#"{"type":"assistant","requestId":"r1","costUSD":0.5,"cwd":"/work","message":{"id":"m1","content":[]}}"#,
+        )
+        .unwrap();
+        assert_eq!(turn.cost_usd(), Some(0.5));
+        assert_eq!(turn.cwd(), Some("/work"));
+    }
+
+    #[test]
+    fn reads_the_run_totals_on_result() {
+        let result = parse_line(
+            r#"{"type":"result","subtype":"success","is_error":false,"result":"done","total_cost_usd":0.25,"usage":{"input_tokens":10}}"#,
+        )
+        .unwrap();
+        assert_eq!(result.cost_usd(), Some(0.25));
+        assert_eq!(result.usage().and_then(|u| u.input_tokens), Some(10));
+    }
+
+    #[test]
+    fn unknown_and_malformed_lines() {
+        assert!(matches!(
+            parse_line(r#"{"type":"summary","summary":"x"}"#),
+            Some(StreamMessage::Unknown)
+        ));
+        assert!(matches!(
+            parse_line(r#"{"type":"assistant","message":42}"#),
+            Some(StreamMessage::Unknown)
+        ));
+        assert!(parse_line("[1, 2]").is_none());
+        assert!(parse_line("not json").is_none());
+        assert!(parse_line("").is_none());
+    }
+}
@@ -3,6 +3,7 @@ use clap::Parser;
 mod claude_binary;
 mod commands;
 mod process;
+mod stream_json;
 mod web_server;
 
 #[derive(Parser)]
@@ -19,6 +19,9 @@ use tower_http::services::ServeDir;
 use which;
 
 use crate::commands;
+use crate::commands::budget::{self, BudgetEvent, BudgetTracker};
+use crate::commands::claude::{ClaudeRunOptions, SessionTarget};
+use crate::stream_json::parse_line;
 
 // Find Claude binary for web mode - use bundled binary first
 fn find_claude_binary_web() -> Result<String, String> {
@@ -73,6 +76,8 @@ pub struct ClaudeExecutionRequest {
     pub model: Option<String>,
     pub session_id: Option<String>,
     pub command_type: String, // "execute", "continue", or "resume"
+    #[serde(default)]
+    pub options: Option<ClaudeRunOptions>,
 }
 
 #[derive(Deserialize)]
@@ -567,6 +572,7 @@ async fn claude_websocket_handler(socket: WebSocket, state: AppState) {
                                         request.project_path,
                                         request.prompt,
                                         request.model.unwrap_or_default(),
+                                        request.options.unwrap_or_default(),
                                         session_id_clone.clone(),
                                         state_clone.clone(),
                                     )
@@ -578,6 +584,7 @@ async fn claude_websocket_handler(socket: WebSocket, state: AppState) {
                                         request.project_path,
                                         request.prompt,
                                         request.model.unwrap_or_default(),
+                                        request.options.unwrap_or_default(),
                                         session_id_clone.clone(),
                                         state_clone.clone(),
                                     )
@@ -590,6 +597,7 @@ async fn claude_websocket_handler(socket: WebSocket, state: AppState) {
                                         request.session_id.unwrap_or_default(),
                                         request.prompt,
                                         request.model.unwrap_or_default(),
+                                        request.options.unwrap_or_default(),
                                         session_id_clone.clone(),
                                         state_clone.clone(),
                                     )
@@ -682,6 +690,7 @@ async fn execute_claude_command(
     project_path: String,
     prompt: String,
     model: String,
+    options: ClaudeRunOptions,
     session_id: String,
     state: AppState,
 ) -> Result<(), String> {
@@ -691,20 +700,14 @@ async fn execute_claude_command(
     println!("[TRACE]   model: {}", model);
     println!("[TRACE]   session_id: {}", session_id);
 
-    let args = vec![
-        "-p".to_string(),
-        prompt,
-        "--model".to_string(),
-        model,
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--verbose".to_string(),
-        "--dangerously-skip-permissions".to_string(),
-    ];
+    let options = options
+        .print_prompt(prompt)
+        .model(model)
+        .skip_checks_unless_restricted();
 
     execute_claude_with_streaming(
         project_path,
-        args,
+        options,
         session_id,
         state,
         "Starting Claude execution...",
@@ -716,24 +719,19 @@ async fn continue_claude_command(
     project_path: String,
     prompt: String,
     model: String,
+    options: ClaudeRunOptions,
     session_id: String,
     state: AppState,
 ) -> Result<(), String> {
-    let args = vec![
-        "-c".to_string(), // Continue flag
-        "-p".to_string(),
-        prompt,
-        "--model".to_string(),
-        model,
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--verbose".to_string(),
-        "--dangerously-skip-permissions".to_string(),
-    ];
+    let options = options
+        .session(SessionTarget::Continue)
+        .print_prompt(prompt)
+        .model(model)
+        .skip_checks_unless_restricted();
 
     execute_claude_with_streaming(
         project_path,
-        args,
+        options,
         session_id,
         state,
         "Continuing Claude session...",
@@ -746,28 +744,22 @@ async fn resume_claude_command(
     claude_session_id: String,
     prompt: String,
     model: String,
+    options: ClaudeRunOptions,
     session_id: String,
     state: AppState,
 ) -> Result<(), String> {
     println!("[resume_claude_command] Starting with project_path: {}, claude_session_id: {}, prompt: {}, model: {}",
              project_path, claude_session_id, prompt, model);
 
-    let args = vec![
-        "--resume".to_string(),
-        claude_session_id,
-        "-p".to_string(),
-        prompt,
-        "--model".to_string(),
-        model,
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--verbose".to_string(),
-        "--dangerously-skip-permissions".to_string(),
-    ];
+    let options = options
+        .session(SessionTarget::Resume(claude_session_id))
+        .print_prompt(prompt)
+        .model(model)
+        .skip_checks_unless_restricted();
 
     execute_claude_with_streaming(
         project_path,
-        args,
+        options,
         session_id,
         state,
         "Resuming Claude session...",
@@ -799,14 +791,17 @@ async fn send_to_session(state: &AppState, session_id: &str, message: String) {
 }
 
 /// Common utility function for executing Claude commands with streaming output
-/// Handles the full lifecycle: spawn process, stream output, wait for completion
+/// Handles the full lifecycle: spawn process, stream output, wait for completion.
+/// A run with a budget is interrupted once it goes over it, after a `budget-exceeded`
+/// message; there is no database in web mode, so the event isn't recorded.
 async fn execute_claude_with_streaming(
     project_path: String,
-    args: Vec<String>,
+    options: ClaudeRunOptions,
     session_id: String,
     state: AppState,
     start_message: &str,
 ) -> Result<(), String> {
+    let args = options.to_args();
     use tokio::io::{AsyncBufReadExt, BufReader};
     use tokio::process::Command;
 
@@ -841,6 +836,7 @@ async fn execute_claude_with_streaming(
     println!("[TRACE] Creating Claude command...");
     let mut cmd = Command::new(&claude_path);
     cmd.args(&args);
+    cmd.envs(&options.env);
     cmd.current_dir(&project_path);
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
@@ -870,18 +866,69 @@ async fn execute_claude_with_streaming(
     // Stream output line by line
     let mut lines = stdout_reader.lines();
     let mut line_count = 0;
-    while let Ok(Some(line)) = lines.next_line().await {
+    let mut budget_tracker = options.budget.clone().map(BudgetTracker::new);
+    let mut budget_exceeded = None;
+    loop {
+        // Quiet runs can go over their time budget without sending anything
+        let next_line = match budget_tracker.as_ref().and_then(|t| t.time_left()) {
+            Some(time_left) => match tokio::time::timeout(time_left, lines.next_line()).await {
+                Ok(next_line) => next_line,
+                Err(_) => {
+                    budget_exceeded = budget_tracker.as_mut().and_then(|t| t.check_duration());
+                    break;
+                }
+            },
+            None => lines.next_line().await,
+        };
+        let Ok(Some(line)) = next_line else {
+            break;
+        };
         line_count += 1;
         println!("[TRACE] Claude output line {}: {}", line_count, line);
 
-        // Send each line to WebSocket
+        // Send each line to WebSocket, along with its parsed form
+        let parsed = parse_line(&line);
         let message = json!({
             "type": "output",
-            "content": line
+            "content": line,
+            "message": parsed
         })
         .to_string();
         println!("[TRACE] Sending output message to session: {}", message);
         send_to_session(&state, &session_id, message).await;
+
+        if let (Some(tracker), Some(parsed)) = (budget_tracker.as_mut(), &parsed) {
+            budget_exceeded = tracker.observe(parsed);
+            if budget_exceeded.is_some() {
+                break;
+            }
+        }
+    }
+
+    if let Some(exceeded) = budget_exceeded {
+        println!("[TRACE] Claude run went over budget: {}", exceeded.reason);
+        let event = BudgetEvent::new(&project_path, None, None, &exceeded);
+        send_to_session(
+            &state,
+            &session_id,
+            json!({
+                "type": "budget-exceeded",
+                "event": event
+            })
+            .to_string(),
+        )
+        .await;
+        if let Some(pid) = child.id() {
+            budget::send_interrupt(pid);
+        }
+        if tokio::time::timeout(budget::INTERRUPT_GRACE_PERIOD, child.wait())
+            .await
+            .is_err()
+        {
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+        }
+        return Err(exceeded.reason);
     }
 
     println!(
@@ -122,7 +122,7 @@ export const EnhancedPreviewPanel: React.FC<EnhancedPreviewPanelProps> = ({
   } = usePreviewStore();
 
   // 메시지 훅
-  usePreviewMessages();
+  usePreviewMessages(projectPath);
   const { isSelectorActive, isComponentSelectorInitialized } = useComponentSelectorShortcut();
   const { startDevServer, stopDevServer, connectToExistingServer } = useDevServer(projectPath, projectId);
 
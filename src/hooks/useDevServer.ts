@@ -12,10 +12,11 @@ const isTauri = typeof window !== 'undefined' && (
 
 interface DevServerOutput {
   project_path: string;
-  output_type: 'stdout' | 'stderr' | 'info' | 'port-detected' | 'error';
+  output_type: 'stdout' | 'stderr' | 'info' | 'port-detected' | 'ready' | 'exited' | 'crashed' | 'error';
   message: string;
   port?: number;
   proxy_url?: string;
+  seq?: number; // Log entry seq for stdout/stderr lines
 }
 
 interface DevServerInfo {
@@ -25,6 +26,11 @@ interface DevServerInfo {
   original_url?: string;
   proxy_port?: number;
   proxy_url?: string;
+  status: 'building' | 'running' | 'restarting' | 'exited' | 'crashed' | 'failed';
+  mode: 'dev' | 'build';
+  exit_code?: number;
+  restart_count: number;
+  log_tail: string[];
 }
 
 /**
@@ -176,9 +182,9 @@ export function useDevServer(
         }
       }
 
-      // 2. 감지된 포트가 없거나 죽었으면, 일반 포트 스캔
+      // 2. 감지된 포트가 없거나 죽었으면, 이 프로젝트가 연 포트 스캔
       setConnectionState('starting');
-      const ports = await invoke<Array<{ port: number; alive: boolean; url: string }>>('scan_ports');
+      const ports = await invoke<Array<{ port: number; alive: boolean; url: string }>>('scan_ports', { projectPath });
       const runningServer = ports.find(p => p.alive && p.port >= 3000 && p.port <= 9000);
 
       if (runningServer) {
@@ -233,6 +239,42 @@ export function useDevServer(
     }
   }, [projectPath, projectId, connectToExistingServer, setDevServerRunning, setPackageManager, setIsLoading, addAppOutput, setConnectionState, setConnectionError]);
 
+  // 프로덕션 빌드 후 결과물 미리보기 (dev server를 대체)
+  const startBuildPreview = useCallback(async () => {
+    if (!projectPath || !isTauri) return;
+
+    try {
+      setIsLoading(true);
+      setConnectionState('starting');
+      setConnectionError(null);
+      await invoke('start_build_preview', {
+        projectPath,
+        projectId: projectId || null,
+      });
+
+      setDevServerRunning(true);
+      addAppOutput({
+        type: 'info',
+        message: `[anyon] Building production preview...`,
+        timestamp: Date.now(),
+        projectPath,
+      });
+      // 빌드가 끝나면 이벤트 리스너가 port-detected/ready로 연결을 이어받음
+    } catch (err) {
+      console.error('Failed to start build preview:', err);
+      setConnectionState('error');
+      setConnectionError(err instanceof Error ? err.message : String(err));
+      addAppOutput({
+        type: 'stderr',
+        message: `[anyon] Failed to start build preview: ${err}`,
+        timestamp: Date.now(),
+        projectPath,
+      });
+    } finally {
+      setIsLoading(false);
+    }
+  }, [projectPath, projectId, setDevServerRunning, setIsLoading, addAppOutput, setConnectionState, setConnectionError]);
+
   // Dev server 중지
   const stopDevServer = useCallback(async () => {
     if (!projectPath || !isTauri) return;
@@ -279,8 +321,8 @@ export function useDevServer(
         // 콘솔에 출력 추가
         addAppOutput({
           type: data.output_type === 'stderr' ? 'stderr' :
-                data.output_type === 'error' ? 'stderr' :
-                data.output_type === 'port-detected' ? 'info' : 'stdout',
+                data.output_type === 'error' || data.output_type === 'crashed' ? 'stderr' :
+                data.output_type === 'port-detected' || data.output_type === 'exited' ? 'info' : 'stdout',
           message: data.message,
           timestamp: Date.now(),
           projectPath: data.project_path,
@@ -370,7 +412,7 @@ export function useDevServer(
 
     const checkExistingServer = async () => {
       const info = await getDevServerInfo();
-      if (info && info.proxy_url) {
+      if (info && info.proxy_url && (info.status === 'running' || info.status === 'restarting')) {
         setDevServerRunning(true);
         setDevServerPort(info.detected_port || null);
         setDevServerProxyUrl(info.proxy_url);
@@ -383,6 +425,7 @@ export function useDevServer(
 
   return {
     startDevServer,
+    startBuildPreview,
     stopDevServer,
     getDevServerInfo,
     connectToExistingServer,
@@ -1,12 +1,14 @@
 import { useEffect, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { usePreviewStore } from '@/stores/previewStore';
 import type { IframeMessage, ComponentSelection, SelectedElement } from '@/types/preview';
 
 /**
  * 프리뷰 iframe 메시지 처리 훅
  * iframe에서 보내는 에러, 네비게이션, 컴포넌트 선택 메시지 처리
+ * projectPath가 있으면 선택된 요소의 소스 위치를 백엔드에서 확인해 채움
  */
-export function usePreviewMessages() {
+export function usePreviewMessages(projectPath?: string) {
   const {
     iframeRef,
     setPreviewError,
@@ -44,12 +46,30 @@ export function usePreviewMessages() {
           const selection: ComponentSelection = {
             id: extendedComponent.id || extendedComponent.selector,
             name: extendedComponent.name || '<unknown>',
-            // CSS selector 방식에서는 소스 위치를 알 수 없으므로 빈 값
-            relativePath: extendedComponent.relativePath || '',
-            lineNumber: extendedComponent.lineNumber || 0,
-            columnNumber: extendedComponent.columnNumber || 0,
+            // 소스 위치는 프로젝트 안에서 확인된 경로만 사용 (페이지가 보낸 경로는 그대로 쓰지 않음)
+            relativePath: '',
+            lineNumber: 0,
+            columnNumber: 0,
           };
-          addSelectedComponent(selection);
+          if (projectPath) {
+            invoke<Array<{ file: string; line: number; column: number | null }>>(
+              'resolve_element_source',
+              { projectPath, element: extendedComponent, limit: 1 }
+            )
+              .then(([best]) => {
+                addSelectedComponent(
+                  best
+                    ? { ...selection, relativePath: best.file, lineNumber: best.line, columnNumber: best.column || 0 }
+                    : selection
+                );
+              })
+              .catch((err) => {
+                console.warn('[Preview] Failed to resolve element source:', err);
+                addSelectedComponent(selection);
+              });
+          } else {
+            addSelectedComponent(selection);
+          }
 
           // SelectedElement로도 저장 (HTML 정보 포함)
           if (extendedComponent.selector || extendedComponent.tag) {
@@ -193,6 +213,7 @@ export function usePreviewMessages() {
         break;
     }
   }, [
+    projectPath,
     iframeRef,
     setPreviewError,
     addSelectedComponent,
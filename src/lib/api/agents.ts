@@ -1,5 +1,5 @@
 import { apiCall } from '../apiAdapter';
-import type { Agent, AgentExport, GitHubAgentFile, AgentRun, AgentRunWithMetrics } from './types';
+import type { Agent, AgentExport, GitHubAgentFile, AgentRun, AgentRunWithMetrics, RunBudget } from './types';
 
 /**
  * Agent API methods for managing CC agents
@@ -204,11 +204,12 @@ export const agentsApi = {
    * @param projectPath - The project path to run the agent in
    * @param task - The task description
    * @param model - Optional model override
+   * @param budget - Optional budget override for this run
    * @returns Promise resolving to the run ID when execution starts
    */
-  async executeAgent(agentId: number, projectPath: string, task: string, model?: string): Promise<number> {
+  async executeAgent(agentId: number, projectPath: string, task: string, model?: string, budget?: RunBudget): Promise<number> {
     try {
-      return await apiCall<number>('execute_agent', { agentId, projectPath, task, model });
+      return await apiCall<number>('execute_agent', { agentId, projectPath, task, model, budget });
     } catch (error) {
       console.error("Failed to execute agent:", error);
       // Return a sentinel value to indicate error
@@ -384,4 +385,32 @@ export const agentsApi = {
       throw error;
     }
   },
+
+  /**
+   * Gets the budget new runs of an agent start with
+   * @param agentId - The agent ID
+   * @returns Promise resolving to the budget, or null when runs are unlimited
+   */
+  async getAgentBudget(agentId: number): Promise<RunBudget | null> {
+    try {
+      return await apiCall<RunBudget | null>('get_agent_budget', { agentId });
+    } catch (error) {
+      console.error("Failed to get agent budget:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets or clears the budget new runs of an agent start with
+   * @param agentId - The agent ID
+   * @param budget - The budget, or null to remove it
+   */
+  async setAgentBudget(agentId: number, budget: RunBudget | null): Promise<void> {
+    try {
+      return await apiCall<void>('set_agent_budget', { agentId, budget });
+    } catch (error) {
+      console.error("Failed to set agent budget:", error);
+      throw error;
+    }
+  },
 };
@@ -1,5 +1,5 @@
 import { apiCall } from '../apiAdapter';
-import type { DevSession } from './types';
+import type { BudgetEvent, ClaudeRunOptions, DevSession, PermissionRequest, PermissionRule, RunBudget } from './types';
 
 /**
  * Sessions API
@@ -30,25 +30,56 @@ export const sessionsApi = {
   /**
    * Executes a new interactive Claude Code session with streaming output
    * @param executionMode - Optional execution mode: "execute" (default, bypass permissions) or "plan" (allows questions)
+   * @param inputStreaming - Keep the run open for follow-up messages via sendSessionMessage
+   * @param options - Extra CLI options such as allowed tools, max turns or environment variables
    */
-  async executeClaudeCode(projectPath: string, prompt: string, model: string, executionMode?: string): Promise<void> {
-    return apiCall("execute_claude_code", { projectPath, prompt, model, executionMode });
+  async executeClaudeCode(projectPath: string, prompt: string, model: string, executionMode?: string, inputStreaming?: boolean, options?: ClaudeRunOptions): Promise<void> {
+    return apiCall("execute_claude_code", { projectPath, prompt, model, executionMode, inputStreaming, options });
   },
 
   /**
    * Continues an existing Claude Code conversation with streaming output
    * @param executionMode - Optional execution mode: "execute" (default, bypass permissions) or "plan" (allows questions)
+   * @param inputStreaming - Keep the run open for follow-up messages via sendSessionMessage
+   * @param options - Extra CLI options such as allowed tools, max turns or environment variables
    */
-  async continueClaudeCode(projectPath: string, prompt: string, model: string, executionMode?: string): Promise<void> {
-    return apiCall("continue_claude_code", { projectPath, prompt, model, executionMode });
+  async continueClaudeCode(projectPath: string, prompt: string, model: string, executionMode?: string, inputStreaming?: boolean, options?: ClaudeRunOptions): Promise<void> {
+    return apiCall("continue_claude_code", { projectPath, prompt, model, executionMode, inputStreaming, options });
   },
 
   /**
    * Resumes an existing Claude Code session by ID with streaming output
    * @param executionMode - Optional execution mode: "execute" (default, bypass permissions) or "plan" (allows questions)
+   * @param inputStreaming - Keep the run open for follow-up messages via sendSessionMessage
+   * @param options - Extra CLI options such as allowed tools, max turns or environment variables
    */
-  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string, executionMode?: string): Promise<void> {
-    return apiCall("resume_claude_code", { projectPath, sessionId, prompt, model, executionMode });
+  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string, executionMode?: string, inputStreaming?: boolean, options?: ClaudeRunOptions): Promise<void> {
+    return apiCall("resume_claude_code", { projectPath, sessionId, prompt, model, executionMode, inputStreaming, options });
+  },
+
+  /**
+   * Sends a follow-up message to a running input-streaming session
+   * @param sessionId - The running session to message
+   * @param text - The user message
+   */
+  async sendSessionMessage(sessionId: string, text: string): Promise<void> {
+    return apiCall("send_session_message", { sessionId, text });
+  },
+
+  /**
+   * Interrupts what a running session is currently doing
+   * @param sessionId - The running session to interrupt
+   */
+  async interruptSession(sessionId: string): Promise<void> {
+    return apiCall("interrupt_session", { sessionId });
+  },
+
+  /**
+   * Stops taking input for a running input-streaming session; it exits after its current messages
+   * @param sessionId - The running session to end
+   */
+  async endSessionInput(sessionId: string): Promise<void> {
+    return apiCall("end_session_input", { sessionId });
   },
 
   /**
@@ -78,9 +109,10 @@ export const sessionsApi = {
 
   /**
    * Start development workflow
+   * @param budget - Limits for all the workflow's runs together, replacing the workflow definition's own
    */
-  async startDevWorkflow(projectPath: string, model: string): Promise<void> {
-    return apiCall("start_dev_workflow", { projectPath, model });
+  async startDevWorkflow(projectPath: string, model: string, budget?: RunBudget): Promise<void> {
+    return apiCall("start_dev_workflow", { projectPath, model, budget });
   },
 
   /**
@@ -96,4 +128,57 @@ export const sessionsApi = {
   async getDevWorkflowStatus(projectPath: string): Promise<DevSession> {
     return apiCall("get_dev_workflow_status", { projectPath });
   },
+
+  /**
+   * Approves or denies a pending tool permission request
+   * @param alwaysAllow - Allow this tool in the request's project from now on
+   * @param message - Why the request was denied, passed on to Claude
+   */
+  async respondPermissionRequest(requestId: string, allow: boolean, alwaysAllow?: boolean, message?: string): Promise<void> {
+    return apiCall("respond_permission_request", { requestId, allow, alwaysAllow, message });
+  },
+
+  /**
+   * Lists permission requests still waiting for an answer
+   * @param sessionId - Only requests from this session
+   * @param runId - Only requests from this run, e.g. before its session ID is known
+   */
+  async listPermissionRequests(sessionId?: string, runId?: number): Promise<PermissionRequest[]> {
+    return apiCall("list_permission_requests", { sessionId, runId });
+  },
+
+  /**
+   * Lists "always allow" permission rules
+   */
+  async listPermissionRules(projectPath?: string): Promise<PermissionRule[]> {
+    return apiCall("list_permission_rules", { projectPath });
+  },
+
+  /**
+   * Deletes an "always allow" permission rule
+   */
+  async deletePermissionRule(id: number): Promise<void> {
+    return apiCall("delete_permission_rule", { id });
+  },
+
+  /**
+   * Whether runs ask before using tools instead of skipping permission checks
+   */
+  async getInteractivePermissions(): Promise<boolean> {
+    return apiCall("get_interactive_permissions");
+  },
+
+  /**
+   * Turns interactive tool permissions on or off for runs started afterwards
+   */
+  async setInteractivePermissions(enabled: boolean): Promise<void> {
+    return apiCall("set_interactive_permissions", { enabled });
+  },
+
+  /**
+   * Lists runs stopped for going over budget, newest first
+   */
+  async listBudgetEvents(projectPath?: string): Promise<BudgetEvent[]> {
+    return apiCall("list_budget_events", { projectPath });
+  },
 };
@@ -25,10 +25,141 @@ export interface DevSession {
   last_prompt: string;
   cycle_count: number;
   status: string;
+  workflow_name: string;
+  current_state: string;
+  model: string;
   created_at: string;
   updated_at: string;
 }
 
+/**
+ * A tool call waiting for the user's approval (interactive permissions)
+ */
+export interface PermissionRequest {
+  request_id: string;
+  project_path: string;
+  run_id?: number;
+  session_id?: string;
+  tool_name: string;
+  input: Record<string, any>;
+  tool_use_id?: string;
+  requested_at: string;
+}
+
+/**
+ * A tool a project always allows without asking
+ */
+export interface PermissionRule {
+  id: number;
+  project_path: string;
+  tool_name: string;
+  created_at: string;
+}
+
+/** Extra CLI options for a Claude Code run; every field is optional */
+export interface ClaudeRunOptions {
+  /** Directories outside the project the run may access */
+  add_dirs?: string[];
+  allowed_tools?: string[];
+  disallowed_tools?: string[];
+  max_turns?: number;
+  append_system_prompt?: string;
+  /** Used when the main model is overloaded */
+  fallback_model?: string;
+  mcp_config?: string;
+  /** Extra environment variables for the Claude process */
+  env?: Record<string, string>;
+  /** Limits the app enforces while the run is going */
+  budget?: RunBudget;
+}
+
+/** Limits for a single run; unset limits don't apply */
+export interface RunBudget {
+  max_cost_usd?: number;
+  /** Input plus output tokens over all turns */
+  max_tokens?: number;
+  max_duration_secs?: number;
+  /** Assistant turns, i.e. model requests */
+  max_turns?: number;
+}
+
+/**
+ * A run stopped for going over its budget, as sent on the `budget-exceeded` and
+ * `budget-exceeded:{sessionId}` events
+ */
+export interface BudgetEvent {
+  id?: number;
+  project_path: string;
+  session_id?: string;
+  agent_run_id?: number;
+  limit: 'cost' | 'tokens' | 'duration' | 'turns';
+  reason: string;
+  cost_usd: number;
+  total_tokens: number;
+  turns: number;
+  duration_ms: number;
+  created_at?: string;
+}
+
+/** Token usage of a turn, or the run total on a result */
+export interface StreamUsage {
+  input_tokens?: number;
+  output_tokens?: number;
+  cache_creation_input_tokens?: number;
+  cache_read_input_tokens?: number;
+}
+
+/** A content block of an assistant or user turn */
+export type StreamContentBlock =
+  | { type: 'text'; text: string }
+  | { type: 'thinking'; thinking: string }
+  | { type: 'tool_use'; id: string; name: string; input: any }
+  | { type: 'tool_result'; tool_use_id: string; content: any; is_error: boolean }
+  | { type: 'unknown' };
+
+/** A conversation turn carried by `assistant` and `user` messages */
+export interface StreamConversationMessage {
+  message: {
+    id?: string;
+    role?: string;
+    model?: string;
+    content: string | StreamContentBlock[];
+    usage?: StreamUsage;
+  };
+  session_id?: string;
+  parent_tool_use_id?: string;
+  timestamp?: string;
+}
+
+/**
+ * A parsed stream-json line, as sent on the `claude-message:{sessionId}` and
+ * `agent-message:{runId}` events
+ */
+export type StreamMessage =
+  | {
+      type: 'system';
+      subtype: string;
+      session_id?: string;
+      model?: string;
+      cwd?: string;
+      tools: string[];
+    }
+  | ({ type: 'assistant' } & StreamConversationMessage)
+  | ({ type: 'user' } & StreamConversationMessage)
+  | { type: 'stream_event'; event: any; session_id?: string; parent_tool_use_id?: string }
+  | {
+      type: 'result';
+      subtype: string;
+      result?: string;
+      is_error: boolean;
+      session_id?: string;
+      total_cost_usd?: number;
+      duration_ms?: number;
+      num_turns?: number;
+      usage?: StreamUsage;
+    }
+  | { type: 'unknown' };
+
 /**
  * Represents a project in the ~/.claude/projects directory
  */
@@ -77,6 +77,9 @@ export interface PortInfo {
   port: number;
   url: string;
   alive: boolean;
+  pid?: number | null;      // 포트를 연 프로세스
+  command?: string | null;  // 그 프로세스의 커맨드 라인
+  owner?: 'dev-server' | 'claude-run' | 'project' | null;
 }
 
 // 파싱된 라우트 정보